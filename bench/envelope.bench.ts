@@ -0,0 +1,51 @@
+/**
+ * 上游 envelope 序列化基准：每次发送都 JSON.stringify 整个 envelope vs EnvelopeSerializer 复用 request 序列化结果
+ * 运行：bun run bench:envelope [历史轮数] [每个请求的发送次数]
+ */
+
+import { EnvelopeSerializer } from "../src/services/antigravity/envelope"
+
+const turns = Number(process.argv[2] || 200)
+const sendsPerRequest = Number(process.argv[3] || 3)
+const iterations = 2000
+
+function buildEnvelope(): Record<string, unknown> {
+    const contents = Array.from({ length: turns }, (_, i) => ({
+        role: i % 2 === 0 ? "user" : "model",
+        parts: [{ text: `turn ${i}: ${"lorem ipsum dolor sit amet ".repeat(20)}` }],
+    }))
+    return {
+        model: "claude-sonnet-4-5",
+        userAgent: "antigravity",
+        requestType: "agent",
+        project: "bench-project",
+        requestId: "agent-bench",
+        request: { contents, generationConfig: { maxOutputTokens: 4096, temperature: 1 } },
+    }
+}
+
+function measure(label: string, run: () => number): void {
+    const started = performance.now()
+    let bytes = 0
+    for (let i = 0; i < iterations; i++) bytes += run()
+    const elapsed = performance.now() - started
+    console.log(`${label.padEnd(22)} ${(elapsed / iterations * 1000).toFixed(1).padStart(8)}µs/request  (${(bytes / iterations / 1024).toFixed(0)} KiB/request)`)
+}
+
+const envelope = buildEnvelope()
+if (new EnvelopeSerializer(envelope).serialize() !== JSON.stringify(envelope)) {
+    throw new Error("EnvelopeSerializer output differs from JSON.stringify")
+}
+
+console.log(`${turns} history turns, ${sendsPerRequest} sends per request, ${iterations} requests`)
+measure("JSON.stringify", () => {
+    let bytes = 0
+    for (let i = 0; i < sendsPerRequest; i++) bytes += JSON.stringify(envelope).length
+    return bytes
+})
+measure("EnvelopeSerializer", () => {
+    const serializer = new EnvelopeSerializer(envelope)
+    let bytes = 0
+    for (let i = 0; i < sendsPerRequest; i++) bytes += serializer.serialize().length
+    return bytes
+})
@@ -0,0 +1,61 @@
+/**
+ * 🆕 库入口：在其他 Bun / Hono 服务中嵌入代理
+ * - buildRouter(): 返回完整的 Hono 应用（含 ANTI_API_BASE_PATH 前缀），可直接 app.route() 挂载或作为 fetch 处理器
+ * - proxyOnce(): 不经过网络监听，直接处理一次模型请求，便于嵌入方与测试复用处理逻辑
+ * 配置仍通过环境变量 / ANTI_API_CONFIG 配置文件读取；路由与账号状态为进程级单例
+ * 命令行入口（main.ts）只负责参数解析、认证初始化与监听
+ */
+
+import type { Hono } from "hono"
+import { app, basePath } from "./server"
+import { BILLING_TAG_HEADER } from "./lib/billing-tag"
+
+export { state, type State } from "./lib/state"
+export { CONFIG_KEYS, loadConfigFile } from "./lib/config-file"
+export { readEnv } from "./lib/env"
+export { accountManager } from "./services/antigravity/account-manager"
+export { basePath }
+
+export type ProxyFormat = "anthropic" | "openai"
+
+export interface ProxyRequest {
+    /** anthropic = /v1/messages，openai = /v1/chat/completions */
+    format: ProxyFormat
+    body: unknown
+    headers?: Record<string, string>
+    signal?: AbortSignal
+    /** 🆕 计费归属标签，等价于 X-Billing-Tag 请求头（需在 ANTI_API_BILLING_TAGS 中） */
+    billingTag?: string
+}
+
+export interface ProxyResponse {
+    status: number
+    headers: Headers
+    /** JSON 响应为解析后的对象，SSE 等其他类型为原始文本 */
+    body: unknown
+}
+
+const FORMAT_PATHS: Record<ProxyFormat, string> = {
+    anthropic: "/v1/messages",
+    openai: "/v1/chat/completions",
+}
+
+export function buildRouter(): Hono {
+    return app
+}
+
+export async function proxyOnce(request: ProxyRequest): Promise<ProxyResponse> {
+    const response = await app.fetch(new Request(`http://localhost${basePath}${FORMAT_PATHS[request.format]}`, {
+        method: "POST",
+        headers: {
+            "Content-Type": "application/json",
+            ...request.headers,
+            ...(request.billingTag ? { [BILLING_TAG_HEADER]: request.billingTag } : {}),
+        },
+        body: JSON.stringify(request.body),
+        signal: request.signal,
+    }))
+    const text = await response.text()
+    const isJson = (response.headers.get("Content-Type") || "").includes("application/json")
+    return { status: response.status, headers: response.headers, body: isJson && text ? JSON.parse(text) : text }
+}
@@ -0,0 +1,50 @@
+/**
+ * 管理接口鉴权
+ * 需要设置 ANTI_API_ADMIN_KEY，请求携带 `Authorization: Bearer <key>` 或 `X-Admin-Key: <key>`
+ * 🆕 管理密钥独立于任何数据面凭据；未设置时管理接口（/admin/*、/config、/stats、/metrics/reset、/metrics.json、/debug/*）
+ *   一律返回 404，不回退到其他密钥；密钥比较为常量时间
+ */
+
+import type { Context, Next } from "hono"
+import { timingSafeEqual } from "crypto"
+import { readEnv } from "./env"
+
+export function safeCompare(a: string, b: string): boolean {
+    const left = Buffer.from(a)
+    const right = Buffer.from(b)
+    if (left.length !== right.length) {
+        // 长度不同也做一次比较，避免通过耗时推断长度
+        timingSafeEqual(left, left)
+        return false
+    }
+    return timingSafeEqual(left, right)
+}
+
+function extractAdminKey(c: Context): string | undefined {
+    const header = c.req.header("X-Admin-Key")
+    if (header) return header.trim()
+    const auth = c.req.header("Authorization") || ""
+    if (auth.toLowerCase().startsWith("bearer ")) return auth.slice(7).trim()
+    return undefined
+}
+
+/**
+ * 🆕 请求是否携带有效管理密钥（不拦截，供按请求开启调试功能时判断）
+ */
+export function hasValidAdminKey(c: Context): boolean {
+    const adminKey = readEnv("ANTI_API_ADMIN_KEY")
+    const provided = extractAdminKey(c)
+    return !!adminKey && !!provided && safeCompare(provided, adminKey)
+}
+
+export async function requireAdmin(c: Context, next: Next) {
+    const adminKey = readEnv("ANTI_API_ADMIN_KEY")
+    if (!adminKey) {
+        return c.json({ error: { type: "not_found", message: "Not found" } }, 404)
+    }
+    const provided = extractAdminKey(c)
+    if (!provided || !safeCompare(provided, adminKey)) {
+        return c.json({ error: { type: "authentication_error", message: "Invalid admin key" } }, 401)
+    }
+    await next()
+}
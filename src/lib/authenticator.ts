@@ -0,0 +1,178 @@
+/**
+ * 🆕 可插拔的入站鉴权（模型接口 /v1/messages、/v1/chat/completions）
+ * ANTI_API_AUTH_CHAIN = 逗号分隔的鉴权器名称，按顺序组成鉴权链（默认为空 = 不鉴权）
+ * - api_key: 请求携带 `x-api-key: <key>` 或 `Authorization: Bearer <key>`，key 属于 ANTI_API_CLIENT_API_KEYS
+ * - hmac: `X-Signature: <hex>` = HMAC-SHA256(ANTI_API_HMAC_SECRET, `${X-Timestamp}.${body}`)，
+ *   X-Timestamp 为 Unix 秒，与本机时间相差不超过 ANTI_API_HMAC_MAX_SKEW_SECS（默认 300）
+ * - 🆕 ANTI_API_HMAC_REQUIRE_NONCE=true 时开启防重放：请求还需携带 `X-Nonce`（8~128 个可见字符），
+ *   签名改为 HMAC-SHA256(secret, `${X-Timestamp}.${X-Nonce}.${body}`)；签名通过的 nonce 在时间窗口内
+ *   （2 × 允许偏差）记录在内存中，重复出现即拒绝。记录上限 ANTI_API_HMAC_NONCE_MAX_ENTRIES（默认 100000），
+ *   超出时先清理过期条目，仍超出则淘汰最早的条目
+ * 每个鉴权器返回 accept / reject / skip（请求未携带该方式的凭据）：任一 accept 即放行；
+ * 无 accept 时返回最先出现的 reject（401），全部 skip 也返回 401
+ * 新增鉴权方式只需实现 Authenticator 并登记到 AUTHENTICATORS
+ */
+
+import type { Context, Next } from "hono"
+import { createHmac } from "crypto"
+import consola from "consola"
+import { safeCompare } from "./admin-auth"
+import { envBool, envInt, envList, readEnv } from "./env"
+import { incrementCounter } from "./metrics"
+
+export type AuthResult =
+    | { outcome: "accept"; principal: string }
+    | { outcome: "reject"; message: string }
+    | { outcome: "skip" }
+
+export interface Authenticator {
+    readonly name: string
+    authenticate(c: Context): AuthResult | Promise<AuthResult>
+}
+
+function extractApiKey(c: Context): string | undefined {
+    const header = c.req.header("x-api-key")
+    if (header) return header.trim()
+    const auth = c.req.header("Authorization") || ""
+    if (auth.toLowerCase().startsWith("bearer ")) return auth.slice(7).trim()
+    return undefined
+}
+
+export class ApiKeyAuthenticator implements Authenticator {
+    readonly name = "api_key"
+
+    authenticate(c: Context): AuthResult {
+        const provided = extractApiKey(c)
+        if (!provided) return { outcome: "skip" }
+        const keys = envList("ANTI_API_CLIENT_API_KEYS")
+        // 逐个比较，不在第一个匹配处提前返回，避免耗时泄露匹配位置
+        let index = -1
+        keys.forEach((key, i) => {
+            if (safeCompare(provided, key) && index === -1) index = i
+        })
+        return index === -1
+            ? { outcome: "reject", message: "Invalid API key" }
+            : { outcome: "accept", principal: `api_key:${index}` }
+    }
+}
+
+export function signHmacRequest(secret: string, timestamp: string, body: string, nonce?: string): string {
+    const payload = nonce === undefined ? `${timestamp}.${body}` : `${timestamp}.${nonce}.${body}`
+    return createHmac("sha256", secret).update(payload).digest("hex")
+}
+
+const NONCE_PATTERN = /^[\x21-\x7e]{8,128}$/
+
+/** 🆕 已使用的 nonce → 过期时间（ms），Map 保持插入顺序便于淘汰最早条目 */
+const seenNonces = new Map<string, number>()
+
+export function isHmacNonceRequired(): boolean {
+    return envBool("ANTI_API_HMAC_REQUIRE_NONCE")
+}
+
+/**
+ * 🆕 记录 nonce；窗口内已出现过返回 false
+ */
+export function rememberNonce(nonce: string, ttlMs: number, now: number = Date.now()): boolean {
+    const expiresAt = seenNonces.get(nonce)
+    if (expiresAt !== undefined && expiresAt > now) return false
+    seenNonces.delete(nonce)
+    const maxEntries = Math.max(1, envInt("ANTI_API_HMAC_NONCE_MAX_ENTRIES", 100000))
+    if (seenNonces.size >= maxEntries) {
+        for (const [key, expiry] of seenNonces) {
+            if (expiry <= now) seenNonces.delete(key)
+        }
+        for (const key of seenNonces.keys()) {
+            if (seenNonces.size < maxEntries) break
+            seenNonces.delete(key)
+        }
+    }
+    seenNonces.set(nonce, now + ttlMs)
+    return true
+}
+
+export function resetNonceCache(): void {
+    seenNonces.clear()
+}
+
+export class HmacAuthenticator implements Authenticator {
+    readonly name = "hmac"
+
+    async authenticate(c: Context): Promise<AuthResult> {
+        const signature = c.req.header("X-Signature")?.trim().toLowerCase()
+        if (!signature) return { outcome: "skip" }
+        const secret = readEnv("ANTI_API_HMAC_SECRET")
+        if (!secret) return { outcome: "reject", message: "HMAC authentication is not configured" }
+        const timestamp = c.req.header("X-Timestamp")?.trim() || ""
+        const seconds = Number(timestamp)
+        const maxSkewSecs = Math.max(1, envInt("ANTI_API_HMAC_MAX_SKEW_SECS", 300))
+        if (!/^\d+$/.test(timestamp) || Math.abs(Date.now() / 1000 - seconds) > maxSkewSecs) {
+            return { outcome: "reject", message: "Missing or stale X-Timestamp" }
+        }
+        const requireNonce = isHmacNonceRequired()
+        const nonce = c.req.header("X-Nonce")?.trim()
+        if (requireNonce && (!nonce || !NONCE_PATTERN.test(nonce))) {
+            return { outcome: "reject", message: "Missing or invalid X-Nonce" }
+        }
+        const expected = signHmacRequest(secret, timestamp, await c.req.text(), requireNonce ? nonce : undefined)
+        if (!safeCompare(signature, expected)) return { outcome: "reject", message: "Invalid request signature" }
+        // 只记录签名有效的 nonce，伪造请求无法占满记录
+        if (requireNonce && !rememberNonce(nonce!, maxSkewSecs * 2000)) {
+            incrementCounter("hmac_replays_rejected")
+            return { outcome: "reject", message: "Replayed request (nonce already used)" }
+        }
+        return { outcome: "accept", principal: "hmac" }
+    }
+}
+
+const AUTHENTICATORS: Record<string, () => Authenticator> = {
+    api_key: () => new ApiKeyAuthenticator(),
+    hmac: () => new HmacAuthenticator(),
+}
+
+/**
+ * 按名称构建鉴权链；未知名称抛出（启动时校验）
+ */
+export function buildAuthChain(names: string[]): Authenticator[] {
+    return names.map(name => {
+        const factory = AUTHENTICATORS[name.toLowerCase()]
+        if (!factory) {
+            throw new Error(`Unknown authenticator "${name}" in ANTI_API_AUTH_CHAIN (expected ${Object.keys(AUTHENTICATORS).join(", ")})`)
+        }
+        return factory()
+    })
+}
+
+let cachedChain: { raw: string; chain: Authenticator[] } | undefined
+
+export function getAuthChain(): Authenticator[] {
+    const raw = readEnv("ANTI_API_AUTH_CHAIN") || ""
+    if (cachedChain?.raw !== raw) {
+        cachedChain = { raw, chain: buildAuthChain(envList("ANTI_API_AUTH_CHAIN")) }
+    }
+    return cachedChain.chain
+}
+
+export async function runAuthChain(chain: Authenticator[], c: Context): Promise<AuthResult> {
+    if (chain.length === 0) return { outcome: "accept", principal: "anonymous" }
+    let rejection: AuthResult | undefined
+    for (const authenticator of chain) {
+        const result = await authenticator.authenticate(c)
+        if (result.outcome === "accept") return result
+        if (result.outcome === "reject" && !rejection) rejection = result
+    }
+    return rejection ?? { outcome: "reject", message: "Authentication required" }
+}
+
+export async function authGuard(c: Context, next: Next) {
+    const chain = getAuthChain()
+    if (chain.length === 0) return next()
+    const result = await runAuthChain(chain, c)
+    if (result.outcome !== "accept") {
+        const message = result.outcome === "reject" ? result.message : "Authentication required"
+        incrementCounter("auth_rejections", { route: c.req.routePath })
+        consola.debug(`Auth rejected for ${c.req.method} ${c.req.path}: ${message}`)
+        return c.json({ error: { type: "authentication_error", message } }, 401)
+    }
+    await next()
+}
@@ -0,0 +1,170 @@
+/**
+ * 🆕 基于 429 反馈的并发 + 请求间隔自动调节（AIMD）
+ * ANTI_API_AUTOTUNE=true 时开启（默认关闭），每 ANTI_API_AUTOTUNE_WINDOW_SECS（默认 10）秒评估一次窗口内的 429 比例：
+ * - 超过 ANTI_API_AUTOTUNE_TARGET_429_RATE（默认 0.05）：并发减半、间隔翻倍（乘性退让）
+ * - 未超过：并发 +1、间隔减少 ANTI_API_AUTOTUNE_INTERVAL_STEP_MS（默认 100）（加性恢复）
+ * - 窗口内没有上游响应：保持不变
+ * 取值范围：
+ * - 并发 ANTI_API_AUTOTUNE_MIN_CONCURRENCY（默认 1）~ ANTI_API_AUTOTUNE_MAX_CONCURRENCY（默认 ANTI_API_MAX_CONCURRENCY，未设置时 8）
+ * - 间隔 ANTI_API_AUTOTUNE_MIN_INTERVAL_MS（默认 ANTI_API_MIN_REQUEST_INTERVAL_MS）~ ANTI_API_AUTOTUNE_MAX_INTERVAL_MS（默认 5000）
+ * 从最宽松的值（最大并发、最小间隔）开始；启动爬坡（ANTI_API_RAMP_SECS）期间爬坡上限优先
+ * 指标：autotune_concurrency、autotune_interval_ms、autotune_429_rate、autotune_backing_off（仪表），autotune_adjustments（计数）
+ */
+
+import consola from "consola"
+import { setTunedConcurrencyLimit, getGlobalConcurrencyLimit } from "./concurrency"
+import { envBool, envInt, readEnv } from "./env"
+import { incrementCounter, setGauge } from "./metrics"
+import { getMinRequestIntervalMs, rateLimiter } from "./rate-limiter"
+
+export interface AutoTuneBounds {
+    minConcurrency: number
+    maxConcurrency: number
+    minIntervalMs: number
+    maxIntervalMs: number
+    intervalStepMs: number
+    target429Rate: number
+}
+
+export type AutoTuneState = "relaxing" | "backing_off" | "holding"
+
+export interface AutoTuneSnapshot {
+    concurrency: number
+    intervalMs: number
+    state: AutoTuneState
+    last429Rate: number
+    responses: number
+    throttled: number
+}
+
+export function isAutoTuneEnabled(): boolean {
+    return envBool("ANTI_API_AUTOTUNE")
+}
+
+function readRate(name: string, fallback: number): number {
+    const raw = Number.parseFloat(readEnv(name) || "")
+    return Number.isFinite(raw) ? Math.min(1, Math.max(0, raw)) : fallback
+}
+
+export function getAutoTuneBounds(): AutoTuneBounds {
+    const configuredMax = getGlobalConcurrencyLimit()
+    const minConcurrency = Math.max(1, envInt("ANTI_API_AUTOTUNE_MIN_CONCURRENCY", 1))
+    const maxConcurrency = Math.max(minConcurrency, envInt("ANTI_API_AUTOTUNE_MAX_CONCURRENCY", configuredMax > 0 ? configuredMax : 8))
+    const minIntervalMs = Math.max(0, envInt("ANTI_API_AUTOTUNE_MIN_INTERVAL_MS", getMinRequestIntervalMs()))
+    const maxIntervalMs = Math.max(minIntervalMs, envInt("ANTI_API_AUTOTUNE_MAX_INTERVAL_MS", 5000))
+    return {
+        minConcurrency,
+        maxConcurrency,
+        minIntervalMs,
+        maxIntervalMs,
+        intervalStepMs: Math.max(1, envInt("ANTI_API_AUTOTUNE_INTERVAL_STEP_MS", 100)),
+        target429Rate: readRate("ANTI_API_AUTOTUNE_TARGET_429_RATE", 0.05),
+    }
+}
+
+/**
+ * 纯状态机：记录窗口内的响应，evaluate() 时给出下一组取值
+ */
+export class AutoTuner {
+    private concurrency: number
+    private intervalMs: number
+    private state: AutoTuneState = "holding"
+    private last429Rate = 0
+    private responses = 0
+    private throttled = 0
+
+    constructor(private readonly bounds: AutoTuneBounds) {
+        this.concurrency = bounds.maxConcurrency
+        this.intervalMs = bounds.minIntervalMs
+    }
+
+    record(status: number): void {
+        this.responses++
+        if (status === 429) this.throttled++
+    }
+
+    evaluate(): AutoTuneSnapshot {
+        const { bounds } = this
+        if (this.responses === 0) {
+            this.state = "holding"
+        } else {
+            this.last429Rate = this.throttled / this.responses
+            if (this.last429Rate > bounds.target429Rate) {
+                this.state = "backing_off"
+                this.concurrency = Math.max(bounds.minConcurrency, Math.floor(this.concurrency / 2))
+                this.intervalMs = Math.min(bounds.maxIntervalMs, Math.max(bounds.intervalStepMs, this.intervalMs * 2))
+            } else {
+                this.state = "relaxing"
+                this.concurrency = Math.min(bounds.maxConcurrency, this.concurrency + 1)
+                this.intervalMs = Math.max(bounds.minIntervalMs, this.intervalMs - bounds.intervalStepMs)
+            }
+        }
+        const snapshot = this.snapshot()
+        this.responses = 0
+        this.throttled = 0
+        return snapshot
+    }
+
+    snapshot(): AutoTuneSnapshot {
+        return {
+            concurrency: this.concurrency,
+            intervalMs: this.intervalMs,
+            state: this.state,
+            last429Rate: this.last429Rate,
+            responses: this.responses,
+            throttled: this.throttled,
+        }
+    }
+}
+
+let tuner: AutoTuner | null = null
+let timer: ReturnType<typeof setInterval> | null = null
+
+/**
+ * 上游响应回调（每次上游调用得到状态码时调用）
+ */
+export function recordAutoTuneStatus(status: number): void {
+    tuner?.record(status)
+}
+
+export function getAutoTuneSnapshot(): AutoTuneSnapshot | null {
+    return tuner?.snapshot() ?? null
+}
+
+function apply(snapshot: AutoTuneSnapshot): void {
+    setTunedConcurrencyLimit(snapshot.concurrency)
+    rateLimiter.setMinInterval(snapshot.intervalMs)
+    setGauge("autotune_concurrency", snapshot.concurrency)
+    setGauge("autotune_interval_ms", snapshot.intervalMs)
+    setGauge("autotune_429_rate", snapshot.last429Rate)
+    setGauge("autotune_backing_off", snapshot.state === "backing_off" ? 1 : 0)
+}
+
+export function startAutoTuner(): void {
+    if (!isAutoTuneEnabled() || timer) return
+    const bounds = getAutoTuneBounds()
+    const windowMs = Math.max(1, envInt("ANTI_API_AUTOTUNE_WINDOW_SECS", 10)) * 1000
+    tuner = new AutoTuner(bounds)
+    apply(tuner.snapshot())
+    consola.info(`Auto-tune: concurrency ${bounds.minConcurrency}-${bounds.maxConcurrency}, interval ${bounds.minIntervalMs}-${bounds.maxIntervalMs}ms, target 429 rate ${bounds.target429Rate}`)
+
+    timer = setInterval(() => {
+        if (!tuner) return
+        const before = tuner.snapshot()
+        const next = tuner.evaluate()
+        apply(next)
+        if (next.concurrency !== before.concurrency || next.intervalMs !== before.intervalMs) {
+            incrementCounter("autotune_adjustments", { direction: next.state === "backing_off" ? "down" : "up" })
+            consola.info(`Auto-tune ${next.state}: concurrency ${before.concurrency} -> ${next.concurrency}, interval ${before.intervalMs} -> ${next.intervalMs}ms (429 rate ${(next.last429Rate * 100).toFixed(1)}%)`)
+        }
+    }, windowMs)
+    timer.unref?.()
+}
+
+export function stopAutoTuner(): void {
+    if (timer) clearInterval(timer)
+    timer = null
+    tuner = null
+    setTunedConcurrencyLimit(null)
+    rateLimiter.setMinInterval(getMinRequestIntervalMs())
+}
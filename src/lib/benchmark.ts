@@ -0,0 +1,72 @@
+/**
+ * 🆕 基准测试模式（ANTI_API_BENCHMARK=1，默认关闭）
+ * 上游调用替换为固定延迟的合成响应（ANTI_API_BENCHMARK_DELAY_MS，默认 200），
+ * 其余流程（限流、许可、账号选择、序列化）照常执行，用于测量代理自身开销
+ * 结果见 GET /debug/benchmark（需管理密钥）
+ */
+
+import { envBool, envInt } from "./env"
+import { RingBuffer } from "./recent-requests"
+import { getRequestContext } from "./request-context"
+
+const MAX_SAMPLES = 10000
+
+const samples = new RingBuffer<number>(MAX_SAMPLES)
+
+export function isBenchmarkMode(): boolean {
+    return envBool("ANTI_API_BENCHMARK")
+}
+
+export function getBenchmarkDelayMs(): number {
+    return Math.max(0, envInt("ANTI_API_BENCHMARK_DELAY_MS", 200))
+}
+
+const SYNTHETIC_CHUNK = JSON.stringify({
+    response: {
+        candidates: [{ content: { role: "model", parts: [{ text: "benchmark" }] }, finishReason: "STOP" }],
+        usageMetadata: { promptTokenCount: 1, candidatesTokenCount: 1, totalTokenCount: 2 },
+    },
+})
+
+/**
+ * 合成上游响应；延迟计入请求上下文，便于从总耗时中扣除
+ */
+export async function syntheticUpstreamResponse(): Promise<Response> {
+    const delayMs = getBenchmarkDelayMs()
+    const ctx = getRequestContext()
+    if (ctx) ctx.syntheticUpstreamMs = (ctx.syntheticUpstreamMs || 0) + delayMs
+    if (delayMs > 0) await new Promise(resolve => setTimeout(resolve, delayMs))
+    return new Response(`data: ${SYNTHETIC_CHUNK}\n\n`, {
+        status: 200,
+        headers: { "Content-Type": "text/event-stream" },
+    })
+}
+
+export function recordBenchmarkSample(overheadMs: number): void {
+    samples.push(Math.max(0, overheadMs))
+}
+
+function percentile(sorted: number[], p: number): number | null {
+    if (sorted.length === 0) return null
+    const index = Math.min(sorted.length - 1, Math.ceil((p / 100) * sorted.length) - 1)
+    return sorted[Math.max(0, index)]
+}
+
+export function getBenchmarkStats() {
+    const sorted = samples.recent().sort((a, b) => a - b)
+    return {
+        enabled: isBenchmarkMode(),
+        syntheticDelayMs: getBenchmarkDelayMs(),
+        samples: sorted.length,
+        overheadMs: {
+            p50: percentile(sorted, 50),
+            p90: percentile(sorted, 90),
+            p99: percentile(sorted, 99),
+            max: sorted.length ? sorted[sorted.length - 1] : null,
+        },
+    }
+}
+
+export function resetBenchmarkStats(): void {
+    samples.clear()
+}
@@ -0,0 +1,39 @@
+/**
+ * 🆕 计费归属标签（X-Billing-Tag 请求头；库模式下为 ProxyRequest.billingTag）
+ * ANTI_API_BILLING_TAGS: 允许的标签列表（逗号分隔）；未设置时忽略该请求头
+ * 标签不在列表中返回 400 invalid_billing_tag，标签基数因此受列表约束
+ * 标签写入请求日志、model_requests_total 的 billing_tag 标签与 /debug/recent，不会发往上游
+ */
+
+import type { Context, Next } from "hono"
+import { envList } from "./env"
+import { getRequestContext, updateRequestContext } from "./request-context"
+
+export const BILLING_TAG_HEADER = "X-Billing-Tag"
+
+export function getAllowedBillingTags(): string[] {
+    return envList("ANTI_API_BILLING_TAGS")
+}
+
+export async function billingTagGuard(c: Context, next: Next) {
+    const tag = c.req.header(BILLING_TAG_HEADER)?.trim()
+    const allowed = getAllowedBillingTags()
+    if (!tag || allowed.length === 0) return next()
+    if (!allowed.includes(tag)) {
+        return c.json({ error: { type: "invalid_billing_tag", message: `Billing tag "${tag.slice(0, 64)}" is not allowed` } }, 400)
+    }
+    updateRequestContext({ billingTag: tag })
+    await next()
+}
+
+export function billingTagLabel(tag: string | undefined): string {
+    return tag ?? "none"
+}
+
+/**
+ * 日志后缀：` [billing=<tag>]`，无标签时为空
+ */
+export function formatBillingTag(): string {
+    const tag = getRequestContext()?.billingTag
+    return tag ? ` [billing=${tag}]` : ""
+}
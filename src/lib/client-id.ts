@@ -0,0 +1,53 @@
+/**
+ * 调用方标识 (X-Client-Id)
+ * - ANTI_API_CLIENT_IDS: 允许的客户端 ID 列表（逗号分隔）；设置后未知 ID 被拒绝，且作为指标标签
+ * - ANTI_API_REQUIRE_CLIENT_ID=1: 缺少请求头时返回 400，否则记为 "anonymous"
+ * 未设置允许列表时只记录到日志，不作为指标标签，避免基数失控
+ */
+
+import type { Context, Next } from "hono"
+import { envBool, envList } from "./env"
+
+declare module "hono" {
+    interface ContextVariableMap {
+        clientId: string
+    }
+}
+
+export const ANONYMOUS_CLIENT_ID = "anonymous"
+const CLIENT_ID_PATTERN = /^[A-Za-z0-9._-]{1,64}$/
+
+export function getClientId(c: Context): string {
+    return c.get("clientId") || ANONYMOUS_CLIENT_ID
+}
+
+/**
+ * 指标标签：只有允许列表中的 ID 才作为标签值
+ */
+export function getClientMetricLabel(c: Context): string | undefined {
+    const allowlist = envList("ANTI_API_CLIENT_IDS")
+    if (allowlist.length === 0) return undefined
+    const id = getClientId(c)
+    return allowlist.includes(id) ? id : ANONYMOUS_CLIENT_ID
+}
+
+export async function clientIdentity(c: Context, next: Next) {
+    const raw = (c.req.header("X-Client-Id") || "").trim()
+    if (!raw) {
+        if (envBool("ANTI_API_REQUIRE_CLIENT_ID")) {
+            return c.json({ error: { type: "invalid_request_error", message: "X-Client-Id header is required" } }, 400)
+        }
+        c.set("clientId", ANONYMOUS_CLIENT_ID)
+        return next()
+    }
+
+    if (!CLIENT_ID_PATTERN.test(raw)) {
+        return c.json({ error: { type: "invalid_request_error", message: "Invalid X-Client-Id" } }, 400)
+    }
+    const allowlist = envList("ANTI_API_CLIENT_IDS")
+    if (allowlist.length > 0 && !allowlist.includes(raw)) {
+        return c.json({ error: { type: "permission_error", message: `Unknown client id "${raw}"` } }, 403)
+    }
+    c.set("clientId", raw)
+    return next()
+}
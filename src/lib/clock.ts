@@ -0,0 +1,66 @@
+/**
+ * 时钟抽象
+ * 生产环境使用 systemClock；测试使用 MockClock 手动推进时间，避免真实等待
+ */
+
+export interface Clock {
+    now(): number
+    sleep(ms: number): Promise<void>
+}
+
+export const systemClock: Clock = {
+    now: () => Date.now(),
+    sleep: (ms: number) => new Promise(resolve => setTimeout(resolve, ms)),
+}
+
+interface Sleeper {
+    wakeAt: number
+    resolve: () => void
+}
+
+export class MockClock implements Clock {
+    private current: number
+    private sleepers: Sleeper[] = []
+
+    constructor(start: number = 0) {
+        this.current = start
+    }
+
+    now(): number {
+        return this.current
+    }
+
+    sleep(ms: number): Promise<void> {
+        if (ms <= 0) return Promise.resolve()
+        return new Promise(resolve => {
+            this.sleepers.push({ wakeAt: this.current + ms, resolve })
+        })
+    }
+
+    get pendingSleepers(): number {
+        return this.sleepers.length
+    }
+
+    /**
+     * 推进时间并唤醒到期的 sleep；每次唤醒后让出微任务，便于被唤醒的代码继续注册新的 sleep
+     */
+    async advance(ms: number): Promise<void> {
+        const target = this.current + ms
+        while (true) {
+            await flushMicrotasks()
+            const next = this.sleepers
+                .filter(sleeper => sleeper.wakeAt <= target)
+                .sort((a, b) => a.wakeAt - b.wakeAt)[0]
+            if (!next) break
+            this.sleepers.splice(this.sleepers.indexOf(next), 1)
+            this.current = Math.max(this.current, next.wakeAt)
+            next.resolve()
+        }
+        this.current = target
+        await flushMicrotasks()
+    }
+}
+
+async function flushMicrotasks(): Promise<void> {
+    for (let i = 0; i < 10; i++) await Promise.resolve()
+}
@@ -0,0 +1,57 @@
+/**
+ * 🆕 响应压缩（ANTI_API_RESPONSE_COMPRESSION=1 开启，默认关闭）
+ * 按 Accept-Encoding 选择 gzip / deflate；SSE、已压缩响应与小于阈值的响应不处理
+ * 🆕 上游流式（SSE）请求默认发送 Accept-Encoding: identity，覆盖运行时默认的 gzip/br 协商：
+ *   部分上游或中间层会整块压缩事件流，解压端必须攒够压缩块才能吐出数据，表现为流式卡顿、帧被截断或拼接错乱；
+ *   缓冲（非流式）请求不受影响，仍可压缩。ANTI_API_SSE_UPSTREAM_COMPRESSION=1 恢复运行时默认协商
+ */
+
+import type { Context, Next } from "hono"
+import { envBool } from "./env"
+
+const COMPRESSION_THRESHOLD_BYTES = 1024
+
+export function selectEncoding(acceptEncoding: string | undefined): "gzip" | "deflate" | null {
+    const accepted = new Map<string, number>()
+    for (const part of (acceptEncoding || "").split(",")) {
+        const [name, ...params] = part.trim().toLowerCase().split(";")
+        if (!name) continue
+        const q = params.map(param => param.trim()).find(param => param.startsWith("q="))
+        accepted.set(name, q ? Number.parseFloat(q.slice(2)) || 0 : 1)
+    }
+    for (const encoding of ["gzip", "deflate"] as const) {
+        const quality = accepted.get(encoding) ?? accepted.get("*")
+        if (quality !== undefined && quality > 0) return encoding
+    }
+    return null
+}
+
+export function shouldCompressResponse(res: Response): boolean {
+    if (!res.body) return false
+    if (res.headers.has("Content-Encoding")) return false
+    const contentType = res.headers.get("Content-Type") || ""
+    if (contentType.includes("text/event-stream")) return false
+    const contentLength = res.headers.get("Content-Length")
+    if (contentLength && Number(contentLength) < COMPRESSION_THRESHOLD_BYTES) return false
+    return true
+}
+
+export async function responseCompression(c: Context, next: Next) {
+    await next()
+    if (!envBool("ANTI_API_RESPONSE_COMPRESSION") || c.req.method === "HEAD") return
+    const encoding = selectEncoding(c.req.header("Accept-Encoding"))
+    if (!encoding || !shouldCompressResponse(c.res)) return
+
+    const compressed = new Response(c.res.body!.pipeThrough(new CompressionStream(encoding)), c.res)
+    compressed.headers.delete("Content-Length")
+    compressed.headers.set("Content-Encoding", encoding)
+    compressed.headers.append("Vary", "Accept-Encoding")
+    c.res = compressed
+}
+
+/**
+ * 🆕 上游 SSE 请求附加的编码协商头（空对象 = 沿用运行时默认）
+ */
+export function getUpstreamSseEncodingHeaders(): Record<string, string> {
+    return envBool("ANTI_API_SSE_UPSTREAM_COMPRESSION") ? {} : { "Accept-Encoding": "identity" }
+}
@@ -0,0 +1,170 @@
+/**
+ * 并发控制
+ * - 全局上限：ANTI_API_MAX_CONCURRENCY（默认 0 = 不限制）
+ * - 单账号上限：ANTI_API_MAX_CONCURRENCY_PER_ACCOUNT（默认 1 = 串行）
+ * - 超限策略：ANTI_API_CONCURRENCY_MODE = wait | fail（默认 wait）
+ * - 启动爬坡：ANTI_API_RAMP_SECS（默认 0 = 关闭），全局上限在该时间内从 1 线性增长到配置值
+ * - 🆕 排队饥饿检测：等待超过 ANTI_API_PERMIT_WAIT_WARN_MS 记录告警，
+ *   超过 ANTI_API_PERMIT_WAIT_TIMEOUT_MS 返回 503 permit_wait_timeout（默认均为 0 = 关闭）
+ * - 🆕 排队顺序：ANTI_API_QUEUE_POLICY = fifo（默认）| lifo（过载时优先服务最新请求，
+ *   旧的等待者配合 ANTI_API_PERMIT_WAIT_TIMEOUT_MS 超时失败）
+ * - 🆕 流式上限：ANTI_API_MAX_CONCURRENT_STREAMS（默认 0 = 不限制），与全局上限相互独立；
+ *   达到上限时按 ANTI_API_STREAM_LIMIT_MODE = reject（默认，503 too_many_streams）| buffered（降级为非流式）处理
+ * - 🆕 自动调节：ANTI_API_AUTOTUNE 开启时全局上限由 auto-tune.ts 根据 429 比例调整（爬坡期间爬坡上限优先）
+ */
+
+import consola from "consola"
+import { envInt, envString } from "./env"
+import { AntigravityError, ConcurrencyLimitError } from "./error"
+import { incrementCounter } from "./metrics"
+import { Semaphore, type QueuePolicy, type Release } from "./semaphore"
+
+export type ConcurrencyMode = "wait" | "fail"
+
+export function getGlobalConcurrencyLimit(): number {
+    return Math.max(0, envInt("ANTI_API_MAX_CONCURRENCY", 0))
+}
+
+export function getPerAccountConcurrencyLimit(): number {
+    return Math.max(0, envInt("ANTI_API_MAX_CONCURRENCY_PER_ACCOUNT", 1))
+}
+
+export function getConcurrencyMode(): ConcurrencyMode {
+    return envString("ANTI_API_CONCURRENCY_MODE", "wait").toLowerCase() === "fail" ? "fail" : "wait"
+}
+
+export function getQueuePolicy(): QueuePolicy {
+    return envString("ANTI_API_QUEUE_POLICY", "fifo").toLowerCase() === "lifo" ? "lifo" : "fifo"
+}
+
+export const globalSemaphore = new Semaphore(getGlobalConcurrencyLimit())
+
+// 爬坡期间的临时上限，null 表示使用配置值
+let rampLimit: number | null = null
+// 🆕 自动调节给出的上限，null 表示未开启
+let tunedLimit: number | null = null
+
+function getEffectiveGlobalLimit(): number {
+    return rampLimit ?? tunedLimit ?? getGlobalConcurrencyLimit()
+}
+
+/**
+ * 🆕 由自动调节器设置全局上限（null 恢复为配置值）
+ */
+export function setTunedConcurrencyLimit(limit: number | null): void {
+    tunedLimit = limit
+    if (rampLimit === null) globalSemaphore.setLimit(getEffectiveGlobalLimit())
+}
+
+/**
+ * 🆕 启动并发爬坡（需要同时配置 ANTI_API_MAX_CONCURRENCY）
+ */
+export function startConcurrencyRamp(): void {
+    const rampSecs = envInt("ANTI_API_RAMP_SECS", 0)
+    const target = getGlobalConcurrencyLimit()
+    if (rampSecs <= 0 || target <= 1) return
+
+    const startedAt = Date.now()
+    rampLimit = 1
+    globalSemaphore.setLimit(rampLimit)
+    consola.info(`Concurrency ramp: 1 -> ${target} over ${rampSecs}s`)
+
+    const timer = setInterval(() => {
+        const progress = Math.min(1, (Date.now() - startedAt) / (rampSecs * 1000))
+        const next = Math.max(1, Math.ceil(target * progress))
+        if (progress >= 1) {
+            clearInterval(timer)
+            rampLimit = null
+            globalSemaphore.setLimit(getEffectiveGlobalLimit())
+            consola.info(`Concurrency ramp complete: ${getEffectiveGlobalLimit()} permits`)
+            return
+        }
+        if (next !== rampLimit) {
+            rampLimit = next
+            globalSemaphore.setLimit(next)
+            consola.info(`Concurrency ramp: ${next}/${target} permits`)
+        }
+    }, 1000)
+    timer.unref?.()
+}
+
+/**
+ * 按当前模式获取许可：wait 模式排队，fail 模式立即抛出 ConcurrencyLimitError
+ */
+export async function acquirePermit(semaphore: Semaphore, scope: "global" | "account"): Promise<Release> {
+    if (getConcurrencyMode() === "fail") {
+        const release = semaphore.tryAcquire()
+        if (!release) {
+            throw new ConcurrencyLimitError(scope, scope === "global"
+                ? "Too many concurrent requests"
+                : "Too many concurrent requests for this account")
+        }
+        return release
+    }
+    return acquireWithStarvationCheck(semaphore, scope)
+}
+
+/**
+ * 🆕 wait 模式排队：长时间拿不到许可时告警/失败，暴露被单个长请求阻塞的情况
+ */
+async function acquireWithStarvationCheck(semaphore: Semaphore, scope: "global" | "account"): Promise<Release> {
+    const warnMs = Math.max(0, envInt("ANTI_API_PERMIT_WAIT_WARN_MS", 0))
+    const timeoutMs = Math.max(0, envInt("ANTI_API_PERMIT_WAIT_TIMEOUT_MS", 0))
+    if (warnMs === 0 && timeoutMs === 0) return semaphore.acquire()
+
+    const startedAt = Date.now()
+    const warnTimer = warnMs > 0 && (timeoutMs === 0 || warnMs < timeoutMs)
+        ? setTimeout(() => {
+            incrementCounter("permit_wait_warnings", { scope })
+            consola.warn(`Waiting ${Date.now() - startedAt}ms for a ${scope} concurrency permit (${semaphore.inUse} in use, ${semaphore.waiting} waiting)`)
+        }, warnMs)
+        : undefined
+    try {
+        const release = timeoutMs > 0 ? await semaphore.acquireWithTimeout(timeoutMs) : await semaphore.acquire()
+        if (release) return release
+        incrementCounter("permit_wait_timeouts", { scope })
+        consola.warn(`Gave up after ${timeoutMs}ms waiting for a ${scope} concurrency permit (${semaphore.inUse} in use, ${semaphore.waiting} waiting)`)
+        throw new AntigravityError(`Timed out after ${timeoutMs}ms waiting for a concurrency permit`, "permit_wait_timeout", 503)
+    } finally {
+        if (warnTimer) clearTimeout(warnTimer)
+    }
+}
+
+/**
+ * 获取全局请求许可（HTTP 层调用，流式请求需在流结束时释放）
+ */
+export function acquireRequestPermit(): Promise<Release> {
+    const limit = getEffectiveGlobalLimit()
+    if (globalSemaphore.capacity !== limit) globalSemaphore.setLimit(limit)
+    const policy = getQueuePolicy()
+    if (globalSemaphore.queuePolicy !== policy) globalSemaphore.setQueuePolicy(policy)
+    return acquirePermit(globalSemaphore, "global")
+}
+
+export type StreamLimitMode = "reject" | "buffered"
+
+export function getMaxConcurrentStreams(): number {
+    return Math.max(0, envInt("ANTI_API_MAX_CONCURRENT_STREAMS", 0))
+}
+
+export function getStreamLimitMode(): StreamLimitMode {
+    return envString("ANTI_API_STREAM_LIMIT_MODE", "reject").toLowerCase() === "buffered" ? "buffered" : "reject"
+}
+
+export const streamSemaphore = new Semaphore(getMaxConcurrentStreams())
+
+/**
+ * 🆕 获取流式许可（不排队）：已满时 buffered 模式返回 null 由调用方降级为非流式，reject 模式抛出 503
+ */
+export function acquireStreamSlot(): Release | null {
+    const limit = getMaxConcurrentStreams()
+    if (streamSemaphore.capacity !== limit) streamSemaphore.setLimit(limit)
+    const release = streamSemaphore.tryAcquire()
+    if (release) return release
+    if (getStreamLimitMode() === "buffered") {
+        incrementCounter("stream_limit_downgrades")
+        return null
+    }
+    incrementCounter("streams_rejected")
+    throw new AntigravityError(`Too many concurrent streams (limit ${limit})`, "too_many_streams", 503)
+}
@@ -0,0 +1,218 @@
+/**
+ * 🆕 启动配置文件（TOML）
+ * ANTI_API_CONFIG 指向 TOML 文件；键名为环境变量去掉 ANTI_API_ 前缀后的小写形式，
+ * 表会按 "_" 展开（[maintenance] status = 503 等价于 ANTI_API_MAINTENANCE_STATUS=503）
+ * 优先级：环境变量 > 配置文件 > 内置默认值
+ */
+
+import { readFileSync } from "fs"
+import consola from "consola"
+
+export const ENV_PREFIX = "ANTI_API_"
+
+/**
+ * 配置文件可设置的键（不含前缀）；新增 ANTI_API_* 变量时需同步添加
+ */
+export const CONFIG_KEYS = new Set([
+    "ADMIN_KEY",
+    "ALLOW_REQUEST_EXTRA_QUERY",
+    "ALL_COOLING_MAX_WAIT_MS",
+    "ALL_COOLING_MODE",
+    "AUTH_CHAIN",
+    "AUTOTUNE",
+    "AUTOTUNE_INTERVAL_STEP_MS",
+    "AUTOTUNE_MAX_CONCURRENCY",
+    "AUTOTUNE_MAX_INTERVAL_MS",
+    "AUTOTUNE_MIN_CONCURRENCY",
+    "AUTOTUNE_MIN_INTERVAL_MS",
+    "AUTOTUNE_TARGET_429_RATE",
+    "AUTOTUNE_WINDOW_SECS",
+    "BASE_PATH",
+    "BENCHMARK",
+    "BENCHMARK_DELAY_MS",
+    "BILLING_TAGS",
+    "CAPACITY_MAX_WAIT_MS",
+    "CA_BUNDLE",
+    "CHECK_TOKEN_EXP",
+    "CHECK_TOKEN_PROJECT",
+    "CLIENT_API_KEYS",
+    "CLIENT_IDS",
+    "CONCURRENCY_MODE",
+    "CONFIG_WATCH",
+    "CONN_LIMIT_EXEMPT",
+    "DATA_DIR",
+    "DEBUG_ECHO_ENVELOPE",
+    "DEFAULT_MODEL",
+    "DISK_CACHE_DIR",
+    "DISK_CACHE_MAX_ENTRIES",
+    "DISK_CACHE_STALE_MAX_AGE_SECS",
+    "DISK_CACHE_TTL_SECS",
+    "EMPTY_RESPONSE_MODE",
+    "ENDPOINTS",
+    "ENDPOINT_TIMEOUTS_MS",
+    "ERROR_DUMP_DIR",
+    "EXTRA_QUERY",
+    "FAILOVER_STATUSES",
+    "FAULT_INJECT_KINDS",
+    "FAULT_INJECT_RATE",
+    "HMAC_MAX_SKEW_SECS",
+    "HMAC_NONCE_MAX_ENTRIES",
+    "HMAC_REQUIRE_NONCE",
+    "HMAC_SECRET",
+    "IDEMPOTENCY_MAX_ENTRIES",
+    "IDEMPOTENCY_TTL_SECS",
+    "INFRA_AT_ROOT",
+    "LOG_LINES",
+    "LOG_SAMPLE_RATE",
+    "MAINTENANCE",
+    "MAINTENANCE_MESSAGE",
+    "MAINTENANCE_RETRY_AFTER",
+    "MAINTENANCE_STATUS",
+    "MAX_CONCURRENCY",
+    "MAX_CONCURRENCY_PER_ACCOUNT",
+    "MAX_CONCURRENT_STREAMS",
+    "MAX_CONN_PER_IP",
+    "MAX_SSE_FRAMES",
+    "MAX_SSE_FRAME_BYTES",
+    "MAX_STREAM_BYTES",
+    "MAX_TOKEN_LEN",
+    "MAX_UPSTREAM_CALLS",
+    "METRICS_BACKEND",
+    "METRICS_MAX_MODELS",
+    "MIN_REQUEST_INTERVAL_MS",
+    "MIN_TLS_VERSION",
+    "MODEL_FALLBACKS",
+    "MODEL_LOWERCASE",
+    "MODEL_TIMEOUTS_MS",
+    "MTLS_CA",
+    "NO_OPEN",
+    "OTEL",
+    "OAUTH_REDIRECT_URL",
+    "OVERFLOW_ENDPOINT",
+    "OVERFLOW_THRESHOLD",
+    "OVERSIZED_SSE_FRAME",
+    "PERMIT_WAIT_TIMEOUT_MS",
+    "PERMIT_WAIT_WARN_MS",
+    "POOL_IDLE_REUSE_SECS",
+    "QUEUE_POLICY",
+    "RAMP_SECS",
+    "RATELIMIT_HEADERS",
+    "RECENT_REQUESTS",
+    "REPORT_REQUEST_SIZE",
+    "REQUEST_FIELD_ALLOW",
+    "REQUEST_FIELD_DENY",
+    "REQUEST_FIELD_STRIP",
+    "REQUEST_ID_HEADER",
+    "REQUEST_ID_PREFIX",
+    "REQUIRE_CLIENT_ID",
+    "RESOLVE",
+    "RESPONSE_COMPRESSION",
+    "RETRIES",
+    "RETRY_BUDGET",
+    "RETRY_BUDGET_MAX",
+    "RETRY_BUDGET_RATIO",
+    "RETRY_CONNECT_ONLY",
+    "REWRITE_STREAM_MODEL",
+    "SHADOW_ENDPOINT",
+    "SHADOW_MAX_CONCURRENCY",
+    "SHADOW_PCT",
+    "SHED_LAG_MS",
+    "SHED_MAX_FRACTION",
+    "SSE_UPSTREAM_COMPRESSION",
+    "STATSD_ADDR",
+    "STATUS_MAP",
+    "STREAM_IDLE_TIMEOUT_SECS",
+    "STREAM_LIMIT_MODE",
+    "STREAM_UPSTREAM_MODE",
+    "STREAM_UPSTREAM_MODE_BY_MODEL",
+    "SUCCESS_STATUSES",
+    "SUPERSEDE_BY_SESSION",
+    "TEE_STREAM_DIR",
+    "TLS_CERT",
+    "TLS_KEY",
+    "TLS_PIN",
+    "TLS_PORT",
+    "TOTAL_DEADLINE_SECS",
+    "TRUSTED_PROXIES",
+    "TTFT_TIMEOUT_MS",
+    "UPSTREAM_TIMEOUT_MS",
+    "VALIDATE_SSE",
+    "VALIDATE_TOKEN_PER_MIN",
+])
+
+function toEnvValue(value: unknown): string | undefined {
+    if (value === null || value === undefined) return undefined
+    if (Array.isArray(value)) return value.map(item => String(item)).join(",")
+    if (typeof value === "object") return undefined
+    return String(value)
+}
+
+/**
+ * 将 TOML 解析结果展开为 { ANTI_API_XXX: "value" }，未知键写入 unknown
+ */
+export function flattenConfig(
+    data: Record<string, unknown>,
+    unknown: string[] = [],
+    prefix: string = "",
+): Record<string, string> {
+    const values: Record<string, string> = {}
+    for (const [rawKey, value] of Object.entries(data)) {
+        const key = `${prefix}${rawKey}`.toUpperCase().replace(/[.-]/g, "_")
+        if (value && typeof value === "object" && !Array.isArray(value)) {
+            Object.assign(values, flattenConfig(value as Record<string, unknown>, unknown, `${key}_`))
+            continue
+        }
+        if (!CONFIG_KEYS.has(key)) {
+            unknown.push(key.toLowerCase())
+            continue
+        }
+        const envValue = toEnvValue(value)
+        if (envValue !== undefined) values[ENV_PREFIX + key] = envValue
+    }
+    return values
+}
+
+export function parseConfigFile(text: string): { values: Record<string, string>; unknown: string[] } {
+    const data = Bun.TOML.parse(text) as Record<string, unknown>
+    const unknown: string[] = []
+    const values = flattenConfig(data, unknown)
+    return { values, unknown }
+}
+
+export interface ConfigFileStatus {
+    path: string | null
+    loaded: boolean
+    unknownKeys: string[]
+    error?: string
+}
+
+let lastStatus: ConfigFileStatus = { path: null, loaded: true, unknownKeys: [] }
+
+/**
+ * 最近一次加载配置文件的结果（未配置文件时视为加载成功）
+ */
+export function getConfigFileStatus(): ConfigFileStatus {
+    return lastStatus
+}
+
+/**
+ * 读取配置文件；失败时记录错误并返回空配置（不阻止启动）
+ */
+export function loadConfigFile(path: string | undefined): Record<string, string> {
+    if (!path) {
+        lastStatus = { path: null, loaded: true, unknownKeys: [] }
+        return {}
+    }
+    try {
+        const { values, unknown } = parseConfigFile(readFileSync(path, "utf-8"))
+        for (const key of unknown) {
+            consola.warn(`Config ${path}: unknown key "${key}" ignored`)
+        }
+        lastStatus = { path, loaded: true, unknownKeys: unknown }
+        return values
+    } catch (error) {
+        consola.error(`Failed to load config ${path}:`, (error as Error).message)
+        lastStatus = { path, loaded: false, unknownKeys: [], error: (error as Error).message }
+        return {}
+    }
+}
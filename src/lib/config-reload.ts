@@ -0,0 +1,108 @@
+/**
+ * 🆕 配置热加载
+ * 收到 SIGHUP（或 ANTI_API_CONFIG_WATCH=1 时配置文件变化）后重新读取 ANTI_API_CONFIG
+ * 可热加载：端点、请求间隔、调用方白名单、状态码/模型映射等按请求读取的配置
+ * 需重启：监听端口、路由前缀、指标后端等启动时确定的配置（变更会被忽略并告警）
+ * 🆕 也可通过 POST /admin/reload 触发（返回变更前后的值）；配置文件解析失败时保留当前配置，不做部分替换
+ */
+
+import { watchFile } from "fs"
+import consola from "consola"
+import { ENV_PREFIX, getConfigFileStatus, loadConfigFile } from "./config-file"
+import { envBool, getFileConfigValues, readEnv, setFileConfigValues } from "./env"
+import { getMinRequestIntervalMs, rateLimiter } from "./rate-limiter"
+import { getAutoTuneSnapshot } from "./auto-tune"
+
+const RESTART_REQUIRED_KEYS = new Set([
+    "BASE_PATH",
+    "CONFIG_WATCH",
+    "DATA_DIR",
+    "INFRA_AT_ROOT",
+    "LOG_LINES",
+    "METRICS_BACKEND",
+    "RECENT_REQUESTS",
+    "STATSD_ADDR",
+].map(key => ENV_PREFIX + key))
+
+export interface ConfigValueChange {
+    old: string | null
+    new: string | null
+}
+
+export interface ConfigReloadResult {
+    path: string | null
+    /** 已生效的变更键 */
+    changed: string[]
+    /** 需要重启才能生效、本次被忽略的键 */
+    ignored: string[]
+    /** 🆕 已生效变更的前后值（密钥类配置打码） */
+    diff: Record<string, ConfigValueChange>
+    /** 🆕 解析失败时的错误，此时未应用任何变更 */
+    error?: string
+}
+
+const SECRET_KEY = /KEY|TOKEN|SECRET|PASSWORD/
+
+function displayValue(key: string, value: string | undefined): string | null {
+    if (value === undefined) return null
+    return SECRET_KEY.test(key.slice(ENV_PREFIX.length)) ? "***" : value
+}
+
+export function diffConfigKeys(previous: Record<string, string>, next: Record<string, string>): string[] {
+    const keys = new Set([...Object.keys(previous), ...Object.keys(next)])
+    return Array.from(keys).filter(key => previous[key] !== next[key]).sort()
+}
+
+/**
+ * 重新读取配置文件并原子替换；需重启的键保留旧值
+ */
+export function reloadConfig(): ConfigReloadResult {
+    const path = readEnv("ANTI_API_CONFIG") ?? null
+    const previous = getFileConfigValues()
+    const next = { ...loadConfigFile(path ?? undefined) }
+    const status = getConfigFileStatus()
+    if (status.error) {
+        consola.error(`Config reload aborted, keeping current config: ${status.error}`)
+        return { path, changed: [], ignored: [], diff: {}, error: status.error }
+    }
+
+    const changed: string[] = []
+    const ignored: string[] = []
+    const diff: Record<string, ConfigValueChange> = {}
+    for (const key of diffConfigKeys(previous, next)) {
+        if (RESTART_REQUIRED_KEYS.has(key)) {
+            ignored.push(key)
+            if (previous[key] === undefined) delete next[key]
+            else next[key] = previous[key]
+        } else {
+            changed.push(key)
+            diff[key] = { old: displayValue(key, previous[key]), new: displayValue(key, next[key]) }
+        }
+    }
+
+    setFileConfigValues(next)
+    // 🆕 自动调节开启时间隔由调节器接管
+    if (!getAutoTuneSnapshot()) rateLimiter.setMinInterval(getMinRequestIntervalMs())
+
+    for (const key of ignored) {
+        consola.warn(`Config reload: ${key} requires a restart, change ignored`)
+    }
+    consola.info(`Config reloaded${path ? ` from ${path}` : ""}: ${changed.length} changed${changed.length ? ` (${changed.join(", ")})` : ""}`)
+    return { path, changed, ignored, diff }
+}
+
+/**
+ * 注册 SIGHUP 与可选的文件监听
+ */
+export function startConfigReloadListeners(): void {
+    process.on("SIGHUP", () => {
+        reloadConfig()
+    })
+
+    const path = readEnv("ANTI_API_CONFIG")
+    if (path && envBool("ANTI_API_CONFIG_WATCH")) {
+        watchFile(path, { interval: 2000, persistent: false }, (current, previous) => {
+            if (current.mtimeMs !== previous.mtimeMs) reloadConfig()
+        })
+    }
+}
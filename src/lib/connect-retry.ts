@@ -0,0 +1,48 @@
+/**
+ * 🆕 仅针对建连失败的重试（ANTI_API_RETRY_CONNECT_ONLY=1）
+ * DNS 解析失败、连接被拒、无法打开套接字等错误发生在请求字节发出之前，上游不可能已开始生成，
+ * 因此在同一端点立即重试一次是安全的，不会造成重复生成；与 429/5xx 重试策略相互独立
+ * 证书校验失败属于确定性错误、连接重置与超时无法确认请求是否已发出，均不在重试范围内
+ */
+
+import consola from "consola"
+import { envBool } from "./env"
+import { incrementCounter } from "./metrics"
+
+const CONNECT_ERROR_CODES = new Set([
+    "ECONNREFUSED",
+    "ConnectionRefused",
+    "FailedToOpenSocket",
+    "ENOTFOUND",
+    "EAI_AGAIN",
+    "DNSException",
+    "EHOSTUNREACH",
+    "ENETUNREACH",
+])
+
+export function isConnectRetryEnabled(): boolean {
+    return envBool("ANTI_API_RETRY_CONNECT_ONLY")
+}
+
+/**
+ * 是否为发送前的建连错误
+ */
+export function isConnectError(error: unknown): boolean {
+    const err = error as { code?: string; name?: string } | null
+    if (!err || err.name === "AbortError") return false
+    return !!err.code && CONNECT_ERROR_CODES.has(err.code)
+}
+
+/**
+ * 执行一次上游调用；开启时建连失败在同一端点重试一次（请求已中止时不再重试）
+ */
+export async function withConnectRetry<T>(attempt: () => Promise<T>, signal?: AbortSignal, label: string = "upstream"): Promise<T> {
+    try {
+        return await attempt()
+    } catch (error) {
+        if (!isConnectRetryEnabled() || !isConnectError(error) || signal?.aborted) throw error
+        incrementCounter("connect_retries")
+        consola.debug(`Connect to ${label} failed (${(error as { code?: string }).code}), retrying once`)
+        return attempt()
+    }
+}
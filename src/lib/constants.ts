@@ -63,6 +63,9 @@ export const MAX_SANITIZED_STRING_LENGTH = 10000
 /** Maximum max_tokens value */
 export const MAX_TOKENS_LIMIT = 1000000
 
+/** Default maximum OAuth token length (override with ANTI_API_MAX_TOKEN_LEN) */
+export const DEFAULT_MAX_TOKEN_LENGTH = 8192
+
 // ============================================
 // Rate Limit Durations
 // ============================================
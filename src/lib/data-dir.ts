@@ -1,10 +1,11 @@
 import { existsSync, mkdirSync } from "fs"
 import { homedir } from "os"
 import { join } from "path"
+import { readEnv } from "./env"
 
 export function getDataDir(): string {
-    const override = process.env.ANTI_API_DATA_DIR
-    if (override && override.trim()) {
+    const override = readEnv("ANTI_API_DATA_DIR")
+    if (override) {
         return override
     }
     const home = process.env.HOME || process.env.USERPROFILE || homedir()
@@ -0,0 +1,75 @@
+/**
+ * 端到端请求截止时间
+ * ANTI_API_TOTAL_DEADLINE_SECS（默认 0 = 关闭），从请求进入开始计时，
+ * 覆盖限流等待、并发许可等待与上游请求；超时返回 504 deadline_exceeded
+ */
+
+import { envInt } from "./env"
+import { AntigravityError } from "./error"
+import { getRequestContext } from "./request-context"
+
+export function getTotalDeadlineMs(): number {
+    return Math.max(0, envInt("ANTI_API_TOTAL_DEADLINE_SECS", 0)) * 1000
+}
+
+export function deadlineExceededError(): AntigravityError {
+    return new AntigravityError("Request deadline exceeded", "deadline_exceeded", 504)
+}
+
+/**
+ * 剩余时间预算（毫秒）；未配置截止时间或不在请求上下文中时返回 null
+ */
+export function getRemainingBudgetMs(): number | null {
+    const totalMs = getTotalDeadlineMs()
+    const ctx = getRequestContext()
+    if (totalMs <= 0 || !ctx) return null
+    return ctx.startedAt + totalMs - Date.now()
+}
+
+export function isDeadlineExceeded(): boolean {
+    const remaining = getRemainingBudgetMs()
+    return remaining !== null && remaining <= 0
+}
+
+export function checkDeadline(): void {
+    if (isDeadlineExceeded()) throw deadlineExceededError()
+}
+
+/**
+ * 将单次超时收缩到剩余预算内
+ */
+export function clampToDeadline(timeoutMs: number): number {
+    const remaining = getRemainingBudgetMs()
+    if (remaining === null) return timeoutMs
+    return Math.max(0, Math.min(timeoutMs, remaining))
+}
+
+/**
+ * 在剩余预算内等待 promise；超时抛出 deadline_exceeded
+ * onLate: 超时后 promise 才完成时的清理（如释放迟到的许可）
+ */
+export async function withDeadline<T>(promise: Promise<T>, onLate?: (value: T) => void): Promise<T> {
+    const remaining = getRemainingBudgetMs()
+    if (remaining === null) return promise
+    if (remaining <= 0) {
+        if (onLate) promise.then(onLate, () => { })
+        throw deadlineExceededError()
+    }
+
+    let timer: ReturnType<typeof setTimeout> | undefined
+    let timedOut = false
+    const timeout = new Promise<never>((_, reject) => {
+        timer = setTimeout(() => {
+            timedOut = true
+            reject(deadlineExceededError())
+        }, remaining)
+    })
+    if (onLate) {
+        promise.then(value => { if (timedOut) onLate(value) }, () => { })
+    }
+    try {
+        return await Promise.race([promise, timeout])
+    } finally {
+        clearTimeout(timer)
+    }
+}
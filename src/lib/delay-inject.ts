@@ -0,0 +1,56 @@
+/**
+ * 🆕 上游调用前的人为延迟（仅用于测试超时、截止时间与首字超时等逻辑，默认关闭）
+ * - ANTI_API_INJECT_DELAY_MS: 每次上游调用发出前固定等待的毫秒数（只读取环境变量，不读取配置文件，避免误开启）
+ * - 请求头 X-Inject-Delay-Ms: <毫秒> 可对单个请求指定延迟，需携带有效管理密钥，否则忽略
+ * 延迟计入单次调用超时与端到端截止时间，超时/中止时与真实慢端点一样失败；每次注入计数 delays_injected
+ */
+
+import type { Context } from "hono"
+import consola from "consola"
+import { hasValidAdminKey } from "./admin-auth"
+import { incrementCounter } from "./metrics"
+
+const MAX_INJECTED_DELAY_MS = 10 * 60 * 1000
+
+function parseDelay(raw: string | undefined): number {
+    const value = Number.parseInt((raw || "").trim(), 10)
+    if (!Number.isFinite(value) || value <= 0) return 0
+    return Math.min(value, MAX_INJECTED_DELAY_MS)
+}
+
+export function getConfiguredDelayMs(): number {
+    return parseDelay(process.env.ANTI_API_INJECT_DELAY_MS)
+}
+
+export function readDelayHeader(c: Context): string | undefined {
+    const value = c.req.header("X-Inject-Delay-Ms")
+    return value && hasValidAdminKey(c) ? value : undefined
+}
+
+/**
+ * 本次调用的延迟：请求头优先，其次全局配置
+ */
+export function getInjectedDelayMs(header: string | undefined): number {
+    return parseDelay(header) || getConfiguredDelayMs()
+}
+
+/**
+ * 可中止的等待；中止时抛出 AbortError，与 fetch 被中止的表现一致
+ */
+export async function injectDelay(ms: number, signal: AbortSignal): Promise<void> {
+    if (ms <= 0) return
+    incrementCounter("delays_injected")
+    consola.info(`[DelayInject] sleeping ${ms}ms before upstream call`)
+    await new Promise<void>((resolve, reject) => {
+        const abort = () => {
+            clearTimeout(timer)
+            reject(Object.assign(new Error("Injected delay aborted"), { name: "AbortError" }))
+        }
+        const timer = setTimeout(() => {
+            signal.removeEventListener("abort", abort)
+            resolve()
+        }, ms)
+        if (signal.aborted) abort()
+        else signal.addEventListener("abort", abort, { once: true })
+    })
+}
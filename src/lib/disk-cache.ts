@@ -0,0 +1,172 @@
+/**
+ * 🆕 磁盘响应缓存（可选，重启后仍然有效）
+ * - ANTI_API_DISK_CACHE_DIR: 缓存目录，设置后开启（默认关闭，与其他缓存相互独立）
+ * - ANTI_API_DISK_CACHE_TTL_SECS: 条目有效期（默认 86400）
+ * - ANTI_API_DISK_CACHE_MAX_ENTRIES: 条目上限（默认 1000），超出时按最近访问时间（mtime）淘汰最久未用的条目
+ * 只缓存确定性请求：非流式且 temperature 显式为 0；只缓存 200 JSON 响应，错误永不缓存
+ * 缓存键为路径 + 规范化请求体的 SHA-256，不包含任何凭据；条目通过临时文件 + rename 原子写入
+ * 命中时带 X-Cache: HIT，未命中写入后带 X-Cache: MISS
+ * 🆕 stale-while-error：ANTI_API_DISK_CACHE_STALE_MAX_AGE_SECS > 0（默认 0 = 关闭）时，过期条目暂不删除；
+ *   全新调用因上游不可用失败（所有端点均失败或上游 502/503/504）且条目过期（createdAt + TTL）后不超过该值时，改为返回该条目，响应体带 stale: true、X-Cache: STALE；
+ *   其他 5xx（如普通 500）照常返回错误
+ */
+
+import type { Context, Next } from "hono"
+import { createHash, randomUUID } from "crypto"
+import { mkdir, readdir, readFile, rename, stat, unlink, utimes, writeFile } from "fs/promises"
+import { join } from "path"
+import consola from "consola"
+import { envInt, envString } from "./env"
+import { incrementCounter } from "./metrics"
+import { resolveStreamMode } from "./stream-mode"
+
+interface DiskCacheEntry {
+    createdAt: number
+    status: number
+    contentType: string
+    body: string
+}
+
+const ENTRY_SUFFIX = ".json"
+
+export function getDiskCacheDir(): string | undefined {
+    return envString("ANTI_API_DISK_CACHE_DIR")
+}
+
+function getTtlMs(): number {
+    return Math.max(0, envInt("ANTI_API_DISK_CACHE_TTL_SECS", 86400)) * 1000
+}
+
+function getStaleMaxAgeMs(): number {
+    return Math.max(0, envInt("ANTI_API_DISK_CACHE_STALE_MAX_AGE_SECS", 0)) * 1000
+}
+
+/**
+ * 给缓存的 JSON 响应体加上 stale 标记
+ */
+export function markStale(body: string): string {
+    try {
+        const parsed = JSON.parse(body)
+        if (parsed && typeof parsed === "object" && !Array.isArray(parsed)) return JSON.stringify({ ...parsed, stale: true })
+    } catch {
+        // 非 JSON 原样返回
+    }
+    return body
+}
+
+// 所有端点均失败时为 503（AllEndpointsFailedError），上游 502/503/504 原样透传
+const UPSTREAM_UNAVAILABLE_STATUSES = new Set([502, 503, 504])
+
+function getMaxEntries(): number {
+    return Math.max(1, envInt("ANTI_API_DISK_CACHE_MAX_ENTRIES", 1000))
+}
+
+/**
+ * 键顺序无关的 JSON 序列化
+ */
+export function canonicalJson(value: unknown): string {
+    if (Array.isArray(value)) return `[${value.map(canonicalJson).join(",")}]`
+    if (value && typeof value === "object") {
+        const keys = Object.keys(value as Record<string, unknown>).sort()
+        return `{${keys.map(key => `${JSON.stringify(key)}:${canonicalJson((value as Record<string, unknown>)[key])}`).join(",")}}`
+    }
+    return JSON.stringify(value) ?? "null"
+}
+
+export function isDeterministicRequest(body: Record<string, unknown>, accept: string | undefined): boolean {
+    if (resolveStreamMode(body.stream, accept)) return false
+    return body.temperature === 0
+}
+
+export function buildDiskCacheKey(path: string, body: unknown): string {
+    return createHash("sha256").update(path).update("\n").update(canonicalJson(body)).digest("hex")
+}
+
+async function readEntry(file: string): Promise<DiskCacheEntry | null> {
+    try {
+        return JSON.parse(await readFile(file, "utf-8")) as DiskCacheEntry
+    } catch {
+        return null
+    }
+}
+
+async function writeEntryAtomic(dir: string, file: string, entry: DiskCacheEntry): Promise<void> {
+    await mkdir(dir, { recursive: true })
+    const tmp = `${file}.${randomUUID()}.tmp`
+    await writeFile(tmp, JSON.stringify(entry), "utf-8")
+    await rename(tmp, file)
+}
+
+/**
+ * 淘汰最久未访问的条目，使总数不超过上限
+ */
+export async function evictDiskCache(dir: string, maxEntries: number): Promise<number> {
+    const names = (await readdir(dir).catch(() => [] as string[])).filter(name => name.endsWith(ENTRY_SUFFIX))
+    if (names.length <= maxEntries) return 0
+    const files = await Promise.all(names.map(async name => {
+        const file = join(dir, name)
+        const info = await stat(file).catch(() => null)
+        return { file, mtimeMs: info?.mtimeMs ?? 0 }
+    }))
+    files.sort((a, b) => a.mtimeMs - b.mtimeMs)
+    const victims = files.slice(0, files.length - maxEntries)
+    await Promise.all(victims.map(victim => unlink(victim.file).catch(() => { })))
+    return victims.length
+}
+
+export async function diskCacheGuard(c: Context, next: Next) {
+    const dir = getDiskCacheDir()
+    if (!dir || c.req.method !== "POST") return next()
+
+    let body: Record<string, unknown>
+    try {
+        body = JSON.parse(await c.req.text())
+    } catch {
+        return next()
+    }
+    if (!body || typeof body !== "object" || !isDeterministicRequest(body, c.req.header("Accept"))) return next()
+
+    const file = join(dir, buildDiskCacheKey(c.req.path, body) + ENTRY_SUFFIX)
+    const cached = await readEntry(file)
+    const ttlMs = getTtlMs()
+    const staleMaxAgeMs = getStaleMaxAgeMs()
+    // 容忍期从条目过期时刻算起，而不是从写入时刻
+    const isWithinStaleWindow = (entry: DiskCacheEntry) => Date.now() - (entry.createdAt + ttlMs) < staleMaxAgeMs
+    if (cached) {
+        const ageMs = Date.now() - cached.createdAt
+        if (ageMs < ttlMs) {
+            const now = new Date()
+            await utimes(file, now, now).catch(() => { })
+            incrementCounter("disk_cache_hits")
+            return new Response(cached.body, {
+                status: cached.status,
+                headers: { "Content-Type": cached.contentType, "X-Cache": "HIT" },
+            })
+        }
+        if (!isWithinStaleWindow(cached)) await unlink(file).catch(() => { })
+    }
+    incrementCounter("disk_cache_misses")
+
+    await next()
+    // 🆕 上游不可用时回退到过期但仍在容忍期内的条目
+    if (UPSTREAM_UNAVAILABLE_STATUSES.has(c.res.status) && cached && isWithinStaleWindow(cached)) {
+        incrementCounter("disk_cache_stale_served")
+        consola.warn(`Serving stale cached response (${Math.round((Date.now() - cached.createdAt) / 1000)}s old) after upstream ${c.res.status}`)
+        c.res = new Response(markStale(cached.body), {
+            status: cached.status,
+            headers: { "Content-Type": cached.contentType, "X-Cache": "STALE" },
+        })
+        return
+    }
+    const contentType = c.res.headers.get("Content-Type") || ""
+    if (c.res.status !== 200 || !contentType.includes("application/json")) return
+
+    const text = await c.res.clone().text()
+    try {
+        await writeEntryAtomic(dir, file, { createdAt: Date.now(), status: 200, contentType, body: text })
+        await evictDiskCache(dir, getMaxEntries())
+        c.res.headers.set("X-Cache", "MISS")
+    } catch (error) {
+        consola.warn(`Disk cache write failed: ${(error as Error).message}`)
+    }
+}
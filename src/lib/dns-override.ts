@@ -0,0 +1,66 @@
+/**
+ * 🆕 上游主机解析覆盖（类似 curl --resolve）
+ * ANTI_API_RESOLVE: 逗号分隔的 `host:ip` 或 `host:port:ip`（IPv6 用方括号，如 `example.com:[::1]`）
+ * 命中时请求改为直连该 IP，同时保留原 Host 头与 TLS SNI / 证书校验主机名；未配置时使用系统 DNS
+ * 配置在启动时校验并打印，非法时拒绝启动
+ */
+
+import { isIP } from "net"
+import { readEnv } from "./env"
+
+export interface ResolveOverride {
+    host: string
+    /** 未指定端口时对所有端口生效 */
+    port?: number
+    address: string
+}
+
+export interface ResolvedTarget {
+    url: string
+    /** 原始 Host 头（含非默认端口） */
+    host: string
+    /** 原始主机名，用作 TLS SNI 与证书校验 */
+    serverName: string
+}
+
+function stripBrackets(value: string): string {
+    return value.startsWith("[") && value.endsWith("]") ? value.slice(1, -1) : value
+}
+
+export function parseResolveOverrides(raw: string): ResolveOverride[] {
+    const overrides: ResolveOverride[] = []
+    for (const item of raw.split(",").map(part => part.trim()).filter(Boolean)) {
+        // host:[v6] / host:port:[v6] / host:v4 / host:port:v4
+        const match = item.match(/^([^:\s]+)(?::(\d{1,5}))?:(\[[0-9a-fA-F:.]+\]|[0-9.]+)$/)
+        const address = match ? stripBrackets(match[3]) : ""
+        const port = match?.[2] ? Number(match[2]) : undefined
+        if (!match || isIP(address) === 0 || (port !== undefined && (port < 1 || port > 65535))) {
+            throw new Error(`Invalid ANTI_API_RESOLVE entry "${item}" (expected host:ip or host:port:ip)`)
+        }
+        overrides.push({ host: match[1].toLowerCase(), ...(port !== undefined ? { port } : {}), address })
+    }
+    return overrides
+}
+
+let cached: { raw: string; overrides: ResolveOverride[] } | null = null
+
+export function getResolveOverrides(): ResolveOverride[] {
+    const raw = readEnv("ANTI_API_RESOLVE") || ""
+    if (cached?.raw !== raw) cached = { raw, overrides: parseResolveOverrides(raw) }
+    return cached.overrides
+}
+
+/**
+ * 把 URL 的主机替换为覆盖的 IP；未命中时返回 null
+ */
+export function applyResolveOverride(url: string, overrides: ResolveOverride[] = getResolveOverrides()): ResolvedTarget | null {
+    if (overrides.length === 0) return null
+    const parsed = new URL(url)
+    const host = parsed.hostname.toLowerCase()
+    const port = Number(parsed.port || (parsed.protocol === "https:" ? 443 : 80))
+    const override = overrides.find(entry => entry.host === host && (entry.port === undefined || entry.port === port))
+    if (!override) return null
+    const originalHost = parsed.host
+    parsed.hostname = isIP(override.address) === 6 ? `[${override.address}]` : override.address
+    return { url: parsed.toString(), host: originalHost, serverName: host }
+}
@@ -0,0 +1,55 @@
+/**
+ * 🆕 "所有端点失败"的原因汇总
+ * 每次上游调用失败时记录到请求上下文；请求最终失败时按记录归类：
+ * 全部超时 all_timeout、全部 5xx all_server_error、全部建连失败 all_connect_error、
+ * 🆕 全部响应体被截断 incomplete_response，否则 mixed
+ * 最后一个错误为 4xx/429 等调用方可处理的上游错误时原样返回，不做汇总
+ */
+
+import { AllEndpointsFailedError, UpstreamError, type AllEndpointsFailedCode } from "./error"
+import { getRequestContext, type EndpointFailure, type EndpointFailureKind } from "./request-context"
+
+const MAX_RECORDED_FAILURES = 20
+const MAX_MESSAGE_LENGTH = 200
+
+export function recordEndpointFailure(endpoint: string, kind: EndpointFailureKind, detail: { status?: number; message?: string } = {}): void {
+    const ctx = getRequestContext()
+    if (!ctx) return
+    const failures = ctx.endpointFailures ?? (ctx.endpointFailures = [])
+    if (failures.length >= MAX_RECORDED_FAILURES) return
+    failures.push({
+        endpoint,
+        kind,
+        ...(detail.status !== undefined ? { status: detail.status } : {}),
+        ...(detail.message ? { message: detail.message.slice(0, MAX_MESSAGE_LENGTH) } : {}),
+    })
+}
+
+export function statusFailureKind(status: number): EndpointFailureKind {
+    if (status >= 200 && status < 300) return "other"
+    return status >= 500 ? "server_error" : "client_error"
+}
+
+export function classifyEndpointFailures(failures: EndpointFailure[]): AllEndpointsFailedCode {
+    const kinds = new Set(failures.map(failure => failure.kind))
+    if (kinds.size !== 1) return "mixed"
+    switch (failures[0].kind) {
+        case "timeout": return "all_timeout"
+        case "server_error": return "all_server_error"
+        case "connect_error": return "all_connect_error"
+        case "incomplete_response": return "incomplete_response"
+        default: return "mixed"
+    }
+}
+
+/**
+ * 端点循环结束仍未成功时调用：有失败记录且最后的错误不是 4xx 上游错误时，转换为 503 AllEndpointsFailedError
+ */
+export function toAllEndpointsFailedError(fallback: Error): Error {
+    const failures = getRequestContext()?.endpointFailures
+    if (!failures?.length) return fallback
+    if (fallback instanceof UpstreamError && fallback.status < 500) return fallback
+    const body = fallback instanceof UpstreamError ? fallback.body : fallback.message
+    const upstreamRequestId = fallback instanceof UpstreamError ? fallback.upstreamRequestId : undefined
+    return new AllEndpointsFailedError(classifyEndpointFailures(failures), [...failures], body, upstreamRequestId)
+}
@@ -0,0 +1,56 @@
+/**
+ * 🆕 上游端点健康状态（被动观测：记录每个端点最近一次真实请求的结果）
+ */
+
+export interface EndpointObservation {
+    ok: boolean
+    status: number | null
+    latencyMs: number
+    checkedAt: number
+    error?: string
+}
+
+export interface EndpointHealth {
+    endpoint: string
+    state: "healthy" | "unhealthy" | "unknown"
+    lastStatus: number | null
+    latencyMs: number | null
+    checkedAt: string | null
+    error?: string
+}
+
+const observations = new Map<string, EndpointObservation>()
+
+/**
+ * 5xx 与网络错误视为端点不健康；4xx/429 说明端点可达
+ */
+export function recordEndpointResult(endpoint: string, status: number | null, latencyMs: number, error?: string): void {
+    observations.set(endpoint, {
+        ok: status !== null && status < 500,
+        status,
+        latencyMs: Math.round(latencyMs),
+        checkedAt: Date.now(),
+        ...(error ? { error } : {}),
+    })
+}
+
+export function getEndpointHealth(endpoints: string[]): EndpointHealth[] {
+    return endpoints.map(endpoint => {
+        const observation = observations.get(endpoint)
+        if (!observation) {
+            return { endpoint, state: "unknown", lastStatus: null, latencyMs: null, checkedAt: null }
+        }
+        return {
+            endpoint,
+            state: observation.ok ? "healthy" : "unhealthy",
+            lastStatus: observation.status,
+            latencyMs: observation.latencyMs,
+            checkedAt: new Date(observation.checkedAt).toISOString(),
+            ...(observation.error ? { error: observation.error } : {}),
+        }
+    })
+}
+
+export function resetEndpointHealth(): void {
+    observations.clear()
+}
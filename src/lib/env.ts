@@ -0,0 +1,68 @@
+/**
+ * 环境变量读取工具
+ * 每次调用时读取，便于运行时调整配置
+ * 🆕 环境变量未设置时回落到 ANTI_API_CONFIG 配置文件中的值
+ */
+
+import { loadConfigFile } from "./config-file"
+
+let fileConfig: Record<string, string> | null = null
+
+function getFileConfig(): Record<string, string> {
+    if (fileConfig === null) {
+        fileConfig = loadConfigFile(process.env.ANTI_API_CONFIG?.trim())
+    }
+    return fileConfig
+}
+
+/**
+ * 🆕 当前配置文件值快照 / 整体替换（热加载使用，替换是原子的）
+ */
+export function getFileConfigValues(): Record<string, string> {
+    return getFileConfig()
+}
+
+export function setFileConfigValues(values: Record<string, string>): void {
+    fileConfig = values
+}
+
+function normalize(value: string | undefined): string | undefined {
+    if (value === undefined) return undefined
+    const trimmed = value.trim()
+    return trimmed ? trimmed : undefined
+}
+
+export function readEnv(name: string): string | undefined {
+    return normalize(process.env[name]) ?? normalize(getFileConfig()[name])
+}
+
+export function envString(name: string, fallback: string): string
+export function envString(name: string): string | undefined
+export function envString(name: string, fallback?: string): string | undefined {
+    return readEnv(name) ?? fallback
+}
+
+export function envInt(name: string, fallback: number): number {
+    const raw = readEnv(name)
+    if (raw === undefined) return fallback
+    const parsed = Number.parseInt(raw, 10)
+    return Number.isFinite(parsed) ? parsed : fallback
+}
+
+export function envBool(name: string, fallback: boolean = false): boolean {
+    const raw = readEnv(name)
+    if (raw === undefined) return fallback
+    const lower = raw.toLowerCase()
+    if (lower === "1" || lower === "true" || lower === "yes" || lower === "on") return true
+    if (lower === "0" || lower === "false" || lower === "no" || lower === "off") return false
+    return fallback
+}
+
+/**
+ * 逗号分隔列表，忽略空项
+ */
+export function envList(name: string): string[] {
+    const raw = readEnv(name)
+    if (!raw) return []
+    return raw.split(",").map(item => item.trim()).filter(Boolean)
+}
@@ -0,0 +1,40 @@
+/**
+ * 🆕 上游 4xx 时在错误响应中回显实际发送的请求体（调试用，默认关闭）
+ * - ANTI_API_DEBUG_ECHO_ENVELOPE=1: 全局开启
+ * - 或请求头 X-Debug-Echo-Envelope: 1，且携带有效管理密钥
+ * 成功响应永远不回显；token/密钥类字段会被替换为 [REDACTED]
+ */
+
+import type { Context } from "hono"
+import { hasValidAdminKey } from "./admin-auth"
+import { envBool } from "./env"
+
+const SENSITIVE_KEY = /token|secret|password|authorization|api[_-]?key|credential/i
+const MAX_ECHO_BYTES = 64 * 1024
+
+export function isEnvelopeEchoEnabled(c: Context): boolean {
+    if (envBool("ANTI_API_DEBUG_ECHO_ENVELOPE")) return true
+    return c.req.header("X-Debug-Echo-Envelope") === "1" && hasValidAdminKey(c)
+}
+
+function redactValue(value: unknown): unknown {
+    if (Array.isArray(value)) return value.map(redactValue)
+    if (!value || typeof value !== "object") return value
+    const result: Record<string, unknown> = {}
+    for (const [key, item] of Object.entries(value)) {
+        result[key] = SENSITIVE_KEY.test(key) ? "[REDACTED]" : redactValue(item)
+    }
+    return result
+}
+
+/**
+ * 解析并脱敏请求体；超过 64 KiB 时截断为字符串
+ */
+export function redactEnvelope(body: string): unknown {
+    if (body.length > MAX_ECHO_BYTES) return `${body.slice(0, MAX_ECHO_BYTES)}…[truncated]`
+    try {
+        return redactValue(JSON.parse(body))
+    } catch {
+        return "[unparseable envelope]"
+    }
+}
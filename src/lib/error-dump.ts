@@ -0,0 +1,65 @@
+/**
+ * 失败请求转储
+ * 设置 ANTI_API_ERROR_DUMP_DIR 后，每个 429/5xx/failover 事件追加一行 JSON 到按天滚动的文件
+ * （errors-YYYY-MM-DD.jsonl）；成功请求不记录，token 永不写入
+ */
+
+import { appendFile, mkdir } from "fs/promises"
+import { createHash } from "crypto"
+import { join } from "path"
+import { envString } from "./env"
+
+export interface ErrorDumpRecord {
+    event: "upstream_error" | "failover"
+    model?: string
+    project?: string
+    endpoint?: string
+    status?: number
+    elapsedMs?: number
+    retryAfterMs?: number | null
+    requestId?: string
+    upstreamRequestId?: string
+    account?: string
+    detail?: string
+}
+
+/**
+ * 短哈希，用于在转储中区分项目/账号而不暴露原值
+ */
+export function shortHash(value: string | undefined): string | undefined {
+    if (!value) return undefined
+    return createHash("sha256").update(value).digest("hex").slice(0, 12)
+}
+
+export function shouldDumpStatus(status: number): boolean {
+    return status === 429 || status >= 500
+}
+
+let ensuredDir: string | null = null
+
+export function dumpErrorEvent(record: ErrorDumpRecord): void {
+    const dir = envString("ANTI_API_ERROR_DUMP_DIR")
+    if (!dir) return
+
+    const now = new Date()
+    const line = JSON.stringify({
+        ts: now.toISOString(),
+        ...record,
+        project: shortHash(record.project),
+        account: shortHash(record.account),
+        detail: record.detail?.slice(0, 500),
+    }) + "\n"
+    const file = join(dir, `errors-${now.toISOString().slice(0, 10)}.jsonl`)
+
+    const write = async () => {
+        if (ensuredDir !== dir) {
+            await mkdir(dir, { recursive: true })
+            ensuredDir = dir
+        }
+        await appendFile(file, line)
+    }
+    write().catch(() => {
+        // 转储失败不影响请求
+        ensuredDir = null
+    })
+}
@@ -4,6 +4,12 @@
 
 import type { Context } from "hono"
 import type { ContentfulStatusCode } from "hono/utils/http-status"
+import consola from "consola"
+import { readEnv } from "./env"
+import { HTTPException } from "hono/http-exception"
+import { incrementCounter } from "./metrics"
+import { getRequestContext, type EndpointFailure } from "./request-context"
+import { redactEnvelope } from "./envelope-echo"
 
 export class HTTPError extends Error {
     response: Response
@@ -16,10 +22,15 @@ export class HTTPError extends Error {
 
 export class AntigravityError extends Error {
     code: string
+    status: number
+    /** 🆕 建议客户端重试前等待的毫秒数（返回 Retry-After） */
+    retryAfterMs?: number
 
-    constructor(message: string, code: string = "antigravity_error") {
+    constructor(message: string, code: string = "antigravity_error", status: number = 500, retryAfterMs?: number) {
         super(message)
         this.code = code
+        this.status = status
+        this.retryAfterMs = retryAfterMs
     }
 }
 
@@ -28,13 +39,46 @@ export class UpstreamError extends Error {
     provider: string
     body: string
     retryAfter?: string
+    /** 上游返回的 x-goog-request-id，便于向 Google 提交工单 */
+    upstreamRequestId?: string
 
-    constructor(provider: string, status: number, body: string, retryAfter?: string) {
+    constructor(provider: string, status: number, body: string, retryAfter?: string, upstreamRequestId?: string) {
         super(`${provider} upstream error (${status})`)
         this.status = status
         this.provider = provider
         this.body = body
         this.retryAfter = retryAfter
+        this.upstreamRequestId = upstreamRequestId
+    }
+}
+
+/**
+ * 🆕 所有端点均失败：状态码固定 503，error_code 为主要原因
+ * （all_timeout / all_server_error / all_connect_error / mixed），并附带每次调用的失败摘要
+ */
+export type AllEndpointsFailedCode = "all_timeout" | "all_server_error" | "all_connect_error" | "incomplete_response" | "mixed"
+
+export class AllEndpointsFailedError extends UpstreamError {
+    errorCode: AllEndpointsFailedCode
+    endpoints: EndpointFailure[]
+
+    constructor(errorCode: AllEndpointsFailedCode, endpoints: EndpointFailure[], body: string, upstreamRequestId?: string) {
+        super("antigravity", 503, body, undefined, upstreamRequestId)
+        this.message = `All endpoints failed (${errorCode})`
+        this.errorCode = errorCode
+        this.endpoints = endpoints
+    }
+}
+
+/**
+ * 🆕 本地并发上限触发（fast-fail 模式），不代表上游限流
+ */
+export class ConcurrencyLimitError extends Error {
+    scope: "global" | "account"
+
+    constructor(scope: "global" | "account", message: string) {
+        super(message)
+        this.scope = scope
     }
 }
 
@@ -112,14 +156,84 @@ export function summarizeUpstream429(error: UpstreamError): { reason: Upstream42
     }
 }
 
-export function summarizeUpstreamError(error: UpstreamError): { message: string; reason?: string } {
+export type UpstreamAuthErrorCode = "token_invalid" | "access_denied"
+export type Upstream403Reason = "project_config" | "permission_denied"
+
+const PROJECT_CONFIG_REASONS = new Set(["SERVICE_DISABLED", "BILLING_DISABLED", "CONSUMER_INVALID", "API_KEY_SERVICE_BLOCKED"])
+
+/**
+ * 🆕 区分 401（令牌无效/过期，可刷新）与 403（访问被拒，刷新/轮换通常无效）
+ * 403 进一步区分项目配置问题（API 未启用、计费关闭等）与权限不足；两者都直接返回调用方，不重试
+ */
+export function classifyUpstreamAuthError(error: UpstreamError): { errorCode: UpstreamAuthErrorCode; reason?: Upstream403Reason } | null {
+    if (error.status === 401) return { errorCode: "token_invalid" }
+    if (error.status !== 403) return null
+    const parsed = parseUpstreamErrorBody(error.body || "")
+    const lower = (parsed.message || "").toLowerCase()
+    const projectConfig = (parsed.reason && PROJECT_CONFIG_REASONS.has(parsed.reason))
+        || lower.includes("has not been used in project")
+        || lower.includes("is disabled")
+        || lower.includes("billing")
+    return { errorCode: "access_denied", reason: projectConfig ? "project_config" : "permission_denied" }
+}
+
+export function summarizeUpstreamError(error: UpstreamError): { message: string; reason?: string; errorCode?: UpstreamAuthErrorCode | AllEndpointsFailedCode | "request_too_large" } {
+    if (error instanceof AllEndpointsFailedError) return { message: error.message, errorCode: error.errorCode }
+    // 🆕 413：请求体过大，客户端需要裁剪上下文
+    if (error.status === 413) return { message: error.body || "Request too large for upstream", errorCode: "request_too_large" }
     if (error.status === 429) {
         const summary = summarizeUpstream429(error)
         return { message: summary.message, reason: summary.reason }
     }
+    const auth = classifyUpstreamAuthError(error)
+    if (auth) return { message: error.body || error.message, reason: auth.reason, errorCode: auth.errorCode }
     return { message: error.body || error.message }
 }
 
+/**
+ * 🆕 上游 400 是否为"模型不存在"（其他 400 不算）
+ */
+export function isModelNotFoundError(error: unknown): boolean {
+    if (!(error instanceof UpstreamError) || error.status !== 400) return false
+    const parsed = parseUpstreamErrorBody(error.body || "")
+    const text = (parsed.message || "").toLowerCase()
+    if (parsed.status === "NOT_FOUND") return true
+    return text.includes("model") && (text.includes("not found") || text.includes("not supported") || text.includes("does not exist"))
+}
+
+let cachedStatusMapRaw: string | undefined
+let cachedStatusMap = new Map<number, number>()
+
+/**
+ * 🆕 解析状态码映射表，格式 "400=422;403=401"
+ */
+export function parseStatusMap(raw: string | undefined): Map<number, number> {
+    const map = new Map<number, number>()
+    if (!raw) return map
+    for (const pair of raw.split(/[;,]/)) {
+        const [from, to] = pair.split("=").map(part => Number.parseInt((part || "").trim(), 10))
+        if (!Number.isInteger(from) || !Number.isInteger(to)) continue
+        if (from < 100 || from > 599 || to < 200 || to > 599) continue
+        map.set(from, to)
+    }
+    return map
+}
+
+/**
+ * 🆕 按 ANTI_API_STATUS_MAP 映射返回给客户端的上游状态码
+ */
+export function remapUpstreamStatus(status: number): number {
+    const raw = readEnv("ANTI_API_STATUS_MAP")
+    if (raw !== cachedStatusMapRaw) {
+        cachedStatusMapRaw = raw
+        cachedStatusMap = parseStatusMap(raw)
+        if (cachedStatusMap.has(429)) {
+            consola.warn("ANTI_API_STATUS_MAP remaps 429; clients relying on 429 for backoff/rotation will no longer see it")
+        }
+    }
+    return cachedStatusMap.get(status) ?? status
+}
+
 function buildLogReason(error: unknown): string {
     if (error instanceof UpstreamError) {
         if (error.status === 429) {
@@ -129,8 +243,8 @@ function buildLogReason(error: unknown): string {
             if (summary.reason === "resource_exhausted") return "resource exhausted"
             return "rate limited"
         }
-        if (error.status === 401) return "unauthorized"
-        if (error.status === 403) return "forbidden"
+        if (error.status === 401) return "unauthorized (token_invalid)"
+        if (error.status === 403) return classifyUpstreamAuthError(error)?.reason === "project_config" ? "forbidden (project config)" : "forbidden"
         if (error.status === 404) return "not found"
         if (error.status >= 500) return "upstream error"
         return "upstream error"
@@ -144,6 +258,10 @@ function buildLogReason(error: unknown): string {
         return error.code || "antigravity error"
     }
 
+    if (error instanceof ConcurrencyLimitError) {
+        return "concurrency limit"
+    }
+
     return "internal error"
 }
 
@@ -173,32 +291,62 @@ export async function forwardError(c: Context, error: unknown) {
 
     if (error instanceof AntigravityError) {
         c.header("X-Log-Reason", buildLogReason(error))
+        if (error.retryAfterMs !== undefined) c.header("Retry-After", String(Math.max(1, Math.ceil(error.retryAfterMs / 1000))))
         return c.json(
             {
                 error: {
                     type: error.code,
                     message: error.message,
+                    ...(error.retryAfterMs !== undefined ? { retry_after_ms: error.retryAfterMs } : {}),
                 },
             },
-            500,
+            error.status as ContentfulStatusCode,
+        )
+    }
+
+    if (error instanceof ConcurrencyLimitError) {
+        c.header("X-Log-Reason", buildLogReason(error))
+        c.header("Retry-After", "1")
+        return c.json(
+            {
+                error: {
+                    type: "rate_limit_error",
+                    message: error.message,
+                    scope: error.scope,
+                },
+            },
+            429,
         )
     }
 
     if (error instanceof UpstreamError) {
         const summary = summarizeUpstreamError(error)
+        const status = remapUpstreamStatus(error.status)
+        // 🆕 调试：4xx 时附带实际发往上游的请求体（已脱敏）
+        const ctx = getRequestContext()
+        const envelope = ctx?.echoEnvelope && ctx.upstreamEnvelope && error.status >= 400 && error.status < 500
+            ? redactEnvelope(ctx.upstreamEnvelope)
+            : undefined
         c.header("X-Log-Reason", buildLogReason(error))
+        if (error.upstreamRequestId) c.header("X-Upstream-Request-Id", error.upstreamRequestId)
         return c.json(
             {
                 error: {
                     type: "upstream_error",
                     message: summary.message,
                     provider: error.provider,
+                    status_code: error.status,
+                    ...(summary.errorCode ? { error_code: summary.errorCode } : {}),
                     ...(summary.reason ? { reason: summary.reason } : {}),
+                    ...(error.upstreamRequestId ? { upstream_request_id: error.upstreamRequestId } : {}),
+                    ...(error instanceof AllEndpointsFailedError ? { endpoints: error.endpoints } : {}),
+                    ...(error.status === 413 && ctx?.requestSize ? { request_size: ctx.requestSize } : {}),
                     // 总是返回上游的错误详情
                     ...(error.body ? { detail: error.body.slice(0, 1000) } : {}),
+                    ...(envelope !== undefined ? { request_envelope: envelope } : {}),
                 },
             },
-            error.status as ContentfulStatusCode,
+            status as ContentfulStatusCode,
         )
     }
 
@@ -213,3 +361,34 @@ export async function forwardError(c: Context, error: unknown) {
         500,
     )
 }
+
+/**
+ * 🆕 全局兜底错误处理（Hono onError）
+ * 已知错误类型沿用 forwardError；其余视为处理器内部异常，返回 internal_panic 并记录 request_id
+ */
+export async function handleUncaughtError(error: Error, c: Context) {
+    if (error instanceof HTTPException) return error.getResponse()
+    if (
+        error instanceof HTTPError ||
+        error instanceof AntigravityError ||
+        error instanceof ConcurrencyLimitError ||
+        error instanceof UpstreamError
+    ) {
+        return forwardError(c, error)
+    }
+
+    const requestId = getRequestContext()?.requestId
+    consola.error(`[internal_panic] ${c.req.method} ${c.req.path}${requestId ? ` [request_id=${requestId}]` : ""}:`, error?.stack || error)
+    incrementCounter("internal_panics", { route: c.req.routePath })
+    c.header("X-Log-Reason", "internal panic")
+    return c.json(
+        {
+            error: {
+                type: "internal_panic",
+                message: "Internal server error",
+                ...(requestId ? { request_id: requestId } : {}),
+            },
+        },
+        500,
+    )
+}
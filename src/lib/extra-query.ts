@@ -0,0 +1,58 @@
+/**
+ * 🆕 上游 URL 附加查询参数
+ * - ANTI_API_EXTRA_QUERY="k=v&k2=v2"：对所有上游请求生效
+ * - 请求体 extra_query: { k: "v" }：仅在 ANTI_API_ALLOW_REQUEST_EXTRA_QUERY=1 时允许，覆盖同名配置
+ * alt 参数固定为 sse，不允许覆盖
+ */
+
+import { envBool, readEnv } from "./env"
+import { getRequestContext } from "./request-context"
+
+const PARAM_NAME_PATTERN = /^[A-Za-z0-9_.-]{1,64}$/
+const MAX_PARAM_VALUE_LENGTH = 256
+const MAX_PARAMS = 16
+const RESERVED_PARAMS = new Set(["alt"])
+
+export function isRequestExtraQueryAllowed(): boolean {
+    return envBool("ANTI_API_ALLOW_REQUEST_EXTRA_QUERY")
+}
+
+/**
+ * 校验参数表，返回错误信息；合法时返回 null
+ */
+export function validateExtraQuery(value: unknown): string | null {
+    if (!value || typeof value !== "object" || Array.isArray(value)) {
+        return "extra_query must be an object of string values"
+    }
+    const entries = Object.entries(value as Record<string, unknown>)
+    if (entries.length > MAX_PARAMS) return `extra_query has too many parameters (max ${MAX_PARAMS})`
+    for (const [name, paramValue] of entries) {
+        if (!PARAM_NAME_PATTERN.test(name)) return `extra_query parameter "${name.slice(0, 64)}" has an invalid name`
+        if (RESERVED_PARAMS.has(name)) return `extra_query cannot override "${name}"`
+        if (typeof paramValue !== "string") return `extra_query.${name} must be a string`
+        if (paramValue.length > MAX_PARAM_VALUE_LENGTH) return `extra_query.${name} too long (max ${MAX_PARAM_VALUE_LENGTH} characters)`
+    }
+    return null
+}
+
+export function parseExtraQueryConfig(raw: string | undefined): Record<string, string> {
+    const params: Record<string, string> = {}
+    if (!raw) return params
+    for (const [name, value] of new URLSearchParams(raw)) {
+        if (PARAM_NAME_PATTERN.test(name) && !RESERVED_PARAMS.has(name)) params[name] = value
+    }
+    return params
+}
+
+/**
+ * 拼接上游 URL：alt=sse + 配置参数 + 请求参数（URLSearchParams 负责编码）
+ */
+export function buildUpstreamUrl(baseUrl: string, endpoint: string): string {
+    const params = new URLSearchParams({ alt: "sse" })
+    const merged = {
+        ...parseExtraQueryConfig(readEnv("ANTI_API_EXTRA_QUERY")),
+        ...(getRequestContext()?.extraQuery || {}),
+    }
+    for (const [name, value] of Object.entries(merged)) params.set(name, value)
+    return `${baseUrl}${endpoint}?${params.toString()}`
+}
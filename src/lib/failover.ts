@@ -0,0 +1,109 @@
+/**
+ * 🆕 触发端点 failover 的状态码集合
+ * ANTI_API_FAILOVER_STATUSES 逗号分隔，支持具体状态码与 "5xx" 这类整段写法
+ * 默认：404,408,5xx（与原硬编码规则一致）；429 与账号相关，不允许加入
+ * 🆕 ANTI_API_SUCCESS_STATUSES: 视为成功的上游状态码（默认 2xx），只能包含 2xx；
+ *   不在集合中的 2xx（例如只接受 200 时的 206）按失败处理并切换到下一个端点
+ */
+
+import { envString } from "./env"
+
+export const DEFAULT_FAILOVER_STATUSES = "404,408,5xx"
+
+export interface FailoverStatusSet {
+    codes: Set<number>
+    /** 整段匹配的百位数，例如 5 表示 5xx */
+    classes: Set<number>
+}
+
+/**
+ * 解析状态码列表；格式错误或包含 429（含 4xx）时抛出
+ */
+export function parseFailoverStatuses(raw: string): FailoverStatusSet {
+    const result: FailoverStatusSet = { codes: new Set(), classes: new Set() }
+    for (const item of raw.split(",").map(part => part.trim().toLowerCase()).filter(Boolean)) {
+        const classMatch = item.match(/^([1-5])xx$/)
+        if (classMatch) {
+            const hundreds = Number(classMatch[1])
+            if (hundreds === 4) throw new Error("ANTI_API_FAILOVER_STATUSES must not include 429 (4xx covers it)")
+            result.classes.add(hundreds)
+            continue
+        }
+        if (!/^\d{3}$/.test(item)) throw new Error(`Invalid status in ANTI_API_FAILOVER_STATUSES: ${item}`)
+        const code = Number(item)
+        if (code < 100 || code > 599) throw new Error(`Invalid status in ANTI_API_FAILOVER_STATUSES: ${item}`)
+        if (code === 429) throw new Error("ANTI_API_FAILOVER_STATUSES must not include 429 (handled per account)")
+        result.codes.add(code)
+    }
+    return result
+}
+
+export function matchesFailoverStatus(set: FailoverStatusSet, status: number): boolean {
+    return set.codes.has(status) || set.classes.has(Math.floor(status / 100))
+}
+
+let cachedRaw: string | null = null
+let cachedSet: FailoverStatusSet = parseFailoverStatuses(DEFAULT_FAILOVER_STATUSES)
+
+/**
+ * 当前配置（热加载时若新值非法则沿用上一次的合法值）
+ */
+export function getFailoverStatuses(): FailoverStatusSet {
+    const raw = envString("ANTI_API_FAILOVER_STATUSES", DEFAULT_FAILOVER_STATUSES)
+    if (raw !== cachedRaw) {
+        try {
+            cachedSet = parseFailoverStatuses(raw)
+        } catch {
+            // 启动时已由 validateFailoverConfig 拦截
+        }
+        cachedRaw = raw
+    }
+    return cachedSet
+}
+
+export const DEFAULT_SUCCESS_STATUSES = "2xx"
+
+/**
+ * 🆕 解析成功状态码集合；格式错误、为空或包含非 2xx 时抛出
+ */
+export function parseSuccessStatuses(raw: string): FailoverStatusSet {
+    const result: FailoverStatusSet = { codes: new Set(), classes: new Set() }
+    for (const item of raw.split(",").map(part => part.trim().toLowerCase()).filter(Boolean)) {
+        if (item === "2xx") {
+            result.classes.add(2)
+            continue
+        }
+        if (!/^2\d\d$/.test(item)) throw new Error(`Invalid status in ANTI_API_SUCCESS_STATUSES: ${item} (only 2xx codes are allowed)`)
+        result.codes.add(Number(item))
+    }
+    if (result.codes.size === 0 && result.classes.size === 0) throw new Error("ANTI_API_SUCCESS_STATUSES must not be empty")
+    return result
+}
+
+let cachedSuccessRaw: string | null = null
+let cachedSuccessSet: FailoverStatusSet = parseSuccessStatuses(DEFAULT_SUCCESS_STATUSES)
+
+export function isSuccessStatus(status: number): boolean {
+    const raw = envString("ANTI_API_SUCCESS_STATUSES", DEFAULT_SUCCESS_STATUSES)
+    if (raw !== cachedSuccessRaw) {
+        try {
+            cachedSuccessSet = parseSuccessStatuses(raw)
+        } catch {
+            // 启动时已由 validateFailoverConfig 拦截
+        }
+        cachedSuccessRaw = raw
+    }
+    return matchesFailoverStatus(cachedSuccessSet, status)
+}
+
+/**
+ * 启动校验：配置非法时抛出，由入口打印并退出
+ */
+export function validateFailoverConfig(): void {
+    parseFailoverStatuses(envString("ANTI_API_FAILOVER_STATUSES", DEFAULT_FAILOVER_STATUSES))
+    parseSuccessStatuses(envString("ANTI_API_SUCCESS_STATUSES", DEFAULT_SUCCESS_STATUSES))
+}
+
+export function isFailoverStatus(status: number): boolean {
+    return status !== 429 && matchesFailoverStatus(getFailoverStatuses(), status)
+}
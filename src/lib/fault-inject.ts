@@ -0,0 +1,78 @@
+/**
+ * 🆕 故障注入（仅用于测试客户端重试与账号/端点轮换逻辑）
+ * 必须显式设置环境变量 ANTI_API_FAULT_INJECT=1 才会生效（不读取配置文件，避免误开启）
+ * - ANTI_API_FAULT_INJECT_RATE: 上游调用被注入故障的比例（0~1，默认 0）
+ * - ANTI_API_FAULT_INJECT_KINDS: 随机注入的故障类型，逗号分隔（默认 429,500,timeout,drop）
+ * - 请求头 X-Fault-Inject: <类型> 可对单个请求强制注入
+ * 429/500 返回与上游格式一致的合成响应；timeout 挂起直到超时/截止时间中止；drop 模拟连接被重置
+ * 每次注入都会记录日志并计数 faults_injected
+ */
+
+import consola from "consola"
+import { envList, readEnv } from "./env"
+import { incrementCounter } from "./metrics"
+
+export type FaultKind = "429" | "500" | "timeout" | "drop"
+
+const FAULT_KINDS: FaultKind[] = ["429", "500", "timeout", "drop"]
+
+export function isFaultInjectionEnabled(): boolean {
+    const raw = (process.env.ANTI_API_FAULT_INJECT || "").trim().toLowerCase()
+    return raw === "1" || raw === "true"
+}
+
+function isFaultKind(value: string): value is FaultKind {
+    return (FAULT_KINDS as string[]).includes(value)
+}
+
+export function getFaultRate(): number {
+    const raw = Number.parseFloat(readEnv("ANTI_API_FAULT_INJECT_RATE") || "")
+    if (!Number.isFinite(raw)) return 0
+    return Math.min(1, Math.max(0, raw))
+}
+
+function getFaultKinds(): FaultKind[] {
+    const configured = envList("ANTI_API_FAULT_INJECT_KINDS").map(kind => kind.toLowerCase()).filter(isFaultKind)
+    return configured.length > 0 ? configured : FAULT_KINDS
+}
+
+/**
+ * 决定本次上游调用注入哪种故障；未开启或未命中返回 null
+ */
+export function pickFault(header: string | undefined, random: () => number = Math.random): FaultKind | null {
+    if (!isFaultInjectionEnabled()) return null
+    const forced = header?.trim().toLowerCase()
+    if (forced && isFaultKind(forced)) return forced
+    const rate = getFaultRate()
+    if (rate <= 0 || random() >= rate) return null
+    const kinds = getFaultKinds()
+    return kinds[Math.floor(random() * kinds.length) % kinds.length]
+}
+
+function jsonResponse(status: number, body: unknown): Response {
+    return new Response(JSON.stringify(body), { status, headers: { "Content-Type": "application/json" } })
+}
+
+/**
+ * 执行注入：返回合成响应，或按故障类型抛出
+ */
+export async function injectFault(kind: FaultKind, url: string, signal: AbortSignal): Promise<Response> {
+    incrementCounter("faults_injected", { kind })
+    consola.warn(`[FaultInject] ${kind} -> ${new URL(url).origin}`)
+    switch (kind) {
+        case "429":
+            return jsonResponse(429, {
+                error: { code: 429, status: "RESOURCE_EXHAUSTED", message: "Injected fault: rate limit exceeded", details: [{ reason: "RATE_LIMIT_EXCEEDED" }] },
+            })
+        case "500":
+            return jsonResponse(500, { error: { code: 500, status: "INTERNAL", message: "Injected fault: internal error" } })
+        case "timeout":
+            return new Promise((_, reject) => {
+                const abort = () => reject(Object.assign(new Error("Injected fault: request aborted"), { name: "AbortError" }))
+                if (signal.aborted) abort()
+                else signal.addEventListener("abort", abort, { once: true })
+            })
+        case "drop":
+            throw Object.assign(new Error("Injected fault: socket connection was closed unexpectedly"), { code: "ECONNRESET" })
+    }
+}
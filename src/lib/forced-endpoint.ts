@@ -0,0 +1,33 @@
+/**
+ * 🆕 按下标强制使用某个上游端点（调试 / A/B 对比用）
+ * 请求头 X-Endpoint-Index: <下标>，且必须携带有效管理密钥，否则忽略该头
+ * 生效时只使用端点列表（ANTI_API_ENDPOINTS 顺序，不含溢出端点）中的该端点，不再切换到其他端点；
+ * 下标非法或越界返回 400 invalid_endpoint_index
+ */
+
+import type { Context } from "hono"
+import consola from "consola"
+import { hasValidAdminKey } from "./admin-auth"
+import { AntigravityError } from "./error"
+
+export function readForcedEndpointHeader(c: Context): string | undefined {
+    const value = c.req.header("X-Endpoint-Index")?.trim()
+    if (value === undefined || value === "") return undefined
+    return hasValidAdminKey(c) ? value : undefined
+}
+
+/**
+ * 返回 [下标, 端点] 列表；下标保持其在配置列表中的位置，按下标配置的超时与指标标签仍然适用
+ */
+export function selectForcedEndpoint(baseUrls: string[], forced: string): Array<[number, string]> {
+    const index = Number(forced)
+    if (!/^\d+$/.test(forced) || index >= baseUrls.length) {
+        throw new AntigravityError(
+            `X-Endpoint-Index must be an integer between 0 and ${baseUrls.length - 1}`,
+            "invalid_endpoint_index",
+            400,
+        )
+    }
+    consola.info(`Forced upstream endpoint ${index}: ${baseUrls[index]}`)
+    return [[index, baseUrls[index]]]
+}
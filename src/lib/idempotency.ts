@@ -0,0 +1,159 @@
+/**
+ * 🆕 Idempotency-Key 去重
+ * 客户端重试时携带相同的 Idempotency-Key，直接回放首个请求的结果而不是再次调用上游
+ * - ANTI_API_IDEMPOTENCY_TTL_SECS: 结果缓存时长（默认 300，0 = 关闭）
+ * - ANTI_API_IDEMPOTENCY_MAX_ENTRIES: 缓存条目上限（默认 1000，超出时淘汰最早的条目）
+ * 键按调用方凭据（Authorization / x-api-key）与 X-Client-Id 隔离；同一键携带不同请求体返回 422
+ * 只缓存 2xx 且完整结束的响应（流式需以 message_stop / [DONE] 收尾），失败的请求可以正常重试
+ * 首个请求仍在进行时，重复请求等待其完成后回放；回放的响应带 Idempotent-Replayed: true
+ */
+
+import type { Context, Next } from "hono"
+import { createHash } from "crypto"
+import { getClientId } from "./client-id"
+import { envInt } from "./env"
+import { incrementCounter } from "./metrics"
+
+interface CachedResponse {
+    status: number
+    headers: [string, string][]
+    body: string
+}
+
+interface IdempotencyEntry {
+    bodyHash: string
+    expiresAt: number
+    response?: CachedResponse
+    /** 首个请求完成（无论是否缓存）时 resolve */
+    settled: Promise<void>
+}
+
+const KEY_PATTERN = /^[\x21-\x7e]{1,255}$/
+/** 回放时不复制的逐跳/每请求头 */
+const SKIPPED_HEADERS = new Set(["content-length", "transfer-encoding", "connection", "x-request-id", "date"])
+
+const entries = new Map<string, IdempotencyEntry>()
+
+export function getIdempotencyTtlMs(): number {
+    return Math.max(0, envInt("ANTI_API_IDEMPOTENCY_TTL_SECS", 300)) * 1000
+}
+
+function getMaxEntries(): number {
+    return Math.max(1, envInt("ANTI_API_IDEMPOTENCY_MAX_ENTRIES", 1000))
+}
+
+function sha256(value: string): string {
+    return createHash("sha256").update(value).digest("hex")
+}
+
+/**
+ * 缓存键：凭据哈希 + 客户端 ID + 路径 + Idempotency-Key
+ */
+export function buildIdempotencyScope(c: Context, key: string): string {
+    const credential = c.req.header("Authorization") || c.req.header("x-api-key") || ""
+    return `${sha256(credential).slice(0, 16)}:${getClientId(c)}:${c.req.path}:${key}`
+}
+
+/**
+ * 流式响应只有正常收尾才视为完整
+ */
+export function isCompleteBody(contentType: string, body: string): boolean {
+    if (!contentType.includes("text/event-stream")) return true
+    return /^event: message_stop$/m.test(body) || /^data: \[DONE\]\s*$/m.test(body)
+}
+
+function pruneEntries(now: number): void {
+    for (const [scope, entry] of entries) {
+        if (entry.response && entry.expiresAt <= now) entries.delete(scope)
+    }
+    const overflow = entries.size - getMaxEntries()
+    if (overflow <= 0) return
+    let removed = 0
+    for (const [scope, entry] of entries) {
+        if (removed >= overflow) break
+        if (!entry.response) continue
+        entries.delete(scope)
+        removed++
+    }
+}
+
+function replay(cached: CachedResponse): Response {
+    const headers = new Headers(cached.headers)
+    headers.set("Idempotent-Replayed", "true")
+    return new Response(cached.body, { status: cached.status, headers })
+}
+
+export function resetIdempotencyCache(): void {
+    entries.clear()
+}
+
+export async function idempotencyGuard(c: Context, next: Next) {
+    const key = c.req.header("Idempotency-Key")?.trim()
+    const ttlMs = getIdempotencyTtlMs()
+    if (!key || ttlMs === 0) return next()
+    if (!KEY_PATTERN.test(key)) {
+        return c.json({ error: { type: "invalid_request_error", message: "Invalid Idempotency-Key header" } }, 400)
+    }
+
+    const scope = buildIdempotencyScope(c, key)
+    const bodyHash = sha256(await c.req.text())
+    const now = Date.now()
+    pruneEntries(now)
+
+    const existing = entries.get(scope)
+    if (existing) {
+        if (existing.bodyHash !== bodyHash) {
+            return c.json({
+                error: { type: "idempotency_key_reused", message: "Idempotency-Key was already used with a different request body" },
+            }, 422)
+        }
+        await existing.settled
+        const current = entries.get(scope)
+        if (current?.response && current.expiresAt > Date.now()) {
+            incrementCounter("idempotent_replays")
+            return replay(current.response)
+        }
+    }
+
+    let settle!: () => void
+    const entry: IdempotencyEntry = {
+        bodyHash,
+        expiresAt: Number.POSITIVE_INFINITY,
+        settled: new Promise(resolve => { settle = resolve }),
+    }
+    entries.set(scope, entry)
+
+    const drop = () => {
+        if (entries.get(scope) === entry) entries.delete(scope)
+        settle()
+    }
+
+    try {
+        await next()
+    } catch (error) {
+        drop()
+        throw error
+    }
+
+    const res = c.res
+    if (res.status < 200 || res.status >= 300) {
+        drop()
+        return
+    }
+
+    // 流式响应在后台读取副本，流结束后才写入缓存
+    const contentType = res.headers.get("Content-Type") || ""
+    const headers: [string, string][] = []
+    res.headers.forEach((value, name) => {
+        if (!SKIPPED_HEADERS.has(name.toLowerCase())) headers.push([name, value])
+    })
+    res.clone().text().then(body => {
+        if (entries.get(scope) !== entry || !isCompleteBody(contentType, body)) {
+            drop()
+            return
+        }
+        entry.response = { status: res.status, headers, body }
+        entry.expiresAt = Date.now() + ttlMs
+        settle()
+    }, drop)
+}
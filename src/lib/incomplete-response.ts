@@ -0,0 +1,33 @@
+/**
+ * 🆕 非流式上游响应体完整性检查
+ * 连接在响应体传完之前断开时，原先会把截断的内容当作成功返回；现在以下情况视为不完整：
+ * - 读取响应体时出错（连接提前关闭等）
+ * - 未压缩响应的实际字节数与 Content-Length 不一致
+ * 不完整的响应记为端点失败并切换到下一个端点，全部失败时返回 error_code: incomplete_response
+ */
+
+import { AntigravityError } from "./error"
+
+/**
+ * 读取完整响应体；不完整时返回 null
+ */
+export async function readCompleteBody(response: Response): Promise<string | null> {
+    let bytes: ArrayBuffer
+    try {
+        bytes = await response.arrayBuffer()
+    } catch {
+        return null
+    }
+    const declared = response.headers.get("Content-Length")
+    const encoding = response.headers.get("Content-Encoding")
+    // 压缩响应的 Content-Length 是压缩前的长度，无法与解压后的字节数比较
+    if (declared !== null && (!encoding || encoding === "identity")) {
+        const expected = Number(declared)
+        if (Number.isInteger(expected) && expected !== bytes.byteLength) return null
+    }
+    return new TextDecoder().decode(bytes)
+}
+
+export function incompleteResponseError(): AntigravityError {
+    return new AntigravityError("Upstream response body was truncated", "incomplete_response", 502)
+}
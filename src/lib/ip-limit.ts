@@ -0,0 +1,80 @@
+/**
+ * 🆕 单 IP 并发连接上限
+ * ANTI_API_MAX_CONN_PER_IP（默认 0 = 不限制），超限返回 429 too_many_connections
+ * 回环地址与 ANTI_API_CONN_LIMIT_EXEMPT（逗号分隔 CIDR）不受限制
+ * 🆕 直连对端属于 ANTI_API_TRUSTED_PROXIES（逗号分隔 CIDR）时按 X-Forwarded-For 识别真实客户端
+ */
+
+import type { Context, Next } from "hono"
+import { getConnInfo } from "hono/bun"
+import { envInt, envList } from "./env"
+import { isInCidr, isLoopback, parseCidrList, parseIp, resolveClientIp } from "./ip"
+import { trackResponseCompletion } from "./request-context"
+
+const connectionsByIp = new Map<string, number>()
+
+export function getMaxConnectionsPerIp(): number {
+    return Math.max(0, envInt("ANTI_API_MAX_CONN_PER_IP", 0))
+}
+
+export function getRemoteAddress(c: Context): string | undefined {
+    try {
+        return getConnInfo(c).remote.address || undefined
+    } catch {
+        return undefined
+    }
+}
+
+/**
+ * 🆕 真实客户端 IP（受信代理之后的第一个非受信跳点）
+ */
+export function getClientIp(c: Context): string | undefined {
+    const peer = getRemoteAddress(c)
+    if (!peer) return undefined
+    return resolveClientIp(peer, c.req.header("X-Forwarded-For"), parseCidrList(envList("ANTI_API_TRUSTED_PROXIES")))
+}
+
+export function isConnectionLimitExempt(address: string): boolean {
+    const ip = parseIp(address)
+    if (!ip) return false
+    if (isLoopback(ip)) return true
+    return parseCidrList(envList("ANTI_API_CONN_LIMIT_EXEMPT")).some(cidr => isInCidr(ip, cidr))
+}
+
+export function getConnectionCount(address: string): number {
+    return connectionsByIp.get(address) || 0
+}
+
+export async function ipConnectionLimit(c: Context, next: Next) {
+    const limit = getMaxConnectionsPerIp()
+    const address = limit > 0 ? getClientIp(c) : undefined
+    if (!address || isConnectionLimitExempt(address)) return next()
+
+    const current = getConnectionCount(address)
+    if (current >= limit) {
+        c.header("Retry-After", "1")
+        c.header("X-Log-Reason", "too many connections")
+        return c.json({
+            error: { type: "too_many_connections", message: `Too many concurrent connections from this address (limit ${limit})` },
+        }, 429)
+    }
+
+    connectionsByIp.set(address, current + 1)
+    let released = false
+    const release = () => {
+        if (released) return
+        released = true
+        const remaining = getConnectionCount(address) - 1
+        if (remaining > 0) connectionsByIp.set(address, remaining)
+        else connectionsByIp.delete(address)
+    }
+
+    try {
+        await next()
+    } catch (error) {
+        release()
+        throw error
+    }
+    // 响应体发送完毕或客户端断开时释放
+    c.res = trackResponseCompletion(c.res, release)
+}
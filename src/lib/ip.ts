@@ -0,0 +1,116 @@
+/**
+ * IP 地址与 CIDR 工具（IPv4 / IPv6）
+ */
+
+export interface ParsedIp {
+    version: 4 | 6
+    value: bigint
+}
+
+function parseIpv4(address: string): bigint | null {
+    const parts = address.split(".")
+    if (parts.length !== 4) return null
+    let value = 0n
+    for (const part of parts) {
+        if (!/^\d{1,3}$/.test(part)) return null
+        const octet = Number(part)
+        if (octet > 255) return null
+        value = (value << 8n) | BigInt(octet)
+    }
+    return value
+}
+
+function parseIpv6(address: string): bigint | null {
+    let text = address
+    // 末尾内嵌 IPv4（如 ::ffff:1.2.3.4）
+    const lastColon = text.lastIndexOf(":")
+    const tail = text.slice(lastColon + 1)
+    if (tail.includes(".")) {
+        const v4 = parseIpv4(tail)
+        if (v4 === null) return null
+        text = `${text.slice(0, lastColon + 1)}${(v4 >> 16n).toString(16)}:${(v4 & 0xffffn).toString(16)}`
+    }
+
+    const halves = text.split("::")
+    if (halves.length > 2) return null
+    const head = halves[0] ? halves[0].split(":") : []
+    const rest = halves.length === 2 && halves[1] ? halves[1].split(":") : []
+    const missing = 8 - head.length - rest.length
+    if (halves.length === 1 ? missing !== 0 : missing < 1) return null
+    const groups = [...head, ...Array(halves.length === 2 ? missing : 0).fill("0"), ...rest]
+
+    let value = 0n
+    for (const group of groups) {
+        if (!/^[0-9a-fA-F]{1,4}$/.test(group)) return null
+        value = (value << 16n) | BigInt(Number.parseInt(group, 16))
+    }
+    return value
+}
+
+export function parseIp(raw: string): ParsedIp | null {
+    const address = raw.trim().replace(/^\[|\]$/g, "").split("%")[0]
+    if (address.includes(":")) {
+        const value = parseIpv6(address)
+        if (value === null) return null
+        // IPv4 映射地址按 IPv4 处理
+        if (value >> 32n === 0xffffn) return { version: 4, value: value & 0xffffffffn }
+        return { version: 6, value }
+    }
+    const value = parseIpv4(address)
+    return value === null ? null : { version: 4, value }
+}
+
+export interface Cidr {
+    version: 4 | 6
+    network: bigint
+    prefix: number
+}
+
+export function parseCidr(raw: string): Cidr | null {
+    const [address, prefixText] = raw.trim().split("/")
+    const ip = parseIp(address)
+    if (!ip) return null
+    const bits = ip.version === 4 ? 32 : 128
+    const prefix = prefixText === undefined ? bits : Number(prefixText)
+    if (!Number.isInteger(prefix) || prefix < 0 || prefix > bits) return null
+    const shift = BigInt(bits - prefix)
+    return { version: ip.version, network: (ip.value >> shift) << shift, prefix }
+}
+
+export function isInCidr(ip: ParsedIp, cidr: Cidr): boolean {
+    if (ip.version !== cidr.version) return false
+    const bits = ip.version === 4 ? 32 : 128
+    const shift = BigInt(bits - cidr.prefix)
+    return (ip.value >> shift) << shift === cidr.network
+}
+
+export function parseCidrList(items: string[]): Cidr[] {
+    return items.map(parseCidr).filter((cidr): cidr is Cidr => cidr !== null)
+}
+
+export function isLoopback(ip: ParsedIp): boolean {
+    if (ip.version === 4) return ip.value >> 24n === 127n
+    return ip.value === 1n
+}
+
+/**
+ * 🆕 解析真实客户端 IP
+ * 仅当直连对端位于受信代理 CIDR 内时才采信 X-Forwarded-For；从右往左跳过受信代理，
+ * 返回第一个非受信地址。遇到无法解析的条目时停止，返回最后一个受信跳点（防止伪造）
+ */
+export function resolveClientIp(peer: string, forwardedFor: string | undefined, trusted: Cidr[]): string {
+    const isTrusted = (address: string) => {
+        const ip = parseIp(address)
+        return ip !== null && trusted.some(cidr => isInCidr(ip, cidr))
+    }
+    if (!forwardedFor || trusted.length === 0 || !isTrusted(peer)) return peer
+
+    const hops = forwardedFor.split(",").map(hop => hop.trim()).filter(Boolean)
+    let candidate = peer
+    for (let i = hops.length - 1; i >= 0; i--) {
+        if (!parseIp(hops[i])) return candidate
+        candidate = hops[i]
+        if (!isTrusted(candidate)) return candidate
+    }
+    return candidate
+}
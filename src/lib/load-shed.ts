@@ -0,0 +1,73 @@
+/**
+ * 🆕 基于事件循环延迟的负载削减
+ * - ANTI_API_SHED_LAG_MS: 触发阈值（默认 0 = 关闭）
+ * - ANTI_API_SHED_MAX_FRACTION: 最大削减比例（默认 0.9，始终放行一部分请求）
+ * 延迟超过阈值后，削减比例随超载程度线性增长：(lag - threshold) / threshold，不超过最大比例
+ * 被削减的新请求返回 503 shedding，计数 requests_shed
+ */
+
+import type { Context, Next } from "hono"
+import { envInt, readEnv } from "./env"
+import { incrementCounter, setGauge } from "./metrics"
+
+const SAMPLE_INTERVAL_MS = 100
+/** EWMA 平滑系数 */
+const SMOOTHING = 0.3
+
+let smoothedLagMs = 0
+let monitor: ReturnType<typeof setInterval> | null = null
+
+export function getShedLagThresholdMs(): number {
+    return Math.max(0, envInt("ANTI_API_SHED_LAG_MS", 0))
+}
+
+export function getShedMaxFraction(): number {
+    const raw = Number.parseFloat(readEnv("ANTI_API_SHED_MAX_FRACTION") || "")
+    if (!Number.isFinite(raw)) return 0.9
+    return Math.min(1, Math.max(0, raw))
+}
+
+/**
+ * 根据当前延迟计算削减比例
+ */
+export function computeShedFraction(lagMs: number, thresholdMs: number, maxFraction: number): number {
+    if (thresholdMs <= 0 || lagMs <= thresholdMs) return 0
+    return Math.min(maxFraction, (lagMs - thresholdMs) / thresholdMs)
+}
+
+export function getEventLoopLagMs(): number {
+    return smoothedLagMs
+}
+
+/**
+ * 启动延迟采样（定时器实际触发时间与预期的差值）；未配置阈值时不启动
+ */
+export function startLagMonitor(): void {
+    if (monitor || getShedLagThresholdMs() <= 0) return
+    let expected = performance.now() + SAMPLE_INTERVAL_MS
+    monitor = setInterval(() => {
+        const now = performance.now()
+        const lag = Math.max(0, now - expected)
+        expected = now + SAMPLE_INTERVAL_MS
+        smoothedLagMs = smoothedLagMs * (1 - SMOOTHING) + lag * SMOOTHING
+        setGauge("event_loop_lag_ms", Math.round(smoothedLagMs))
+    }, SAMPLE_INTERVAL_MS)
+    monitor.unref?.()
+}
+
+export async function loadShedGuard(c: Context, next: Next) {
+    const threshold = getShedLagThresholdMs()
+    if (threshold <= 0) return next()
+    startLagMonitor()
+
+    const fraction = computeShedFraction(smoothedLagMs, threshold, getShedMaxFraction())
+    if (fraction <= 0 || Math.random() >= fraction) return next()
+
+    incrementCounter("requests_shed", { route: c.req.routePath })
+    c.header("Retry-After", "1")
+    c.header("X-Log-Reason", "shedding")
+    return c.json(
+        { error: { type: "shedding", message: "Server is overloaded, please retry shortly" } },
+        503,
+    )
+}
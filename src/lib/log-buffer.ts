@@ -1,4 +1,5 @@
 import { format } from "util"
+import { envInt } from "./env"
 
 export type LogLevel = "log" | "info" | "warn" | "error" | "debug"
 
@@ -12,7 +13,7 @@ export type LogEntry = {
 type LogListener = (entry: LogEntry) => void
 
 const DEFAULT_MAX_LINES = 2000
-const MAX_LINES = Math.max(100, Number.parseInt(process.env.ANTI_API_LOG_LINES || "", 10) || DEFAULT_MAX_LINES)
+const MAX_LINES = Math.max(100, envInt("ANTI_API_LOG_LINES", 0) || DEFAULT_MAX_LINES)
 
 const buffer: LogEntry[] = []
 const listeners = new Set<LogListener>()
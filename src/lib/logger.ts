@@ -3,6 +3,9 @@
  * Provides formatted output for server status and request logs
  */
 
+import { readEnv } from "./env"
+import { getRequestContext } from "./request-context"
+
 const SEPARATOR = "================================"
 
 // Provider display names
@@ -21,6 +24,28 @@ export interface RequestLogContext {
 
 let lastRequestContext: RequestLogContext = {}
 
+/**
+ * 🆕 请求级 info 日志采样率（ANTI_API_LOG_SAMPLE_RATE，0.0–1.0，默认 1 = 全部记录）
+ * 只影响成功请求的 info 日志；错误/429/5xx 总是记录，指标不采样
+ */
+export function getLogSampleRate(): number {
+    const raw = Number.parseFloat(readEnv("ANTI_API_LOG_SAMPLE_RATE") || "")
+    if (!Number.isFinite(raw)) return 1
+    return Math.min(1, Math.max(0, raw))
+}
+
+/**
+ * 每个请求只抽样一次，保证同一请求的多行日志要么都输出要么都不输出
+ */
+export function isRequestLogSampled(): boolean {
+    const rate = getLogSampleRate()
+    if (rate >= 1) return true
+    const ctx = getRequestContext()
+    if (!ctx) return Math.random() < rate
+    if (ctx.logSampled === undefined) ctx.logSampled = Math.random() < rate
+    return ctx.logSampled
+}
+
 export function formatLogTime(): string {
     return new Date().toLocaleTimeString("en-US", {
         hour: "numeric",
@@ -53,14 +78,33 @@ export function logStartup(port: number): void {
 /**
  * Print startup success
  */
-export function logStartupSuccess(port: number): void {
+export function logStartupSuccess(listeners: Array<{ port: number; scheme: "http" | "https" }>, basePath: string = ""): void {
     console.log(`Succeed. PID: ${process.pid}.`)
-    console.log(`listen on: http://0.0.0.0:${port}/quota`)
+    for (const { port, scheme } of listeners) {
+        console.log(`listen on: ${scheme}://0.0.0.0:${port}${basePath}/quota`)
+    }
     console.log("")
     console.log(SEPARATOR)
     console.log("")
 }
 
+/**
+ * 🆕 打印生效的路由表（跳过中间件条目）
+ */
+export function logRouteMap(routes: { method: string; path: string }[]): void {
+    const byPath = new Map<string, Set<string>>()
+    for (const route of routes) {
+        if (route.method === "ALL" && route.path.endsWith("*")) continue
+        const methods = byPath.get(route.path) || new Set<string>()
+        methods.add(route.method)
+        byPath.set(route.path, methods)
+    }
+    console.log("Routes:")
+    for (const [path, methods] of byPath) {
+        console.log(`  ${Array.from(methods).join(",").padEnd(12)} ${path}`)
+    }
+}
+
 /**
  * Log a successful request with model/provider/account info
  */
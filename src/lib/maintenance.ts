@@ -0,0 +1,66 @@
+/**
+ * 维护模式
+ * 开启后聊天端点直接返回配置的状态码/消息/Retry-After，不访问上游
+ * 启动时可通过 ANTI_API_MAINTENANCE=1 开启（ANTI_API_MAINTENANCE_MESSAGE / _STATUS / _RETRY_AFTER 配置响应）
+ */
+
+import type { Context, Next } from "hono"
+import type { ContentfulStatusCode } from "hono/utils/http-status"
+import { envBool, envInt, envString } from "./env"
+
+export interface MaintenanceState {
+    enabled: boolean
+    status: number
+    message: string
+    retryAfterSeconds: number
+    since: string | null
+}
+
+const DEFAULT_MESSAGE = "Service is under maintenance, please retry later"
+
+const maintenance: MaintenanceState = {
+    enabled: envBool("ANTI_API_MAINTENANCE"),
+    status: envInt("ANTI_API_MAINTENANCE_STATUS", 503),
+    message: envString("ANTI_API_MAINTENANCE_MESSAGE", DEFAULT_MESSAGE),
+    retryAfterSeconds: envInt("ANTI_API_MAINTENANCE_RETRY_AFTER", 300),
+    since: null,
+}
+if (maintenance.enabled) maintenance.since = new Date().toISOString()
+
+export function getMaintenanceState(): MaintenanceState {
+    return { ...maintenance }
+}
+
+export function isMaintenanceEnabled(): boolean {
+    return maintenance.enabled
+}
+
+export function setMaintenance(update: Partial<Omit<MaintenanceState, "since">>): MaintenanceState {
+    if (update.enabled !== undefined && update.enabled !== maintenance.enabled) {
+        maintenance.since = update.enabled ? new Date().toISOString() : null
+        maintenance.enabled = update.enabled
+    }
+    if (typeof update.status === "number" && update.status >= 400 && update.status <= 599) {
+        maintenance.status = update.status
+    }
+    if (typeof update.message === "string" && update.message.trim()) {
+        maintenance.message = update.message.trim()
+    }
+    if (typeof update.retryAfterSeconds === "number" && update.retryAfterSeconds >= 0) {
+        maintenance.retryAfterSeconds = Math.floor(update.retryAfterSeconds)
+    }
+    return getMaintenanceState()
+}
+
+/**
+ * 聊天路由中间件：维护期间短路返回
+ */
+export async function maintenanceGuard(c: Context, next: Next) {
+    if (!maintenance.enabled) return next()
+    c.header("Retry-After", String(maintenance.retryAfterSeconds))
+    c.header("X-Log-Reason", "maintenance")
+    return c.json(
+        { error: { type: "maintenance", message: maintenance.message } },
+        maintenance.status as ContentfulStatusCode,
+    )
+}
@@ -0,0 +1,54 @@
+/**
+ * 🆕 模型请求指标标签
+ * model_requests_total / model_request_duration_seconds 带以下标签：
+ * - model: 路由后的模型 ID（别名已展开）；最多 ANTI_API_METRICS_MAX_MODELS 个不同取值（默认 50），
+ *   超出上限后新出现的模型统一记为 "other"，避免标签基数失控
+ * - outcome: success | rate_limited | auth_error | 🆕 request_too_large（413）| bad_request | server_error | failed
+ *   （failed 涵盖客户端断开 499 与其他非 HTTP 错误结果）
+ * - endpoint_index: 最后一次上游调用使用的端点在 ANTI_API_ENDPOINTS 中的下标；🆕 溢出端点为 "overflow"；未调用上游时为 "none"
+ * - 🆕 billing_tag: X-Billing-Tag（取值受 ANTI_API_BILLING_TAGS 约束）；无标签时为 "none"
+ */
+
+import { envInt } from "./env"
+import { OVERFLOW_ENDPOINT_INDEX } from "./overflow"
+
+export type RequestOutcome = "success" | "rate_limited" | "auth_error" | "request_too_large" | "bad_request" | "server_error" | "failed"
+
+export const OTHER_MODEL_LABEL = "other"
+
+const seenModels = new Set<string>()
+
+export function getMaxModelLabels(): number {
+    return Math.max(0, envInt("ANTI_API_METRICS_MAX_MODELS", 50))
+}
+
+export function classifyOutcome(status: number): RequestOutcome {
+    if (status >= 200 && status < 400) return "success"
+    if (status === 429) return "rate_limited"
+    if (status === 401 || status === 403) return "auth_error"
+    if (status === 413) return "request_too_large"
+    if (status === 499) return "failed"
+    if (status >= 400 && status < 500) return "bad_request"
+    if (status >= 500 && status < 600) return "server_error"
+    return "failed"
+}
+
+/**
+ * 已登记的模型保持原值；未登记且已达上限的模型记为 "other"
+ */
+export function modelLabel(model: string | undefined): string {
+    if (!model) return OTHER_MODEL_LABEL
+    if (seenModels.has(model)) return model
+    if (seenModels.size >= getMaxModelLabels()) return OTHER_MODEL_LABEL
+    seenModels.add(model)
+    return model
+}
+
+export function endpointIndexLabel(index: number | undefined): string {
+    if (index === undefined) return "none"
+    return index === OVERFLOW_ENDPOINT_INDEX ? "overflow" : String(index)
+}
+
+export function resetModelLabels(): void {
+    seenModels.clear()
+}
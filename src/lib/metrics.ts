@@ -0,0 +1,300 @@
+/**
+ * 指标上报
+ * ANTI_API_METRICS_BACKEND = prometheus | statsd | none（默认 prometheus）
+ * - prometheus: 内存聚合，由 GET /metrics 暴露
+ * - statsd: UDP 推送到 ANTI_API_STATSD_ADDR（默认 127.0.0.1:8125，DogStatsD 标签格式）
+ * - none: 全部为空操作
+ * 🆕 模型请求的 model / outcome / endpoint_index 标签与基数控制见 metric-labels.ts
+ * 🆕 prometheus 后端另可导出结构化 JSON（GET /metrics.json），直方图附带按分桶线性插值估算的分位数
+ */
+
+import { createSocket, type Socket } from "node:dgram"
+import { envString } from "./env"
+
+export type MetricTags = Record<string, string | number | undefined>
+
+export interface MetricsBackend {
+    readonly name: string
+    increment(name: string, value: number, tags: MetricTags): void
+    observe(name: string, value: number, tags: MetricTags): void
+    gauge(name: string, value: number, tags: MetricTags): void
+    /** 仅 prometheus 支持文本导出 */
+    render?(): string
+    /** 🆕 清空内存聚合（仅 prometheus） */
+    reset?(): void
+    /** 🆕 结构化快照（仅 prometheus） */
+    snapshot?(): MetricsSnapshot
+}
+
+export interface MetricSample {
+    labels: Record<string, string>
+    value: number
+}
+
+export interface HistogramSample {
+    labels: Record<string, string>
+    buckets: { le: number | "+Inf"; count: number }[]
+    sum: number
+    count: number
+    quantiles: { p50: number | null; p90: number | null; p99: number | null }
+}
+
+export interface MetricsSnapshot {
+    counters: Record<string, MetricSample[]>
+    gauges: Record<string, MetricSample[]>
+    histograms: Record<string, HistogramSample[]>
+}
+
+const METRIC_PREFIX = "anti_api_"
+const DEFAULT_BUCKETS = [0.05, 0.1, 0.25, 0.5, 1, 2.5, 5, 10, 30, 60, 120]
+/** 🆕 名称以 _bytes 结尾的直方图使用字节分桶 */
+const BYTE_BUCKETS = [256, 1024, 4096, 16384, 65536, 262144, 1048576, 4194304, 16777216]
+
+function bucketsFor(name: string): number[] {
+    return name.endsWith("_bytes") ? BYTE_BUCKETS : DEFAULT_BUCKETS
+}
+
+function normalizeTags(tags: MetricTags): [string, string][] {
+    return Object.entries(tags)
+        .filter(([, value]) => value !== undefined && value !== "")
+        .map(([key, value]) => [key, String(value)] as [string, string])
+        .sort(([a], [b]) => a.localeCompare(b))
+}
+
+function escapeLabelValue(value: string): string {
+    return value.replace(/\\/g, "\\\\").replace(/"/g, "\\\"").replace(/\n/g, "\\n")
+}
+
+function formatLabels(tags: [string, string][], extra?: [string, string]): string {
+    const all = extra ? [...tags, extra] : tags
+    if (all.length === 0) return ""
+    return "{" + all.map(([key, value]) => `${key}="${escapeLabelValue(value)}"`).join(",") + "}"
+}
+
+/**
+ * 🆕 由累计分桶估算分位数（同 Prometheus histogram_quantile：桶内线性插值，超出最大桶时取最大上界）
+ */
+export function estimateQuantile(q: number, bounds: number[], buckets: number[], count: number): number | null {
+    if (count === 0) return null
+    const rank = q * count
+    for (let i = 0; i < bounds.length; i++) {
+        if (buckets[i] >= rank) {
+            const lower = i === 0 ? 0 : bounds[i - 1]
+            const below = i === 0 ? 0 : buckets[i - 1]
+            const inBucket = buckets[i] - below
+            return inBucket > 0 ? lower + (bounds[i] - lower) * ((rank - below) / inBucket) : bounds[i]
+        }
+    }
+    return bounds[bounds.length - 1]
+}
+
+interface HistogramState {
+    tags: [string, string][]
+    bounds: number[]
+    buckets: number[]
+    sum: number
+    count: number
+}
+
+class PrometheusBackend implements MetricsBackend {
+    readonly name = "prometheus"
+    private counters = new Map<string, Map<string, { tags: [string, string][]; value: number }>>()
+    private gauges = new Map<string, Map<string, { tags: [string, string][]; value: number }>>()
+    private histograms = new Map<string, Map<string, HistogramState>>()
+
+    private series<T>(store: Map<string, Map<string, T>>, name: string): Map<string, T> {
+        let series = store.get(name)
+        if (!series) {
+            series = new Map()
+            store.set(name, series)
+        }
+        return series
+    }
+
+    increment(name: string, value: number, tags: MetricTags): void {
+        const normalized = normalizeTags(tags)
+        const key = JSON.stringify(normalized)
+        const series = this.series(this.counters, name)
+        const entry = series.get(key) || { tags: normalized, value: 0 }
+        entry.value += value
+        series.set(key, entry)
+    }
+
+    gauge(name: string, value: number, tags: MetricTags): void {
+        const normalized = normalizeTags(tags)
+        this.series(this.gauges, name).set(JSON.stringify(normalized), { tags: normalized, value })
+    }
+
+    observe(name: string, value: number, tags: MetricTags): void {
+        const normalized = normalizeTags(tags)
+        const key = JSON.stringify(normalized)
+        const series = this.series(this.histograms, name)
+        let entry = series.get(key)
+        if (!entry) {
+            const bounds = bucketsFor(name)
+            entry = { tags: normalized, bounds, buckets: bounds.map(() => 0), sum: 0, count: 0 }
+            series.set(key, entry)
+        }
+        for (let i = 0; i < entry.bounds.length; i++) {
+            if (value <= entry.bounds[i]) entry.buckets[i]++
+        }
+        entry.sum += value
+        entry.count++
+    }
+
+    reset(): void {
+        this.counters.clear()
+        this.gauges.clear()
+        this.histograms.clear()
+    }
+
+    snapshot(): MetricsSnapshot {
+        const simple = (store: Map<string, Map<string, { tags: [string, string][]; value: number }>>) => {
+            const result: Record<string, MetricSample[]> = {}
+            for (const [name, series] of store) {
+                result[`${METRIC_PREFIX}${name}`] = Array.from(series.values(), entry => ({ labels: Object.fromEntries(entry.tags), value: entry.value }))
+            }
+            return result
+        }
+        const histograms: Record<string, HistogramSample[]> = {}
+        for (const [name, series] of this.histograms) {
+            histograms[`${METRIC_PREFIX}${name}`] = Array.from(series.values(), entry => ({
+                labels: Object.fromEntries(entry.tags),
+                buckets: [
+                    ...entry.bounds.map((bound, i) => ({ le: bound as number | "+Inf", count: entry.buckets[i] })),
+                    { le: "+Inf" as const, count: entry.count },
+                ],
+                sum: entry.sum,
+                count: entry.count,
+                quantiles: {
+                    p50: estimateQuantile(0.5, entry.bounds, entry.buckets, entry.count),
+                    p90: estimateQuantile(0.9, entry.bounds, entry.buckets, entry.count),
+                    p99: estimateQuantile(0.99, entry.bounds, entry.buckets, entry.count),
+                },
+            }))
+        }
+        return { counters: simple(this.counters), gauges: simple(this.gauges), histograms }
+    }
+
+    render(): string {
+        const lines: string[] = []
+        for (const [name, series] of this.counters) {
+            lines.push(`# TYPE ${METRIC_PREFIX}${name} counter`)
+            for (const entry of series.values()) {
+                lines.push(`${METRIC_PREFIX}${name}${formatLabels(entry.tags)} ${entry.value}`)
+            }
+        }
+        for (const [name, series] of this.gauges) {
+            lines.push(`# TYPE ${METRIC_PREFIX}${name} gauge`)
+            for (const entry of series.values()) {
+                lines.push(`${METRIC_PREFIX}${name}${formatLabels(entry.tags)} ${entry.value}`)
+            }
+        }
+        for (const [name, series] of this.histograms) {
+            const fullName = `${METRIC_PREFIX}${name}`
+            lines.push(`# TYPE ${fullName} histogram`)
+            for (const entry of series.values()) {
+                entry.bounds.forEach((bound, i) => {
+                    lines.push(`${fullName}_bucket${formatLabels(entry.tags, ["le", String(bound)])} ${entry.buckets[i]}`)
+                })
+                lines.push(`${fullName}_bucket${formatLabels(entry.tags, ["le", "+Inf"])} ${entry.count}`)
+                lines.push(`${fullName}_sum${formatLabels(entry.tags)} ${entry.sum}`)
+                lines.push(`${fullName}_count${formatLabels(entry.tags)} ${entry.count}`)
+            }
+        }
+        return lines.join("\n") + "\n"
+    }
+}
+
+class StatsdBackend implements MetricsBackend {
+    readonly name = "statsd"
+    private socket: Socket
+    private host: string
+    private port: number
+
+    constructor(address: string) {
+        const separator = address.lastIndexOf(":")
+        this.host = separator > 0 ? address.slice(0, separator) : address
+        this.port = separator > 0 ? Number.parseInt(address.slice(separator + 1), 10) || 8125 : 8125
+        this.socket = createSocket("udp4")
+        this.socket.unref()
+        this.socket.on("error", () => { })
+    }
+
+    private send(name: string, value: number, type: string, tags: MetricTags): void {
+        const normalized = normalizeTags(tags)
+        const tagPart = normalized.length > 0 ? "|#" + normalized.map(([key, val]) => `${key}:${val}`).join(",") : ""
+        const payload = Buffer.from(`${METRIC_PREFIX}${name}:${value}|${type}${tagPart}`)
+        this.socket.send(payload, this.port, this.host, () => { })
+    }
+
+    increment(name: string, value: number, tags: MetricTags): void {
+        this.send(name, value, "c", tags)
+    }
+
+    observe(name: string, value: number, tags: MetricTags): void {
+        // 🆕 字节数按原值上报为直方图
+        if (name.endsWith("_bytes")) {
+            this.send(name, Math.round(value), "h", tags)
+            return
+        }
+        // statsd 计时单位为毫秒
+        this.send(name, Math.round(value * 1000), "ms", tags)
+    }
+
+    gauge(name: string, value: number, tags: MetricTags): void {
+        this.send(name, value, "g", tags)
+    }
+}
+
+class NoopBackend implements MetricsBackend {
+    readonly name = "none"
+    increment(): void { }
+    observe(): void { }
+    gauge(): void { }
+}
+
+function createBackend(): MetricsBackend {
+    const backend = envString("ANTI_API_METRICS_BACKEND", "prometheus").toLowerCase()
+    if (backend === "none") return new NoopBackend()
+    if (backend === "statsd") return new StatsdBackend(envString("ANTI_API_STATSD_ADDR", "127.0.0.1:8125"))
+    return new PrometheusBackend()
+}
+
+export const metrics: MetricsBackend = createBackend()
+
+export function incrementCounter(name: string, tags: MetricTags = {}, value: number = 1): void {
+    metrics.increment(name, value, tags)
+}
+
+/** 记录耗时/大小分布（耗时单位为秒，大小以 _bytes 结尾命名、单位为字节） */
+export function observeHistogram(name: string, value: number, tags: MetricTags = {}): void {
+    metrics.observe(name, value, tags)
+}
+
+export function setGauge(name: string, value: number, tags: MetricTags = {}): void {
+    metrics.gauge(name, value, tags)
+}
+
+/**
+ * Prometheus 文本格式；非 prometheus 后端返回 null
+ */
+export function renderMetrics(): string | null {
+    return metrics.render ? metrics.render() : null
+}
+
+/**
+ * 🆕 清空已聚合的指标；后端不支持时返回 false
+ */
+export function resetMetrics(): boolean {
+    if (!metrics.reset) return false
+    metrics.reset()
+    return true
+}
+
+/**
+ * 🆕 结构化快照；非 prometheus 后端返回 null
+ */
+export function snapshotMetrics(): MetricsSnapshot | null {
+    return metrics.snapshot ? metrics.snapshot() : null
+}
@@ -0,0 +1,35 @@
+/**
+ * 🆕 溢出端点（按负载路由，区别于按错误切换的 failover）
+ * - ANTI_API_OVERFLOW_ENDPOINT: 溢出端点 URL（默认不设置 = 关闭）
+ * - ANTI_API_OVERFLOW_THRESHOLD: 在途请求数超过该值时，新的上游调用优先发往溢出端点（默认 0 = 关闭）
+ * 未超过阈值时只使用主端点列表；溢出时主端点仍排在其后作为 failover
+ * 主端点保留其在 ANTI_API_ENDPOINTS 中的下标（按下标的超时与 endpoint_index 标签不受影响），
+ * 溢出端点使用独立下标 OVERFLOW_ENDPOINT_INDEX：endpoint_index 标签为 "overflow"，
+ * 超时可用 ANTI_API_ENDPOINT_TIMEOUTS_MS 中的 `overflow=<毫秒>` 或其 URL 单独配置
+ * 发往溢出端点的请求计数 overflow_requests
+ */
+
+import { envInt, envString } from "./env"
+import { incrementCounter } from "./metrics"
+
+export const OVERFLOW_ENDPOINT_INDEX = -1
+
+export function getOverflowEndpoint(): string | undefined {
+    return envString("ANTI_API_OVERFLOW_ENDPOINT")?.replace(/\/+$/, "")
+}
+
+export function getOverflowThreshold(): number {
+    return Math.max(0, envInt("ANTI_API_OVERFLOW_THRESHOLD", 0))
+}
+
+/**
+ * 根据当前在途请求数决定本次请求使用的端点列表（[下标, 端点]）
+ */
+export function applyOverflowRouting(primary: string[], inFlight: number): Array<[number, string]> {
+    const indexed = Array.from(primary.entries())
+    const overflow = getOverflowEndpoint()
+    const threshold = getOverflowThreshold()
+    if (!overflow || threshold === 0 || inFlight <= threshold) return indexed
+    incrementCounter("overflow_requests")
+    return [[OVERFLOW_ENDPOINT_INDEX, overflow], ...indexed.filter(([, url]) => url !== overflow)]
+}
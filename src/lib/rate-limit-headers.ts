@@ -0,0 +1,34 @@
+/**
+ * 🆕 限流状态响应头（ANTI_API_RATELIMIT_HEADERS=1 开启，默认关闭以免增加响应头）
+ * - X-RateLimit-Limit / X-RateLimit-Remaining: 全局并发许可上限与剩余（未设置上限时不发送）
+ * - X-RateLimit-Min-Interval-Ms: 相邻上游请求的最小间隔
+ * - X-RateLimit-Streams-Remaining: 剩余流式许可（仅设置 ANTI_API_MAX_CONCURRENT_STREAMS 时发送）
+ * 客户端可据此自行降速，避免触发本地或上游 429
+ */
+
+import type { Context, Next } from "hono"
+import { globalSemaphore, streamSemaphore } from "./concurrency"
+import { envBool } from "./env"
+import { getMinRequestIntervalMs } from "./rate-limiter"
+
+export function buildRateLimitHeaders(): Record<string, string> {
+    const headers: Record<string, string> = {
+        "X-RateLimit-Min-Interval-Ms": String(getMinRequestIntervalMs()),
+    }
+    if (!globalSemaphore.isUnlimited()) {
+        headers["X-RateLimit-Limit"] = String(globalSemaphore.capacity)
+        headers["X-RateLimit-Remaining"] = String(globalSemaphore.available)
+    }
+    if (!streamSemaphore.isUnlimited()) {
+        headers["X-RateLimit-Streams-Remaining"] = String(streamSemaphore.available)
+    }
+    return headers
+}
+
+export async function rateLimitHeaders(c: Context, next: Next) {
+    await next()
+    if (!envBool("ANTI_API_RATELIMIT_HEADERS")) return
+    for (const [name, value] of Object.entries(buildRateLimitHeaders())) {
+        c.res.headers.set(name, value)
+    }
+}
@@ -8,13 +8,29 @@
  */
 
 import { MIN_REQUEST_INTERVAL_MS } from "./constants"
+import { envInt } from "./env"
+import { systemClock, type Clock } from "./clock"
 
-class RateLimiter {
+/**
+ * 🆕 请求最小间隔（ANTI_API_MIN_REQUEST_INTERVAL_MS，默认 MIN_REQUEST_INTERVAL_MS）
+ */
+export function getMinRequestIntervalMs(): number {
+    return Math.max(0, envInt("ANTI_API_MIN_REQUEST_INTERVAL_MS", MIN_REQUEST_INTERVAL_MS))
+}
+
+export class RateLimiter {
     private minInterval: number
     private lastCall: number | null = null
     private queue: Promise<void> = Promise.resolve()
 
-    constructor(minIntervalMs: number = MIN_REQUEST_INTERVAL_MS) {
+    constructor(minIntervalMs: number = MIN_REQUEST_INTERVAL_MS, private clock: Clock = systemClock) {
+        this.minInterval = minIntervalMs
+    }
+
+    /**
+     * 🆕 调整最小间隔（配置热加载）
+     */
+    setMinInterval(minIntervalMs: number): void {
         this.minInterval = minIntervalMs
     }
 
@@ -38,14 +54,14 @@ class RateLimiter {
 
         // 检查时间间隔
         if (this.lastCall !== null) {
-            const elapsed = Date.now() - this.lastCall
+            const elapsed = this.clock.now() - this.lastCall
             if (elapsed < this.minInterval) {
                 const waitTime = this.minInterval - elapsed
-                await new Promise(resolve => setTimeout(resolve, waitTime))
+                await this.clock.sleep(waitTime)
             }
         }
 
-        this.lastCall = Date.now()
+        this.lastCall = this.clock.now()
 
         // 释放队列锁（允许下一个请求开始等待间隔）
         resolveNext!()
@@ -70,21 +86,21 @@ class RateLimiter {
 
         // 检查时间间隔
         if (this.lastCall !== null) {
-            const elapsed = Date.now() - this.lastCall
+            const elapsed = this.clock.now() - this.lastCall
             if (elapsed < this.minInterval) {
                 const waitTime = this.minInterval - elapsed
-                await new Promise(resolve => setTimeout(resolve, waitTime))
+                await this.clock.sleep(waitTime)
             }
         }
 
-        this.lastCall = Date.now()
+        this.lastCall = this.clock.now()
 
         // 返回释放函数，调用者在请求完成后调用
         // 使用 released 标志防止重复释放
         return () => {
             if (!released) {
                 released = true
-                this.lastCall = Date.now()
+                this.lastCall = this.clock.now()
                 resolveNext!()
             }
         }
@@ -92,5 +108,5 @@ class RateLimiter {
 }
 
 // 全局单例，确保所有请求共享同一个限流器
-export const rateLimiter = new RateLimiter(MIN_REQUEST_INTERVAL_MS)
+export const rateLimiter = new RateLimiter(getMinRequestIntervalMs())
 
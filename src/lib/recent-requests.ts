@@ -0,0 +1,78 @@
+/**
+ * 最近请求环形缓冲（用于 /debug/recent）
+ * 容量 ANTI_API_RECENT_REQUESTS（默认 200），只保存摘要，不含 token
+ */
+
+import { envInt } from "./env"
+
+export interface RecentRequestSummary {
+    requestId: string
+    time: string
+    method: string
+    path: string
+    model?: string
+    clientId?: string
+    /** 🆕 真实客户端 IP（按 ANTI_API_TRUSTED_PROXIES 解析） */
+    clientIp?: string
+    stream?: boolean
+    status: number
+    latencyMs: number
+    endpoint?: string
+    retryAfterMs?: number | null
+    upstreamRequestId?: string
+    /** 🆕 X-Billing-Tag */
+    billingTag?: string
+}
+
+export class RingBuffer<T> {
+    private items: T[] = []
+    private next = 0
+
+    constructor(private capacity: number) { }
+
+    push(item: T): void {
+        if (this.capacity <= 0) return
+        if (this.items.length < this.capacity) {
+            this.items.push(item)
+        } else {
+            this.items[this.next] = item
+        }
+        this.next = (this.next + 1) % this.capacity
+    }
+
+    /**
+     * 最新的在前
+     */
+    recent(limit: number = this.capacity): T[] {
+        const ordered = this.items.length < this.capacity
+            ? [...this.items]
+            : [...this.items.slice(this.next), ...this.items.slice(0, this.next)]
+        return ordered.reverse().slice(0, Math.max(0, limit))
+    }
+
+    get size(): number {
+        return this.items.length
+    }
+
+    clear(): void {
+        this.items = []
+        this.next = 0
+    }
+}
+
+const recentRequests = new RingBuffer<RecentRequestSummary>(Math.max(0, envInt("ANTI_API_RECENT_REQUESTS", 200)))
+/** 🆕 进程启动以来按状态码的累计次数（不受环形缓冲容量限制） */
+const statusCounts = new Map<number, number>()
+
+export function recordRecentRequest(summary: RecentRequestSummary): void {
+    recentRequests.push(summary)
+    statusCounts.set(summary.status, (statusCounts.get(summary.status) ?? 0) + 1)
+}
+
+export function getStatusCounts(): Record<string, number> {
+    return Object.fromEntries(Array.from(statusCounts).sort((a, b) => a[0] - b[0]).map(([status, count]) => [String(status), count]))
+}
+
+export function getRecentRequests(limit: number): RecentRequestSummary[] {
+    return recentRequests.recent(limit)
+}
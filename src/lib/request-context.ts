@@ -0,0 +1,149 @@
+/**
+ * 请求上下文
+ * 基于 AsyncLocalStorage，在 HTTP 层创建，服务层（chat.ts 等）可读取/补充字段
+ */
+
+import { AsyncLocalStorage } from "node:async_hooks"
+import { readEnv } from "./env"
+import type { TraceContext } from "./tracing"
+
+export interface RequestContext {
+    requestId: string
+    startedAt: number
+    method: string
+    path: string
+    clientId?: string
+    model?: string
+    stream?: boolean
+    endpoint?: string
+    /** 🆕 endpoint 在本次请求端点列表中的下标 */
+    endpointIndex?: number
+    retryAfterMs?: number | null
+    upstreamRequestId?: string
+    /** 本次请求发出的上游调用次数（含重试与切换） */
+    upstreamCalls: number
+    trace?: TraceContext
+    /** 🆕 请求体 extra_query（已校验） */
+    extraQuery?: Record<string, string>
+    /** 🆕 基准测试模式下合成上游响应的累计延迟 */
+    syntheticUpstreamMs?: number
+    /** 🆕 已读取的上游响应 body 字节数（所有上游调用累计） */
+    upstreamBytes?: number
+    /** 🆕 本请求的 info 日志是否被采样 */
+    logSampled?: boolean
+    /** 🆕 上游 4xx 时是否回显请求体，以及最近一次发往上游的请求体 */
+    echoEnvelope?: boolean
+    upstreamEnvelope?: string
+    /** 🆕 X-Fault-Inject 请求头（仅故障注入开启时记录） */
+    faultHeader?: string
+    /** 🆕 X-Endpoint-Index 请求头（仅携带有效管理密钥时记录） */
+    forcedEndpoint?: string
+    /** 🆕 本请求各次上游调用的失败记录（用于"所有端点失败"的原因汇总） */
+    endpointFailures?: EndpointFailure[]
+    /** 🆕 计费归属标签（已按 ANTI_API_BILLING_TAGS 校验） */
+    billingTag?: string
+    /** 🆕 X-Inject-Delay-Ms 请求头（仅携带有效管理密钥时记录） */
+    delayHeader?: string
+    /** 🆕 上游 413 时计算的请求体大小（ANTI_API_REPORT_REQUEST_SIZE） */
+    requestSize?: { body_bytes: number; request_bytes?: number }
+}
+
+export type EndpointFailureKind = "timeout" | "connect_error" | "server_error" | "client_error" | "incomplete_response" | "other"
+
+export interface EndpointFailure {
+    endpoint: string
+    kind: EndpointFailureKind
+    status?: number
+    message?: string
+}
+
+const storage = new AsyncLocalStorage<RequestContext>()
+
+export function runWithRequestContext<T>(ctx: RequestContext, fn: () => T): T {
+    return storage.run(ctx, fn)
+}
+
+export function getRequestContext(): RequestContext | undefined {
+    return storage.getStore()
+}
+
+export function updateRequestContext(update: Partial<RequestContext>): void {
+    const ctx = storage.getStore()
+    if (ctx) Object.assign(ctx, update)
+}
+
+const SAFE_REQUEST_ID = /^[A-Za-z0-9._:-]{1,128}$/
+const HEADER_NAME = /^[A-Za-z0-9-]{1,64}$/
+export const DEFAULT_REQUEST_ID_HEADER = "X-Request-Id"
+
+/**
+ * 🆕 关联 ID 的请求/响应头名称（ANTI_API_REQUEST_ID_HEADER，默认 X-Request-Id）
+ */
+export function getRequestIdHeader(): string {
+    const configured = readEnv("ANTI_API_REQUEST_ID_HEADER")
+    return configured && HEADER_NAME.test(configured) ? configured : DEFAULT_REQUEST_ID_HEADER
+}
+
+/**
+ * 使用客户端传入的请求 ID（字符安全时），否则生成 UUID
+ */
+export function resolveRequestId(inbound: string | undefined): string {
+    const trimmed = (inbound || "").trim()
+    return SAFE_REQUEST_ID.test(trimmed) ? trimmed : crypto.randomUUID()
+}
+
+/** 🆕 客户端提前断开时用于日志/指标的合成状态码（nginx 约定） */
+export const CLIENT_CLOSED_REQUEST = 499
+
+export interface ResponseOutcome {
+    /** 客户端在响应结束前断开 */
+    cancelled: boolean
+    /** 已发送给客户端的 body 字节数 */
+    bytes: number
+}
+
+/**
+ * 包装响应 body，在 body 发送完毕或客户端断开时回调（流式响应也能拿到真实结束时间）
+ */
+export function trackResponseCompletion(res: Response, onDone: (outcome: ResponseOutcome) => void): Response {
+    let bytes = 0
+    let finished = false
+    const finish = (cancelled: boolean) => {
+        if (finished) return
+        finished = true
+        try {
+            onDone({ cancelled, bytes })
+        } catch {
+            // 统计回调失败不影响响应
+        }
+    }
+
+    if (!res.body) {
+        finish(false)
+        return res
+    }
+
+    const reader = res.body.getReader()
+    const body = new ReadableStream<Uint8Array>({
+        async pull(controller) {
+            try {
+                const { done, value } = await reader.read()
+                if (done) {
+                    controller.close()
+                    finish(false)
+                    return
+                }
+                bytes += value.byteLength
+                controller.enqueue(value)
+            } catch (error) {
+                controller.error(error)
+                finish(true)
+            }
+        },
+        cancel(reason) {
+            finish(true)
+            return reader.cancel(reason)
+        },
+    })
+    return new Response(body, { status: res.status, statusText: res.statusText, headers: res.headers })
+}
@@ -0,0 +1,73 @@
+/**
+ * 🆕 上游请求体字段策略（作用于信封中的 request 对象，发往上游之前执行）
+ * - ANTI_API_REQUEST_FIELD_DENY: 出现即拒绝的字段路径，返回 400 field_not_allowed 并指出字段
+ * - ANTI_API_REQUEST_FIELD_STRIP: 静默删除的字段路径（debug 日志）
+ * - ANTI_API_REQUEST_FIELD_ALLOW: 设置后，不在列表中的顶层字段一律删除
+ * 字段路径以 "." 分隔，如 `tools`、`generationConfig.maxOutputTokens`；均为逗号分隔列表，可写入配置文件并热加载
+ */
+
+import consola from "consola"
+import { envList } from "./env"
+import { AntigravityError } from "./error"
+
+type JsonObject = Record<string, unknown>
+
+function isObject(value: unknown): value is JsonObject {
+    return !!value && typeof value === "object" && !Array.isArray(value)
+}
+
+function resolveParent(root: JsonObject, path: string): { parent: JsonObject; key: string } | null {
+    const segments = path.split(".").filter(Boolean)
+    if (segments.length === 0) return null
+    let current: unknown = root
+    for (const segment of segments.slice(0, -1)) {
+        if (!isObject(current)) return null
+        current = current[segment]
+    }
+    if (!isObject(current)) return null
+    const key = segments[segments.length - 1]
+    return key in current ? { parent: current, key } : null
+}
+
+export interface FieldPolicy {
+    deny: string[]
+    strip: string[]
+    allow: string[]
+}
+
+export function getFieldPolicy(): FieldPolicy {
+    return {
+        deny: envList("ANTI_API_REQUEST_FIELD_DENY"),
+        strip: envList("ANTI_API_REQUEST_FIELD_STRIP"),
+        allow: envList("ANTI_API_REQUEST_FIELD_ALLOW"),
+    }
+}
+
+/**
+ * 原地应用策略；返回被删除的字段路径
+ */
+export function applyRequestFieldPolicy(request: unknown, policy: FieldPolicy = getFieldPolicy()): string[] {
+    if (!isObject(request)) return []
+    for (const path of policy.deny) {
+        if (resolveParent(request, path)) {
+            throw new AntigravityError(`Request field "${path}" is not allowed by proxy policy`, "field_not_allowed", 400)
+        }
+    }
+
+    const stripped: string[] = []
+    for (const path of policy.strip) {
+        const target = resolveParent(request, path)
+        if (!target) continue
+        delete target.parent[target.key]
+        stripped.push(path)
+    }
+    if (policy.allow.length > 0) {
+        for (const key of Object.keys(request)) {
+            if (policy.allow.includes(key)) continue
+            delete request[key]
+            stripped.push(key)
+        }
+    }
+    if (stripped.length > 0) consola.debug(`Request field policy stripped: ${stripped.join(", ")}`)
+    return stripped
+}
@@ -0,0 +1,42 @@
+/**
+ * 🆕 上游 413（请求体过大）
+ * 上游返回 413 时响应带 error_code: request_too_large，计数 upstream_request_too_large，
+ * 模型请求指标的 outcome 记为 request_too_large，便于与其他 4xx 区分
+ * ANTI_API_REPORT_REQUEST_SIZE=true 时错误体额外带 request_size：
+ * { body_bytes: 发往上游的完整请求体字节数, request_bytes: 其中 request 字段（对话内容、工具定义等）的字节数 }
+ * 方便客户端判断需要裁剪多少上下文；只在 413 时计算
+ */
+
+import { envBool } from "./env"
+import { incrementCounter } from "./metrics"
+import { updateRequestContext } from "./request-context"
+
+export interface RequestSize {
+    body_bytes: number
+    request_bytes?: number
+}
+
+export function isRequestSizeReportEnabled(): boolean {
+    return envBool("ANTI_API_REPORT_REQUEST_SIZE")
+}
+
+export function measureRequestSize(body: string): RequestSize {
+    const size: RequestSize = { body_bytes: Buffer.byteLength(body) }
+    try {
+        const request = JSON.parse(body)?.request
+        if (request !== undefined) size.request_bytes = Buffer.byteLength(JSON.stringify(request))
+    } catch {
+        // 非 JSON 请求体只报告总大小
+    }
+    return size
+}
+
+/**
+ * 上游返回 413 时调用
+ */
+export function noteRequestTooLarge(body: BodyInit | null | undefined): void {
+    incrementCounter("upstream_request_too_large")
+    if (isRequestSizeReportEnabled() && typeof body === "string") {
+        updateRequestContext({ requestSize: measureRequestSize(body) })
+    }
+}
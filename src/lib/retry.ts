@@ -3,6 +3,10 @@
  * 解析 Google API 返回的 retryDelay 并提供智能退避
  */
 
+import { envBool, envInt, envString } from "./env"
+import { systemClock, type Clock } from "./clock"
+import { setGauge } from "./metrics"
+
 /**
  * 解析 Duration 字符串 (e.g., "1.5s", "200ms", "1h16m0.667s")
  */
@@ -243,12 +247,72 @@ export function calculateRetryDelay(
 export async function applyRetryDelay(
     strategy: RetryStrategy,
     attempt: number,
-    enableJitter: boolean = true
+    enableJitter: boolean = true,
+    clock: Clock = systemClock
 ): Promise<boolean> {
     const delayMs = calculateRetryDelay(strategy, attempt, enableJitter)
     if (delayMs === null) {
         return false
     }
-    await new Promise(resolve => setTimeout(resolve, delayMs))
+    await clock.sleep(delayMs)
     return true
 }
+
+/**
+ * 🆕 是否对 5xx 等可重试错误整轮重试端点列表（ANTI_API_RETRIES，默认开启）
+ * 关闭后仍按端点列表依次切换，只是不再重新开始一轮
+ */
+export function areRetriesEnabled(): boolean {
+    return envBool("ANTI_API_RETRIES", true)
+}
+
+/**
+ * 🆕 重试预算（token bucket），防止上游大面积故障时的重试风暴
+ * - 每次整轮重试消耗 1 个 token，预算耗尽时不再重试；切换到下一个端点不消耗 token
+ * - 每次成功按比例回填（ANTI_API_RETRY_BUDGET_RATIO，默认 0.1）
+ * - 上限 ANTI_API_RETRY_BUDGET_MAX（默认 10）
+ * - ANTI_API_RETRY_BUDGET 默认跟随 ANTI_API_RETRIES（开启重试时开启），=0 关闭
+ */
+export class RetryBudget {
+    private tokens: number
+
+    constructor(private readonly getMax: () => number, private readonly getRatio: () => number, private readonly isEnabled: () => boolean) {
+        this.tokens = getMax()
+    }
+
+    get remaining(): number {
+        return this.tokens
+    }
+
+    tryConsume(): boolean {
+        if (!this.isEnabled()) return true
+        this.tokens = Math.min(this.tokens, this.getMax())
+        if (this.tokens < 1) {
+            setGauge("retry_budget_remaining", this.tokens)
+            return false
+        }
+        this.tokens -= 1
+        setGauge("retry_budget_remaining", this.tokens)
+        return true
+    }
+
+    recordSuccess(): void {
+        if (!this.isEnabled()) return
+        this.tokens = Math.min(this.getMax(), this.tokens + this.getRatio())
+        setGauge("retry_budget_remaining", this.tokens)
+    }
+
+    /** 恢复为满额（测试用） */
+    reset(): void {
+        this.tokens = this.getMax()
+    }
+}
+
+export const retryBudget = new RetryBudget(
+    () => Math.max(0, envInt("ANTI_API_RETRY_BUDGET_MAX", 10)),
+    () => {
+        const ratio = Number.parseFloat(envString("ANTI_API_RETRY_BUDGET_RATIO", "0.1"))
+        return Number.isFinite(ratio) && ratio >= 0 ? ratio : 0.1
+    },
+    () => envBool("ANTI_API_RETRY_BUDGET", areRetriesEnabled()),
+)
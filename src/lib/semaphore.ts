@@ -0,0 +1,139 @@
+/**
+ * 计数信号量
+ * limit <= 0 表示不限制；返回的释放函数只能生效一次
+ * 🆕 等待者默认先进先出（fifo），lifo 时优先唤醒最新的等待者
+ */
+
+export type Release = () => void
+export type QueuePolicy = "fifo" | "lifo"
+
+export class Semaphore {
+    private limit: number
+    private policy: QueuePolicy = "fifo"
+    private active = 0
+    private waiters: Array<(release: Release) => void> = []
+    private availabilityListeners = new Set<() => void>()
+
+    constructor(limit: number) {
+        this.limit = limit
+    }
+
+    get inUse(): number {
+        return this.active
+    }
+
+    get waiting(): number {
+        return this.waiters.length
+    }
+
+    get capacity(): number {
+        return this.limit
+    }
+
+    get queuePolicy(): QueuePolicy {
+        return this.policy
+    }
+
+    setQueuePolicy(policy: QueuePolicy): void {
+        this.policy = policy
+    }
+
+    isUnlimited(): boolean {
+        return this.limit <= 0
+    }
+
+    isFull(): boolean {
+        return !this.isUnlimited() && this.active >= this.limit
+    }
+
+    isIdle(): boolean {
+        return this.active === 0 && this.waiters.length === 0
+    }
+
+    /**
+     * 调整上限（扩容时立即唤醒等待者，缩容时等待在途请求自然结束）
+     */
+    setLimit(limit: number): void {
+        this.limit = limit
+        this.drain()
+    }
+
+    get available(): number {
+        if (this.isUnlimited()) return Number.POSITIVE_INFINITY
+        return Math.max(0, this.limit - this.active)
+    }
+
+    /**
+     * 等待出现空闲许可（不占用许可），超时返回 false
+     */
+    waitForAvailable(timeoutMs: number): Promise<boolean> {
+        if (!this.isFull() && this.waiters.length === 0) return Promise.resolve(true)
+        return new Promise(resolve => {
+            const listener = () => {
+                clearTimeout(timer)
+                this.availabilityListeners.delete(listener)
+                resolve(true)
+            }
+            const timer = setTimeout(() => {
+                this.availabilityListeners.delete(listener)
+                resolve(false)
+            }, Math.max(0, timeoutMs))
+            this.availabilityListeners.add(listener)
+        })
+    }
+
+    tryAcquire(): Release | null {
+        if (this.isFull()) return null
+        this.active++
+        return this.createRelease()
+    }
+
+    acquire(): Promise<Release> {
+        const release = this.waiters.length === 0 ? this.tryAcquire() : null
+        if (release) return Promise.resolve(release)
+        return new Promise(resolve => {
+            this.waiters.push(resolve)
+        })
+    }
+
+    /**
+     * 🆕 带超时的排队获取：超时后从队列移除并返回 null
+     */
+    acquireWithTimeout(timeoutMs: number): Promise<Release | null> {
+        const release = this.waiters.length === 0 ? this.tryAcquire() : null
+        if (release) return Promise.resolve(release)
+        return new Promise(resolve => {
+            const waiter = (granted: Release) => {
+                clearTimeout(timer)
+                resolve(granted)
+            }
+            const timer = setTimeout(() => {
+                const index = this.waiters.indexOf(waiter)
+                if (index >= 0) this.waiters.splice(index, 1)
+                resolve(null)
+            }, Math.max(0, timeoutMs))
+            this.waiters.push(waiter)
+        })
+    }
+
+    private createRelease(): Release {
+        let released = false
+        return () => {
+            if (released) return
+            released = true
+            this.active--
+            this.drain()
+        }
+    }
+
+    private drain(): void {
+        while (this.waiters.length > 0 && !this.isFull()) {
+            const next = this.policy === "lifo" ? this.waiters.pop()! : this.waiters.shift()!
+            this.active++
+            next(this.createRelease())
+        }
+        if (!this.isFull() && this.availabilityListeners.size > 0) {
+            for (const listener of Array.from(this.availabilityListeners)) listener()
+        }
+    }
+}
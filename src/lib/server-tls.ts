@@ -0,0 +1,73 @@
+/**
+ * 🆕 入站 HTTPS / 双向 TLS
+ * - ANTI_API_TLS_CERT / ANTI_API_TLS_KEY: 服务端证书与私钥（PEM 文件路径），都设置时监听 HTTPS
+ * - ANTI_API_MTLS_CA: 客户端证书必须链到的 CA（PEM），设置后在 TLS 握手阶段拒绝无有效客户端证书的连接
+ * 均未设置时保持明文 HTTP
+ * 🆕 ANTI_API_MIN_TLS_VERSION = 1.2 | 1.3（默认 1.2），通过 secureOptions 禁用更低版本，低于下限的握手被拒绝
+ * 密码套件沿用 Bun（BoringSSL）的默认策略：TLS 1.2 仅 ECDHE + AEAD，TLS 1.3 固定套件；Bun.serve 不支持自定义套件
+ */
+
+import { readFileSync } from "fs"
+import { constants } from "crypto"
+import { envString } from "./env"
+
+export type MinTlsVersion = "1.2" | "1.3"
+
+export interface ServerTlsOptions {
+    cert: string
+    key: string
+    ca?: string
+    requestCert?: boolean
+    rejectUnauthorized?: boolean
+    secureOptions: number
+}
+
+/**
+ * 解析最低 TLS 版本；非法值抛出（启动时拒绝）
+ */
+export function getMinTlsVersion(): MinTlsVersion {
+    const raw = envString("ANTI_API_MIN_TLS_VERSION", "1.2").replace(/^tls\s*v?/i, "")
+    if (raw === "1.2" || raw === "1.3") return raw
+    throw new Error(`Invalid ANTI_API_MIN_TLS_VERSION "${raw}" (expected 1.2 or 1.3)`)
+}
+
+export function buildSecureOptions(minVersion: MinTlsVersion): number {
+    let options = constants.SSL_OP_NO_SSLv2 | constants.SSL_OP_NO_SSLv3 | constants.SSL_OP_NO_TLSv1 | constants.SSL_OP_NO_TLSv1_1
+    if (minVersion === "1.3") options |= constants.SSL_OP_NO_TLSv1_2
+    return options
+}
+
+function readPem(name: string, path: string): string {
+    try {
+        return readFileSync(path, "utf-8")
+    } catch (error) {
+        throw new Error(`Cannot read ${name} (${path}): ${(error as Error).message}`)
+    }
+}
+
+/**
+ * 未配置时返回 undefined；配置不完整或文件不可读时抛出（由入口打印并退出）
+ */
+export function getServerTlsOptions(): ServerTlsOptions | undefined {
+    const certPath = envString("ANTI_API_TLS_CERT")
+    const keyPath = envString("ANTI_API_TLS_KEY")
+    const caPath = envString("ANTI_API_MTLS_CA")
+    if (!certPath && !keyPath && !caPath) return undefined
+    if (!certPath || !keyPath) {
+        throw new Error(caPath
+            ? "ANTI_API_MTLS_CA requires ANTI_API_TLS_CERT and ANTI_API_TLS_KEY"
+            : "ANTI_API_TLS_CERT and ANTI_API_TLS_KEY must be set together")
+    }
+
+    const options: ServerTlsOptions = {
+        cert: readPem("ANTI_API_TLS_CERT", certPath),
+        key: readPem("ANTI_API_TLS_KEY", keyPath),
+        secureOptions: buildSecureOptions(getMinTlsVersion()),
+    }
+    if (caPath) {
+        options.ca = readPem("ANTI_API_MTLS_CA", caPath)
+        options.requestCert = true
+        options.rejectUnauthorized = true
+    }
+    return options
+}
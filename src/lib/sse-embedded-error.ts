@@ -0,0 +1,85 @@
+/**
+ * 🆕 检测 200 SSE 响应中内嵌的错误帧（上游有时以 200 返回 {"error": {...}}）
+ * ANTI_API_VALIDATE_SSE=1 时启用（默认关闭），命中后按错误重新分类
+ */
+
+import { envBool } from "./env"
+
+export interface EmbeddedSseError {
+    status: number
+    message: string
+    /** 原始 data 内容，作为 UpstreamError body */
+    raw: string
+}
+
+/** google.rpc.Code 字符串到 HTTP 状态码 */
+const RPC_STATUS_TO_HTTP: Record<string, number> = {
+    INVALID_ARGUMENT: 400,
+    FAILED_PRECONDITION: 400,
+    OUT_OF_RANGE: 400,
+    UNAUTHENTICATED: 401,
+    PERMISSION_DENIED: 403,
+    NOT_FOUND: 404,
+    ABORTED: 409,
+    ALREADY_EXISTS: 409,
+    RESOURCE_EXHAUSTED: 429,
+    CANCELLED: 499,
+    INTERNAL: 500,
+    UNKNOWN: 500,
+    DATA_LOSS: 500,
+    NOT_IMPLEMENTED: 501,
+    UNIMPLEMENTED: 501,
+    UNAVAILABLE: 503,
+    DEADLINE_EXCEEDED: 504,
+}
+
+export function isSseValidationEnabled(): boolean {
+    return envBool("ANTI_API_VALIDATE_SSE")
+}
+
+/**
+ * 提取单个 SSE 事件的 data 内容（多行 data 以换行拼接）
+ */
+export function extractSseEventData(event: string): string | null {
+    const dataLines: string[] = []
+    const lines = event.split(/\r?\n/)
+    for (const line of lines) {
+        if (!line.startsWith("data:")) continue
+        let value = line.slice(5)
+        if (value.startsWith(" ")) value = value.slice(1)
+        dataLines.push(value)
+    }
+    if (dataLines.length === 0) return null
+    return dataLines.join("\n")
+}
+
+function toEmbeddedError(error: unknown, raw: string): EmbeddedSseError | null {
+    if (!error || typeof error !== "object") return null
+    const { code, status, message } = error as { code?: unknown; status?: unknown; message?: unknown }
+    let httpStatus = typeof code === "number" && code >= 400 && code <= 599 ? code : 0
+    if (!httpStatus && typeof status === "string") httpStatus = RPC_STATUS_TO_HTTP[status.toUpperCase()] || 0
+    return {
+        status: httpStatus || 502,
+        message: typeof message === "string" && message ? message : "Upstream returned an error inside a 200 stream",
+        raw,
+    }
+}
+
+/**
+ * 扫描缓冲的 SSE body，返回第一个内嵌错误帧；没有则返回 null
+ */
+export function findEmbeddedSseError(body: string): EmbeddedSseError | null {
+    for (const event of body.split(/\r?\n\r?\n/)) {
+        const data = extractSseEventData(event)?.trim()
+        if (!data || data === "[DONE]") continue
+        let parsed: any
+        try {
+            parsed = JSON.parse(data)
+        } catch {
+            continue
+        }
+        const found = toEmbeddedError(parsed?.error, data) ?? toEmbeddedError(parsed?.response?.error, data)
+        if (found) return found
+    }
+    return null
+}
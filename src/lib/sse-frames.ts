@@ -0,0 +1,166 @@
+/**
+ * 🆕 流式 SSE 分帧，限制单帧大小
+ * ANTI_API_MAX_SSE_FRAME_BYTES（默认 8 MiB，0 = 不限制）：超限帧不再继续缓冲
+ * ANTI_API_OVERSIZED_SSE_FRAME = skip | error（默认 skip）
+ *   skip: 丢弃该帧直到下一个帧边界，计数 sse_frames_oversized
+ *   error: 以 502 sse_frame_too_large 结束请求
+ * 🆕 ANTI_API_MAX_SSE_FRAMES（默认 100000，0 = 不限制）：非流式聚合上游 SSE 时最多处理的帧数，
+ *   超出后停止聚合，响应带 truncated: true；帧数记入 sse_decode_frames，截断计数 sse_frame_limit_truncations
+ */
+
+import { envInt, envString } from "./env"
+
+export const DEFAULT_MAX_SSE_FRAME_BYTES = 8 * 1024 * 1024
+
+export function getMaxSseFrameBytes(): number {
+    return Math.max(0, envInt("ANTI_API_MAX_SSE_FRAME_BYTES", DEFAULT_MAX_SSE_FRAME_BYTES))
+}
+
+export function getOversizedSseFrameMode(): "skip" | "error" {
+    return envString("ANTI_API_OVERSIZED_SSE_FRAME", "skip").toLowerCase() === "error" ? "error" : "skip"
+}
+
+const FRAME_SEPARATOR = /\r?\n\r?\n/
+
+export const DEFAULT_MAX_SSE_FRAMES = 100000
+
+export function getMaxSseFrames(): number {
+    return Math.max(0, envInt("ANTI_API_MAX_SSE_FRAMES", DEFAULT_MAX_SSE_FRAMES))
+}
+
+/**
+ * 🆕 按帧边界切分完整的 SSE 文本，最多返回 maxFrames 帧（0 = 不限制）；达到上限后不再继续扫描
+ */
+export function splitSseFrames(raw: string, maxFrames: number): { frames: string[]; truncated: boolean } {
+    const separator = new RegExp(FRAME_SEPARATOR.source, "g")
+    const frames: string[] = []
+    let start = 0
+    while (start < raw.length) {
+        const match = separator.exec(raw)
+        const end = match ? match.index : raw.length
+        const frame = raw.slice(start, end)
+        start = match ? match.index + match[0].length : raw.length
+        if (!frame.trim()) continue
+        if (maxFrames > 0 && frames.length >= maxFrames) return { frames, truncated: true }
+        frames.push(frame)
+    }
+    return { frames, truncated: false }
+}
+
+export class SseFrameSplitter {
+    private buffer = ""
+    private skipping = false
+
+    /**
+     * onOversized 在每个超限帧上调用一次；抛出异常可直接终止解析
+     */
+    constructor(private maxBytes: number, private onOversized: (bytes: number) => void = () => { }) { }
+
+    /**
+     * 追加解码后的文本，返回已完整的帧（不含分隔符）
+     */
+    push(text: string): string[] {
+        this.buffer += text
+        const frames = this.buffer.split(FRAME_SEPARATOR)
+        this.buffer = frames.pop() || ""
+        // 超限帧的剩余部分直到下一个边界都丢弃
+        if (this.skipping && frames.length > 0) {
+            frames.shift()
+            this.skipping = false
+        }
+
+        const accepted: string[] = []
+        for (const frame of frames) {
+            const bytes = this.byteLength(frame)
+            if (this.maxBytes > 0 && bytes > this.maxBytes) {
+                this.onOversized(bytes)
+                continue
+            }
+            accepted.push(frame)
+        }
+
+        if (!this.skipping && this.maxBytes > 0) {
+            const pending = this.byteLength(this.buffer)
+            if (pending > this.maxBytes) {
+                this.onOversized(pending)
+                this.skipping = true
+                this.buffer = ""
+            }
+        } else if (this.skipping) {
+            this.buffer = ""
+        }
+        return accepted
+    }
+
+    /**
+     * 流结束时剩余的未完整帧（正在跳过时为空）
+     */
+    flush(): string {
+        const rest = this.skipping ? "" : this.buffer
+        this.buffer = ""
+        this.skipping = false
+        return rest
+    }
+
+    private byteLength(text: string): number {
+        // 字符数不超过上限时字节数最多为 3 倍，先用长度快速判断
+        if (text.length * 3 <= this.maxBytes) return text.length
+        return Buffer.byteLength(text)
+    }
+}
+
+/**
+ * 🆕 流式请求的响应是否真的是 SSE（缺少 Content-Type 时按 SSE 处理）
+ */
+export function isEventStreamContentType(contentType: string | null): boolean {
+    if (!contentType) return true
+    return contentType.split(";")[0].trim().toLowerCase() === "text/event-stream"
+}
+
+/**
+ * 🆕 缓冲读取完整响应体，两次收到数据之间超过 idleMs（0 = 不限制）时调用 onIdle 并以 "Stream idle timeout" 失败
+ * onIdle 应中断上游请求，释放连接
+ */
+export async function readTextWithIdleTimeout(response: Response, idleMs: number, onIdle: () => void): Promise<string> {
+    if (idleMs <= 0 || !response.body) return response.text()
+    const reader = response.body.getReader()
+    const decoder = new TextDecoder()
+    let text = ""
+    let timer: ReturnType<typeof setTimeout> | undefined
+    const idle = () => new Promise<never>((_, reject) => {
+        timer = setTimeout(() => {
+            onIdle()
+            reader.cancel().catch(() => { })
+            reject(new Error("Stream idle timeout"))
+        }, idleMs)
+    })
+    try {
+        while (true) {
+            const { done, value } = await Promise.race([reader.read(), idle()])
+            clearTimeout(timer)
+            if (done) break
+            text += decoder.decode(value, { stream: true })
+        }
+        return text + decoder.decode()
+    } finally {
+        clearTimeout(timer)
+        // 超时后可能仍有挂起的 read，释放锁失败时忽略
+        try { reader.releaseLock() } catch { }
+    }
+}
+
+/**
+ * 🆕 把非 SSE 的完整响应体转换为等价的 SSE 文本（流式降级为缓冲时使用）
+ * 支持 JSON 对象、JSON 数组（每个元素一帧），其余内容原样返回交给 SSE 解析
+ */
+export function bufferedBodyToSse(body: string): string {
+    const trimmed = body.trim()
+    if (!trimmed.startsWith("{") && !trimmed.startsWith("[")) return body
+    try {
+        const parsed = JSON.parse(trimmed)
+        const items = Array.isArray(parsed) ? parsed : [parsed]
+        return items.map(item => `data: ${JSON.stringify(item)}\n\n`).join("")
+    } catch {
+        return body
+    }
+}
@@ -0,0 +1,40 @@
+/**
+ * SSE 帧改写
+ * 解析每个 data 帧的 JSON，把 model 字段改写为客户端请求的名称；非 JSON 帧原样透传
+ */
+
+function rewriteModelField(payload: any, model: string): boolean {
+    let changed = false
+    if (payload && typeof payload === "object") {
+        if (typeof payload.model === "string" && payload.model !== model) {
+            payload.model = model
+            changed = true
+        }
+        if (payload.message && typeof payload.message === "object" && typeof payload.message.model === "string" && payload.message.model !== model) {
+            payload.message.model = model
+            changed = true
+        }
+    }
+    return changed
+}
+
+export function rewriteSseModel(event: string, model: string): string {
+    if (!event.includes("\"model\"")) return event
+    const lines = event.split("\n")
+    let changed = false
+    for (let i = 0; i < lines.length; i++) {
+        const line = lines[i]
+        if (!line.startsWith("data:")) continue
+        const data = line.slice(5).trimStart()
+        try {
+            const parsed = JSON.parse(data)
+            if (rewriteModelField(parsed, model)) {
+                lines[i] = "data: " + JSON.stringify(parsed)
+                changed = true
+            }
+        } catch {
+            // 非 JSON 帧原样透传
+        }
+    }
+    return changed ? lines.join("\n") : event
+}
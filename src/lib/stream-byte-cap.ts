@@ -0,0 +1,37 @@
+/**
+ * 🆕 单个流式请求的字节上限（ANTI_API_MAX_STREAM_BYTES，默认 0 = 不限制）
+ * 累计下发字节数即将超过上限时停止读取上游，并以错误帧（type: stream_too_large）结束流，
+ * 用于限制失控生成带来的成本与带宽；每次截断计入指标 stream_truncations
+ */
+
+import consola from "consola"
+import { envInt } from "./env"
+import { AntigravityError } from "./error"
+import { incrementCounter } from "./metrics"
+
+export function getMaxStreamBytes(): number {
+    return Math.max(0, envInt("ANTI_API_MAX_STREAM_BYTES", 0))
+}
+
+/**
+ * 包装翻译后的 SSE 事件流：超过上限时抛出 stream_too_large（提前结束迭代会同时关闭上游读取）
+ */
+export async function* capStreamBytes(
+    events: AsyncIterable<string>,
+    maxBytes: number = getMaxStreamBytes(),
+): AsyncGenerator<string, void, unknown> {
+    if (maxBytes <= 0) {
+        yield* events
+        return
+    }
+    let total = 0
+    for await (const event of events) {
+        total += Buffer.byteLength(event)
+        if (total > maxBytes) {
+            incrementCounter("stream_truncations", { reason: "max_bytes" })
+            consola.warn(`Stream exceeded ANTI_API_MAX_STREAM_BYTES (${maxBytes}), truncating`)
+            throw new AntigravityError(`Stream exceeded the ${maxBytes} byte limit`, "stream_too_large", 502)
+        }
+        yield event
+    }
+}
@@ -0,0 +1,66 @@
+/**
+ * 🆕 流式响应中途错误帧
+ *
+ * 上游字节流中途出错时，向客户端发送最后一帧错误而不是直接断开连接：
+ * - Anthropic（/v1/messages）：
+ *     event: error
+ *     data: {"type":"error","error":{"type":"<error_type>","message":"...","request_id":"..."}}
+ * - OpenAI（/v1/chat/completions）：
+ *     data: {"error":{"type":"<error_type>","message":"...","request_id":"..."}}
+ *
+ * error 中可能附带 status_code / error_code / reason / upstream_request_id（🆕 所有端点失败时另有 endpoints 摘要）。
+ * 正常结束时 Anthropic 以 message_stop、OpenAI 以 data: [DONE] 收尾；错误帧之后不再发送这两者，
+ * 客户端据此区分"完整结束"与"被截断"。每次发送错误帧计入指标 stream_errors。
+ */
+
+import { AllEndpointsFailedError, AntigravityError, ConcurrencyLimitError, summarizeUpstreamError, UpstreamError } from "./error"
+import { incrementCounter } from "./metrics"
+import { getRequestContext, type EndpointFailure } from "./request-context"
+
+export interface StreamErrorBody {
+    type: string
+    message: string
+    status_code?: number
+    error_code?: string
+    reason?: string
+    upstream_request_id?: string
+    request_id?: string
+    endpoints?: EndpointFailure[]
+}
+
+export function buildStreamErrorBody(error: unknown): StreamErrorBody {
+    const requestId = getRequestContext()?.requestId
+    const withRequestId = (body: StreamErrorBody): StreamErrorBody => requestId ? { ...body, request_id: requestId } : body
+
+    if (error instanceof UpstreamError) {
+        const summary = summarizeUpstreamError(error)
+        return withRequestId({
+            type: "upstream_error",
+            message: summary.message,
+            status_code: error.status,
+            ...(summary.errorCode ? { error_code: summary.errorCode } : {}),
+            ...(summary.reason ? { reason: summary.reason } : {}),
+            ...(error.upstreamRequestId ? { upstream_request_id: error.upstreamRequestId } : {}),
+            ...(error instanceof AllEndpointsFailedError ? { endpoints: error.endpoints } : {}),
+        })
+    }
+    if (error instanceof AntigravityError) {
+        return withRequestId({ type: error.code, message: error.message, status_code: error.status })
+    }
+    if (error instanceof ConcurrencyLimitError) {
+        return withRequestId({ type: "rate_limit_error", message: error.message, status_code: 429 })
+    }
+    return withRequestId({ type: "stream_error", message: (error as Error)?.message || String(error) })
+}
+
+/**
+ * 生成错误帧（SSE 文本）并计数
+ */
+export function formatStreamErrorFrame(error: unknown, format: "anthropic" | "openai"): string {
+    const body = buildStreamErrorBody(error)
+    incrementCounter("stream_errors", { format, type: body.type })
+    if (format === "anthropic") {
+        return `event: error\ndata: ${JSON.stringify({ type: "error", error: body })}\n\n`
+    }
+    return `data: ${JSON.stringify({ error: body })}\n\n`
+}
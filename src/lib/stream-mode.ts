@@ -0,0 +1,60 @@
+/**
+ * 🆕 流式/非流式选择
+ * 优先级：请求体 stream 字段 > Accept 头（text/event-stream = 流式，application/json = 非流式）> 默认非流式
+ * 🆕 流式请求的上游模式：stream（默认，边收边发）| buffered（上游缓冲后再以 SSE 下发，可完整 failover）
+ *   🆕 优先级：X-Upstream-Mode 请求头 > 按模型配置 ANTI_API_STREAM_UPSTREAM_MODE_BY_MODEL
+ *   > 全局 ANTI_API_STREAM_UPSTREAM_MODE > stream
+ *   按模型配置为逗号分隔的 `<模型>=<stream|buffered>`，模型为路由后的 ID（别名已展开），支持 `前缀*` 通配，
+ *   精确匹配优先，其次最长前缀；例：`gemini-3-flash=stream,claude-opus-*=buffered`（大模型缓冲以便 failover）
+ */
+
+import { envList, envString } from "./env"
+
+export type UpstreamMode = "stream" | "buffered"
+
+export function acceptsEventStream(accept: string | undefined): boolean | undefined {
+    if (!accept) return undefined
+    const types = accept.split(",").map(part => part.split(";")[0].trim().toLowerCase())
+    if (types.includes("text/event-stream")) return true
+    if (types.includes("application/json")) return false
+    return undefined
+}
+
+export function resolveStreamMode(streamField: unknown, accept: string | undefined): boolean {
+    if (typeof streamField === "boolean") return streamField
+    return acceptsEventStream(accept) ?? false
+}
+
+function parseUpstreamMode(raw: string | undefined): UpstreamMode | undefined {
+    const value = raw?.trim().toLowerCase()
+    if (value === "buffered" || value === "buffer") return "buffered"
+    if (value === "stream") return "stream"
+    return undefined
+}
+
+/**
+ * 🆕 按模型配置的上游模式；未配置或非法条目返回 undefined
+ */
+export function getModelUpstreamMode(model: string | undefined): UpstreamMode | undefined {
+    if (!model) return undefined
+    const id = model.trim().toLowerCase()
+    let best: { prefix: string; mode: UpstreamMode } | undefined
+    for (const item of envList("ANTI_API_STREAM_UPSTREAM_MODE_BY_MODEL")) {
+        const separator = item.lastIndexOf("=")
+        const key = item.slice(0, separator).trim().toLowerCase()
+        const mode = parseUpstreamMode(item.slice(separator + 1))
+        if (separator <= 0 || !key || !mode) continue
+        if (key === id) return mode
+        if (!key.endsWith("*")) continue
+        const prefix = key.slice(0, -1)
+        if (id.startsWith(prefix) && (!best || prefix.length > best.prefix.length)) best = { prefix, mode }
+    }
+    return best?.mode
+}
+
+export function resolveUpstreamMode(header: string | undefined, model?: string): UpstreamMode {
+    return parseUpstreamMode(header)
+        ?? getModelUpstreamMode(model)
+        ?? parseUpstreamMode(envString("ANTI_API_STREAM_UPSTREAM_MODE"))
+        ?? "stream"
+}
@@ -0,0 +1,60 @@
+/**
+ * SSE 调试分流（tee）
+ * 设置 ANTI_API_TEE_STREAM_DIR 后，将上游 SSE 原始字节额外写入每个请求独立的文件
+ * 写入为异步缓冲，磁盘缓慢时丢弃超出缓冲上限的数据，绝不阻塞客户端；只写 body，不写请求头/token
+ */
+
+import { createWriteStream, mkdirSync, type WriteStream } from "fs"
+import { join } from "path"
+import consola from "consola"
+import { envString } from "./env"
+
+/** 单个 tee 文件允许积压的最大字节数 */
+const MAX_PENDING_BYTES = 4 * 1024 * 1024
+
+export class StreamTee {
+    private stream: WriteStream
+    private droppedBytes = 0
+    readonly path: string
+
+    constructor(path: string) {
+        this.path = path
+        this.stream = createWriteStream(path, { flags: "a" })
+        this.stream.on("error", (error) => {
+            consola.warn(`[Tee] write failed for ${path}:`, error.message)
+        })
+    }
+
+    write(chunk: Uint8Array): void {
+        if (this.stream.destroyed) return
+        if (this.stream.writableLength > MAX_PENDING_BYTES) {
+            this.droppedBytes += chunk.byteLength
+            return
+        }
+        this.stream.write(chunk)
+    }
+
+    close(): void {
+        if (this.stream.destroyed) return
+        if (this.droppedBytes > 0) {
+            this.stream.write(`\n: tee dropped ${this.droppedBytes} bytes (slow disk)\n`)
+        }
+        this.stream.end()
+    }
+}
+
+/**
+ * 未开启时返回 null
+ */
+export function openStreamTee(requestId: string): StreamTee | null {
+    const dir = envString("ANTI_API_TEE_STREAM_DIR")
+    if (!dir) return null
+    try {
+        mkdirSync(dir, { recursive: true })
+        const safeId = requestId.replace(/[^A-Za-z0-9._-]/g, "_")
+        return new StreamTee(join(dir, `${Date.now()}-${safeId}.sse`))
+    } catch (error) {
+        consola.warn("[Tee] cannot open tee file:", (error as Error).message)
+        return null
+    }
+}
@@ -0,0 +1,91 @@
+/**
+ * 🆕 同一会话只保留最新的排队请求（"停止并重新生成"场景）
+ * ANTI_API_SUPERSEDE_BY_SESSION=true 时开启（默认关闭）；只对携带 X-Session-Id 的请求生效，
+ * 会话按 X-Client-Id 隔离，不同客户端的同名会话互不影响
+ * 同一会话的新请求到达时，仍在限流 / 并发许可队列中等待的旧请求立即以 409 superseded 结束；
+ * 已经发往上游的请求不会被中断
+ */
+
+import type { Context } from "hono"
+import { getClientId } from "./client-id"
+import { envBool } from "./env"
+import { AntigravityError } from "./error"
+import { incrementCounter } from "./metrics"
+
+export const SESSION_HEADER = "X-Session-Id"
+
+export interface SessionTicket {
+    /** 新请求到达时 reject（SupersededError） */
+    readonly superseded: Promise<never>
+    /** 已开始调用上游，此后不再被取代 */
+    dispatch(): void
+    /** 请求结束，从会话表中移除 */
+    done(): void
+}
+
+interface SessionEntry {
+    supersede: () => void
+}
+
+const latestBySession = new Map<string, SessionEntry>()
+
+export function isSupersedeEnabled(): boolean {
+    return envBool("ANTI_API_SUPERSEDE_BY_SESSION")
+}
+
+export function supersededError(): AntigravityError {
+    return new AntigravityError("Request superseded by a newer request in the same session", "superseded", 409)
+}
+
+/**
+ * 登记会话中的最新请求并取代同一会话中仍在排队的旧请求
+ */
+export function registerSessionRequest(sessionKey: string): SessionTicket {
+    latestBySession.get(sessionKey)?.supersede()
+
+    let reject!: (error: Error) => void
+    const superseded = new Promise<never>((_, fail) => { reject = fail })
+    superseded.catch(() => { })
+    const entry: SessionEntry = {
+        supersede: () => {
+            incrementCounter("requests_superseded")
+            reject(supersededError())
+            if (latestBySession.get(sessionKey) === entry) latestBySession.delete(sessionKey)
+        },
+    }
+    latestBySession.set(sessionKey, entry)
+
+    const remove = () => {
+        if (latestBySession.get(sessionKey) === entry) latestBySession.delete(sessionKey)
+    }
+    return { superseded, dispatch: remove, done: remove }
+}
+
+/**
+ * 开启且请求携带会话头时返回 ticket，否则返回 null
+ */
+export function sessionTicketFor(c: Context): SessionTicket | null {
+    if (!isSupersedeEnabled()) return null
+    const sessionId = c.req.header(SESSION_HEADER)?.trim()
+    if (!sessionId) return null
+    return registerSessionRequest(`${getClientId(c)}:${sessionId}`)
+}
+
+/**
+ * 在排队等待期间允许被取代；onLate 用于释放被取代后才拿到的许可
+ */
+export async function withSupersede<T>(ticket: SessionTicket | null, promise: Promise<T>, onLate?: (value: T) => void): Promise<T> {
+    if (!ticket) return promise
+    let lost = false
+    if (onLate) promise.then(value => { if (lost) onLate(value) }, () => { })
+    try {
+        return await Promise.race([promise, ticket.superseded])
+    } catch (error) {
+        lost = true
+        throw error
+    }
+}
+
+export function resetSessionTracking(): void {
+    latestBySession.clear()
+}
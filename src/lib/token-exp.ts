@@ -0,0 +1,47 @@
+/**
+ * 🆕 本地访问令牌过期预检（ANTI_API_CHECK_TOKEN_EXP=1 开启，默认关闭）
+ * 令牌为 JWT 时只解码（不验签）payload 中的 exp；已过期则在调用上游前直接返回 401 token_expired
+ * 非 JWT 的不透明令牌（如 ya29.*）跳过检查；任何情况下都不记录令牌内容
+ */
+
+import { envBool } from "./env"
+import { AntigravityError } from "./error"
+
+/**
+ * 🆕 解码（不验签）JWT payload；不是 JWT 时返回 null
+ */
+export function decodeJwtPayload(token: string): Record<string, unknown> | null {
+    const parts = token.split(".")
+    if (parts.length !== 3 || !parts[1]) return null
+    try {
+        const payload = JSON.parse(Buffer.from(parts[1], "base64url").toString("utf-8"))
+        return payload && typeof payload === "object" && !Array.isArray(payload) ? payload : null
+    } catch {
+        return null
+    }
+}
+
+/**
+ * 返回 exp（秒级时间戳）；不是 JWT 或没有 exp 时返回 null
+ */
+export function decodeJwtExp(token: string): number | null {
+    const exp = decodeJwtPayload(token)?.exp
+    return typeof exp === "number" && Number.isFinite(exp) ? exp : null
+}
+
+export function isTokenExpired(token: string, nowMs: number = Date.now()): boolean {
+    const exp = decodeJwtExp(token)
+    return exp !== null && exp * 1000 <= nowMs
+}
+
+/**
+ * 开启时对 Authorization: Bearer 令牌做预检，过期则抛出 401 token_expired
+ */
+export function assertTokenNotExpired(headers: HeadersInit | undefined): void {
+    if (!envBool("ANTI_API_CHECK_TOKEN_EXP")) return
+    const auth = new Headers(headers).get("Authorization") || ""
+    if (!auth.toLowerCase().startsWith("bearer ")) return
+    if (isTokenExpired(auth.slice(7).trim())) {
+        throw new AntigravityError("Access token has expired; refresh it and retry", "token_expired", 401)
+    }
+}
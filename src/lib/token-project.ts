@@ -0,0 +1,47 @@
+/**
+ * 🆕 访问令牌与 project 不匹配预检（ANTI_API_CHECK_TOKEN_PROJECT=1 开启，默认关闭）
+ * 令牌为 JWT 时只解码（不验签）payload，取 project_id / projectId / project 声明，
+ * 或 aud 中形如 `projects/<id>` 的部分；与请求体的 project 明显不一致时在调用上游前直接返回
+ * 403 project_token_mismatch，代替上游含糊的 403
+ * 不透明令牌（如 ya29.*）或令牌中没有 project 信息时跳过检查；任何情况下都不记录令牌内容
+ */
+
+import { envBool } from "./env"
+import { AntigravityError } from "./error"
+import { decodeJwtPayload } from "./token-exp"
+
+const PROJECT_CLAIMS = ["project_id", "projectId", "project"]
+const AUDIENCE_PROJECT = /(?:^|\/)projects\/([^/]+)/
+
+/**
+ * 令牌中声明的 project；不是 JWT 或没有相关声明时返回 null
+ */
+export function extractTokenProjects(token: string): string[] | null {
+    const payload = decodeJwtPayload(token)
+    if (!payload) return null
+    const projects = new Set<string>()
+    for (const claim of PROJECT_CLAIMS) {
+        const value = payload[claim]
+        if (typeof value === "string" && value) projects.add(value)
+    }
+    const audiences = Array.isArray(payload.aud) ? payload.aud : [payload.aud]
+    for (const audience of audiences) {
+        const match = typeof audience === "string" ? AUDIENCE_PROJECT.exec(audience) : null
+        if (match) projects.add(match[1])
+    }
+    return projects.size > 0 ? [...projects] : null
+}
+
+/**
+ * 开启时检查令牌声明的 project 是否包含请求的 project，不包含则抛出 403 project_token_mismatch
+ */
+export function assertTokenMatchesProject(token: string, project: string | undefined): void {
+    if (!project || !envBool("ANTI_API_CHECK_TOKEN_PROJECT")) return
+    const projects = extractTokenProjects(token)
+    if (!projects || projects.includes(project)) return
+    throw new AntigravityError(
+        `Access token belongs to project ${projects.join(", ")} but the request targets ${project}`,
+        "project_token_mismatch",
+        403,
+    )
+}
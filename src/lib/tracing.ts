@@ -0,0 +1,142 @@
+/**
+ * 🆕 OpenTelemetry 追踪（ANTI_API_OTEL=1 开启，默认关闭）
+ * 通过 OTLP/HTTP JSON 导出到 OTEL_EXPORTER_OTLP_ENDPOINT（默认 http://localhost:4318）
+ * 继承客户端 traceparent，并把当前 span 作为父级透传给上游
+ */
+
+import consola from "consola"
+import { envBool, envString } from "./env"
+
+export interface TraceContext {
+    traceId: string
+    spanId: string
+    parentSpanId?: string
+}
+
+export type SpanAttributes = Record<string, string | number | boolean | undefined>
+
+interface FinishedSpan {
+    trace: TraceContext
+    name: string
+    startMs: number
+    endMs: number
+    attributes: SpanAttributes
+    error: boolean
+}
+
+const TRACEPARENT_PATTERN = /^00-([0-9a-f]{32})-([0-9a-f]{16})-[0-9a-f]{2}$/
+const MAX_BATCH_SIZE = 100
+const FLUSH_INTERVAL_MS = 5000
+const MAX_QUEUE_SIZE = 2048
+
+export function isTracingEnabled(): boolean {
+    return envBool("ANTI_API_OTEL")
+}
+
+function randomHex(bytes: number): string {
+    const buffer = crypto.getRandomValues(new Uint8Array(bytes))
+    return Array.from(buffer, byte => byte.toString(16).padStart(2, "0")).join("")
+}
+
+/**
+ * 解析 W3C traceparent；全零 ID 视为无效
+ */
+export function parseTraceparent(header: string | undefined): { traceId: string; parentSpanId: string } | null {
+    const match = TRACEPARENT_PATTERN.exec((header || "").trim().toLowerCase())
+    if (!match) return null
+    const [, traceId, parentSpanId] = match
+    if (/^0+$/.test(traceId) || /^0+$/.test(parentSpanId)) return null
+    return { traceId, parentSpanId }
+}
+
+export function startTrace(traceparent: string | undefined): TraceContext {
+    const parent = parseTraceparent(traceparent)
+    return {
+        traceId: parent?.traceId ?? randomHex(16),
+        spanId: randomHex(8),
+        parentSpanId: parent?.parentSpanId,
+    }
+}
+
+export function formatTraceparent(trace: TraceContext): string {
+    return `00-${trace.traceId}-${trace.spanId}-01`
+}
+
+const queue: FinishedSpan[] = []
+let flushTimer: ReturnType<typeof setTimeout> | null = null
+
+function toAttributeValue(value: string | number | boolean) {
+    if (typeof value === "boolean") return { boolValue: value }
+    if (typeof value === "number") return Number.isInteger(value) ? { intValue: value } : { doubleValue: value }
+    return { stringValue: value }
+}
+
+function toOtlpAttributes(attributes: SpanAttributes) {
+    return Object.entries(attributes)
+        .filter(([, value]) => value !== undefined && value !== "")
+        .map(([key, value]) => ({ key, value: toAttributeValue(value as string | number | boolean) }))
+}
+
+function toNanos(ms: number): string {
+    return (BigInt(Math.round(ms * 1000)) * 1000n).toString()
+}
+
+export function buildOtlpPayload(spans: FinishedSpan[]) {
+    return {
+        resourceSpans: [{
+            resource: { attributes: toOtlpAttributes({ "service.name": envString("OTEL_SERVICE_NAME", "anti-api") }) },
+            scopeSpans: [{
+                scope: { name: "anti-api" },
+                spans: spans.map(span => ({
+                    traceId: span.trace.traceId,
+                    spanId: span.trace.spanId,
+                    ...(span.trace.parentSpanId ? { parentSpanId: span.trace.parentSpanId } : {}),
+                    name: span.name,
+                    kind: 2, // SPAN_KIND_SERVER
+                    startTimeUnixNano: toNanos(span.startMs),
+                    endTimeUnixNano: toNanos(span.endMs),
+                    attributes: toOtlpAttributes(span.attributes),
+                    status: { code: span.error ? 2 : 1 },
+                })),
+            }],
+        }],
+    }
+}
+
+function getTracesUrl(): string {
+    const explicit = envString("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")
+    if (explicit) return explicit
+    return envString("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4318").replace(/\/+$/, "") + "/v1/traces"
+}
+
+async function flush(): Promise<void> {
+    flushTimer = null
+    while (queue.length > 0) {
+        const batch = queue.splice(0, MAX_BATCH_SIZE)
+        try {
+            const response = await fetch(getTracesUrl(), {
+                method: "POST",
+                headers: { "Content-Type": "application/json" },
+                body: JSON.stringify(buildOtlpPayload(batch)),
+            })
+            if (!response.ok) consola.debug(`[OTel] Export failed: ${response.status}`)
+        } catch (error) {
+            consola.debug("[OTel] Export failed:", (error as Error).message)
+            return
+        }
+    }
+}
+
+/**
+ * 结束 span 并加入导出队列（队列满时丢弃）
+ */
+export function endSpan(trace: TraceContext, name: string, startMs: number, attributes: SpanAttributes, error: boolean): void {
+    if (queue.length >= MAX_QUEUE_SIZE) return
+    queue.push({ trace, name, startMs, endMs: Date.now(), attributes, error })
+    if (queue.length >= MAX_BATCH_SIZE) {
+        void flush()
+    } else if (!flushTimer) {
+        flushTimer = setTimeout(() => void flush(), FLUSH_INTERVAL_MS)
+        flushTimer.unref?.()
+    }
+}
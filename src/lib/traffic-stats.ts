@@ -0,0 +1,37 @@
+/**
+ * 🆕 按模型累计请求/响应字节数（供 /stats 使用，Prometheus 侧另有直方图）
+ * - request_bytes: 入站请求体大小（Content-Length，缺失时不计）
+ * - response_bytes: 上游响应 body 字节数（流式按块累加，不缓冲）
+ */
+
+export interface ModelTrafficStats {
+    requests: number
+    request_bytes: number
+    response_bytes: number
+}
+
+const trafficByModel = new Map<string, ModelTrafficStats>()
+
+export function recordTraffic(model: string, requestBytes: number, responseBytes: number): void {
+    const entry = trafficByModel.get(model) || { requests: 0, request_bytes: 0, response_bytes: 0 }
+    entry.requests++
+    entry.request_bytes += requestBytes
+    entry.response_bytes += responseBytes
+    trafficByModel.set(model, entry)
+}
+
+export function getTrafficStats(): { models: Record<string, ModelTrafficStats>; total: ModelTrafficStats } {
+    const total: ModelTrafficStats = { requests: 0, request_bytes: 0, response_bytes: 0 }
+    const models: Record<string, ModelTrafficStats> = {}
+    for (const [model, entry] of trafficByModel) {
+        models[model] = { ...entry }
+        total.requests += entry.requests
+        total.request_bytes += entry.request_bytes
+        total.response_bytes += entry.response_bytes
+    }
+    return { models, total }
+}
+
+export function resetTrafficStats(): void {
+    trafficByModel.clear()
+}
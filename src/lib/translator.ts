@@ -60,7 +60,7 @@ export function generateMessageId(): string {
 /**
  * 构建 message_start 事件
  */
-export function buildMessageStart(model: string): string {
+export function buildMessageStart(model: string, inputTokens: number = 0): string {
     const event = {
         type: "message_start",
         message: {
@@ -71,7 +71,7 @@ export function buildMessageStart(model: string): string {
             model,
             stop_reason: null,
             stop_sequence: null,
-            usage: { input_tokens: 0, output_tokens: 0 },
+            usage: { input_tokens: inputTokens, output_tokens: 0 },
         },
     }
     return `event: message_start\ndata: ${JSON.stringify(event)}\n\n`
@@ -147,6 +147,31 @@ export function buildMessageStop(): string {
     return `event: message_stop\ndata: {"type":"message_stop"}\n\n`
 }
 
+/**
+ * 🆕 把完整（非流式）响应重新编码为 SSE 事件序列
+ */
+export function buildSseFromResponse(
+    model: string,
+    blocks: Array<{ type: "text" | "tool_use"; text?: string; id?: string; name?: string; input?: any }>,
+    stopReason: string | null,
+    usage?: { inputTokens: number; outputTokens: number }
+): string[] {
+    const events = [buildMessageStart(model, usage?.inputTokens || 0)]
+    blocks.forEach((block, index) => {
+        if (block.type === "tool_use") {
+            events.push(buildContentBlockStart(index, "tool_use", { id: block.id || "", name: block.name || "" }))
+            events.push(buildInputJsonDelta(index, JSON.stringify(block.input ?? {})))
+        } else {
+            events.push(buildContentBlockStart(index, "text"))
+            if (block.text) events.push(buildTextDelta(index, block.text))
+        }
+        events.push(buildContentBlockStop(index))
+    })
+    events.push(buildMessageDelta(stopReason || "end_turn", usage))
+    events.push(buildMessageStop())
+    return events
+}
+
 /**
  * 构建 ping 事件
  */
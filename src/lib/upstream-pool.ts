@@ -0,0 +1,33 @@
+/**
+ * 🆕 空闲连接复用上限（ANTI_API_POOL_IDLE_REUSE_SECS，默认 60，0 = 不限制）
+ * 长时间空闲的池化连接可能被中间设备静默丢弃，下次复用时才表现为连接重置
+ * 这不是 TCP keepalive：Bun 的 fetch 不暴露套接字级 SO_KEEPALIVE / 连接最大寿命设置，不发送任何探测包；
+ * 只按端点跟踪最近一次使用时间，端点空闲超过该秒数后，下一次请求以 keepalive: false 发出，改用新连接
+ */
+
+import { envInt } from "./env"
+import { incrementCounter } from "./metrics"
+
+const DEFAULT_IDLE_REUSE_SECS = 60
+
+const lastUsedAt = new Map<string, number>()
+
+export function getPoolIdleReuseSecs(): number {
+    return Math.max(0, envInt("ANTI_API_POOL_IDLE_REUSE_SECS", DEFAULT_IDLE_REUSE_SECS))
+}
+
+/**
+ * 本次请求是否可以复用池化连接；同时记录端点的使用时间
+ */
+export function shouldReusePooledConnection(endpoint: string, now: number = Date.now()): boolean {
+    const idleLimitMs = getPoolIdleReuseSecs() * 1000
+    const previous = lastUsedAt.get(endpoint)
+    lastUsedAt.set(endpoint, now)
+    if (idleLimitMs === 0 || previous === undefined || now - previous <= idleLimitMs) return true
+    incrementCounter("upstream_fresh_connections")
+    return false
+}
+
+export function resetPooledConnectionTracking(): void {
+    lastUsedAt.clear()
+}
@@ -0,0 +1,121 @@
+/**
+ * 🆕 上游单次调用超时（从发出请求到收到响应头）
+ * - ANTI_API_UPSTREAM_TIMEOUT_MS: 全局默认（默认 30000）
+ * - ANTI_API_ENDPOINT_TIMEOUTS_MS: 按端点覆盖，逗号分隔的 `<端点>=<毫秒>`，端点可写 URL 或在端点列表中的下标
+ *   例：`0=8000,https://cloudcode-pa.googleapis.com=60000`，让主端点更快失败切换、兜底端点更宽松
+ *   🆕 溢出端点（ANTI_API_OVERFLOW_ENDPOINT）可用 `overflow=<毫秒>` 配置
+ * - 🆕 ANTI_API_MODEL_TIMEOUTS_MS: 按模型覆盖，逗号分隔的 `<模型>=<毫秒>`（路由后的模型 ID，支持 `前缀*` 通配）
+ *   例：`gemini-3-flash=15000,claude-opus-*=120000`
+ * 优先级：端点覆盖 > 模型覆盖（精确匹配优先于通配，通配取最长前缀）> 全局默认；配置非法时拒绝启动
+ * 单次超时始终不超过端到端截止时间的剩余预算
+ */
+
+import { envInt, readEnv } from "./env"
+import { OVERFLOW_ENDPOINT_INDEX } from "./overflow"
+
+const DEFAULT_UPSTREAM_TIMEOUT_MS = 30000
+
+export function getDefaultUpstreamTimeoutMs(): number {
+    const value = envInt("ANTI_API_UPSTREAM_TIMEOUT_MS", DEFAULT_UPSTREAM_TIMEOUT_MS)
+    return value > 0 ? value : DEFAULT_UPSTREAM_TIMEOUT_MS
+}
+
+function normalizeKey(key: string): string {
+    return key.trim().toLowerCase()
+}
+
+function normalizeEndpoint(endpoint: string): string {
+    return endpoint.trim().replace(/\/+$/, "").toLowerCase()
+}
+
+function parseTimeoutMap(raw: string, envName: string, expected: string, normalize: (key: string) => string): Map<string, number> {
+    const timeouts = new Map<string, number>()
+    for (const item of raw.split(",").map(part => part.trim()).filter(Boolean)) {
+        const separator = item.lastIndexOf("=")
+        const key = separator > 0 ? normalize(item.slice(0, separator)) : ""
+        const value = separator > 0 ? Number(item.slice(separator + 1).trim()) : Number.NaN
+        if (!key || !Number.isInteger(value) || value <= 0) {
+            throw new Error(`Invalid ${envName} entry "${item}" (expected ${expected}=<ms>)`)
+        }
+        timeouts.set(key, value)
+    }
+    return timeouts
+}
+
+/**
+ * 解析按端点覆盖表；键为规范化后的 URL、下标字符串或 "overflow"
+ */
+export function parseEndpointTimeouts(raw: string): Map<string, number> {
+    return parseTimeoutMap(raw, "ANTI_API_ENDPOINT_TIMEOUTS_MS", "<endpoint|index|overflow>", normalizeEndpoint)
+}
+
+/**
+ * 🆕 解析按模型覆盖表；键为小写模型 ID，可以 "*" 结尾表示前缀匹配
+ */
+export function parseModelTimeouts(raw: string): Map<string, number> {
+    return parseTimeoutMap(raw, "ANTI_API_MODEL_TIMEOUTS_MS", "<model>", normalizeKey)
+}
+
+const cache = new Map<string, { raw: string; timeouts: Map<string, number> }>()
+
+function getTimeoutMap(envName: string, parse: (raw: string) => Map<string, number>): Map<string, number> {
+    const raw = readEnv(envName) || ""
+    let entry = cache.get(envName)
+    if (entry?.raw !== raw) {
+        entry = { raw, timeouts: parse(raw) }
+        cache.set(envName, entry)
+    }
+    return entry.timeouts
+}
+
+function getEndpointTimeouts(): Map<string, number> {
+    return getTimeoutMap("ANTI_API_ENDPOINT_TIMEOUTS_MS", parseEndpointTimeouts)
+}
+
+function getModelTimeouts(): Map<string, number> {
+    return getTimeoutMap("ANTI_API_MODEL_TIMEOUTS_MS", parseModelTimeouts)
+}
+
+/**
+ * 启动时校验（非法时抛出，由入口打印并退出）
+ */
+export function validateUpstreamTimeoutConfig(): void {
+    getEndpointTimeouts()
+    getModelTimeouts()
+}
+
+/**
+ * 🆕 模型覆盖的超时：精确匹配优先，其次最长的前缀通配；未配置返回 undefined
+ */
+export function getModelTimeoutMs(model: string | undefined): number | undefined {
+    if (!model) return undefined
+    const timeouts = getModelTimeouts()
+    const id = normalizeKey(model)
+    const exact = timeouts.get(id)
+    if (exact !== undefined) return exact
+    let best: { prefix: string; value: number } | undefined
+    for (const [key, value] of timeouts) {
+        if (!key.endsWith("*")) continue
+        const prefix = key.slice(0, -1)
+        if (id.startsWith(prefix) && (!best || prefix.length > best.prefix.length)) best = { prefix, value }
+    }
+    return best?.value
+}
+
+/**
+ * 🆕 启动日志：每个配置项最终生效的超时
+ */
+export function describeModelTimeouts(): string[] {
+    return Array.from(getModelTimeouts(), ([model, value]) => `${model}=${value}ms`)
+}
+
+/**
+ * 某个端点的超时：URL 覆盖 > 下标覆盖 > 模型覆盖 > 全局默认
+ */
+export function getEndpointTimeoutMs(baseUrl: string, index: number, model?: string): number {
+    const timeouts = getEndpointTimeouts()
+    return timeouts.get(normalizeEndpoint(baseUrl))
+        ?? timeouts.get(index === OVERFLOW_ENDPOINT_INDEX ? "overflow" : String(index))
+        ?? getModelTimeoutMs(model)
+        ?? getDefaultUpstreamTimeoutMs()
+}
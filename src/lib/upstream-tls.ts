@@ -0,0 +1,82 @@
+/**
+ * 上游 TLS 信任配置
+ * - ANTI_API_CA_BUNDLE: 额外信任的 CA（PEM 文件路径），设置后强制校验证书
+ * - ANTI_API_TLS_PIN: 上游证书 SPKI 指纹（base64 sha256，可逗号分隔多个）
+ * 校验失败时抛出 AntigravityError(code = "tls_error")；不提供跳过校验的选项
+ */
+
+import { readFileSync } from "fs"
+import { createHash, type PeerCertificate } from "crypto"
+import { envList, envString } from "./env"
+import { AntigravityError } from "./error"
+
+export interface UpstreamTlsOptions {
+    ca?: string
+    rejectUnauthorized: true
+    checkServerIdentity?: (hostname: string, cert: PeerCertificate) => Error | undefined
+}
+
+let cachedKey: string | null = null
+let cachedOptions: UpstreamTlsOptions | undefined
+
+export function spkiPin(cert: PeerCertificate): string | null {
+    const pubkey = (cert as PeerCertificate & { pubkey?: Buffer }).pubkey
+    if (!pubkey) return null
+    return createHash("sha256").update(pubkey).digest("base64")
+}
+
+/**
+ * 未配置时返回 undefined（沿用默认行为）
+ */
+export function getUpstreamTlsOptions(): UpstreamTlsOptions | undefined {
+    const caPath = envString("ANTI_API_CA_BUNDLE")
+    const pins = envList("ANTI_API_TLS_PIN")
+    const key = `${caPath || ""}|${pins.join(",")}`
+    if (key === cachedKey) return cachedOptions
+
+    cachedKey = key
+    if (!caPath && pins.length === 0) {
+        cachedOptions = undefined
+        return cachedOptions
+    }
+
+    const options: UpstreamTlsOptions = { rejectUnauthorized: true }
+    if (caPath) {
+        try {
+            options.ca = readFileSync(caPath, "utf-8")
+        } catch (error) {
+            cachedKey = null
+            throw new AntigravityError(`Cannot read ANTI_API_CA_BUNDLE: ${(error as Error).message}`, "tls_error")
+        }
+    }
+    if (pins.length > 0) {
+        options.checkServerIdentity = (hostname, cert) => {
+            const pin = spkiPin(cert)
+            if (!pin || !pins.includes(pin)) {
+                return new Error(`TLS pin mismatch for ${hostname} (got ${pin || "none"})`)
+            }
+            return undefined
+        }
+    }
+    cachedOptions = options
+    return cachedOptions
+}
+
+const TLS_ERROR_CODES = new Set([
+    "UNABLE_TO_VERIFY_LEAF_SIGNATURE",
+    "UNABLE_TO_GET_ISSUER_CERT_LOCALLY",
+    "SELF_SIGNED_CERT_IN_CHAIN",
+    "DEPTH_ZERO_SELF_SIGNED_CERT",
+    "CERT_HAS_EXPIRED",
+    "CERT_NOT_YET_VALID",
+    "ERR_TLS_CERT_ALTNAME_INVALID",
+    "CERT_UNTRUSTED",
+])
+
+export function isTlsError(error: unknown): boolean {
+    const err = error as { code?: string; message?: string } | null
+    if (!err) return false
+    if (err.code && TLS_ERROR_CODES.has(err.code)) return true
+    const message = (err.message || "").toLowerCase()
+    return message.includes("certificate") || message.includes("tls pin mismatch")
+}
@@ -10,13 +10,45 @@ import {
     MAX_ACCOUNT_ID_LENGTH,
     MAX_TOKENS_LIMIT,
     MAX_SANITIZED_STRING_LENGTH,
+    DEFAULT_MAX_TOKEN_LENGTH,
 } from "./constants"
+import { envInt, envString } from "./env"
+import { isRequestExtraQueryAllowed, validateExtraQuery } from "./extra-query"
 
 export interface ValidationResult {
     valid: boolean
     error?: string
 }
 
+/** Allowed model name characters (after trimming) */
+const MODEL_NAME_PATTERN = /^[A-Za-z0-9._:\/@-]+$/
+
+/**
+ * Validate model name characters (surrounding whitespace is tolerated and trimmed later)
+ */
+function validateModelName(model: string): ValidationResult {
+    if (model.length > MAX_MODEL_NAME_LENGTH) {
+        return { valid: false, error: `Model name too long (max ${MAX_MODEL_NAME_LENGTH} characters)` }
+    }
+    const trimmed = model.trim()
+    if (!trimmed) {
+        return { valid: false, error: "Model is required and must be a string" }
+    }
+    if (!MODEL_NAME_PATTERN.test(trimmed)) {
+        return { valid: false, error: `Model name "${trimmed.slice(0, 64)}" contains invalid characters` }
+    }
+    return { valid: true }
+}
+
+/**
+ * 🆕 Fill in ANTI_API_DEFAULT_MODEL when the client omits model (alias mapping still applies later)
+ */
+export function applyDefaultModel(payload: any): void {
+    if (payload.model !== undefined && payload.model !== null && payload.model !== "") return
+    const fallback = envString("ANTI_API_DEFAULT_MODEL")
+    if (fallback) payload.model = fallback
+}
+
 /**
  * Validate chat completion request body
  */
@@ -26,12 +58,12 @@ export function validateChatRequest(payload: any): ValidationResult {
     }
 
     // Model validation
+    applyDefaultModel(payload)
     if (!payload.model || typeof payload.model !== "string") {
         return { valid: false, error: "Model is required and must be a string" }
     }
-    if (payload.model.length > MAX_MODEL_NAME_LENGTH) {
-        return { valid: false, error: `Model name too long (max ${MAX_MODEL_NAME_LENGTH} characters)` }
-    }
+    const modelCheck = validateModelName(payload.model)
+    if (!modelCheck.valid) return modelCheck
 
     // Messages validation
     if (!Array.isArray(payload.messages)) {
@@ -173,7 +205,19 @@ export function validateChatRequest(payload: any): ValidationResult {
         }
     }
 
-    return { valid: true }
+    return validateRequestExtraQuery(payload)
+}
+
+/**
+ * 🆕 请求级 extra_query（需 ANTI_API_ALLOW_REQUEST_EXTRA_QUERY=1）
+ */
+function validateRequestExtraQuery(payload: any): ValidationResult {
+    if (payload.extra_query === undefined) return { valid: true }
+    if (!isRequestExtraQueryAllowed()) {
+        return { valid: false, error: "extra_query is not enabled on this server" }
+    }
+    const error = validateExtraQuery(payload.extra_query)
+    return error ? { valid: false, error } : { valid: true }
 }
 
 /**
@@ -185,9 +229,12 @@ export function validateAnthropicRequest(payload: any): ValidationResult {
     }
 
     // Model validation
+    applyDefaultModel(payload)
     if (!payload.model || typeof payload.model !== "string") {
         return { valid: false, error: "Model is required and must be a string" }
     }
+    const modelCheck = validateModelName(payload.model)
+    if (!modelCheck.valid) return modelCheck
 
     // Messages validation
     if (!Array.isArray(payload.messages)) {
@@ -214,7 +261,7 @@ export function validateAnthropicRequest(payload: any): ValidationResult {
         }
     }
 
-    return { valid: true }
+    return validateRequestExtraQuery(payload)
 }
 
 /**
@@ -233,3 +280,21 @@ export function validateAccountId(id: string): boolean {
     // Only allow alphanumeric, dash, underscore, @ and .
     return /^[a-zA-Z0-9@._-]+$/.test(id) && id.length <= MAX_ACCOUNT_ID_LENGTH
 }
+
+/**
+ * Validate an OAuth token before it is stored or sent upstream
+ * Rejects oversized tokens and characters that would break the Authorization header
+ */
+export function validateAccessToken(token: unknown, field: string = "accessToken"): ValidationResult {
+    if (typeof token !== "string" || !token) {
+        return { valid: false, error: `${field} must be a non-empty string` }
+    }
+    const maxLength = envInt("ANTI_API_MAX_TOKEN_LEN", DEFAULT_MAX_TOKEN_LENGTH)
+    if (maxLength > 0 && token.length > maxLength) {
+        return { valid: false, error: `${field} too long (max ${maxLength} characters)` }
+    }
+    if (/[\x00-\x20\x7f]/.test(token)) {
+        return { valid: false, error: `${field} contains whitespace or control characters` }
+    }
+    return { valid: true }
+}
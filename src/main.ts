@@ -7,10 +7,11 @@
 import { defineCommand, runMain } from "citty"
 import consola from "consola"
 
-import { server } from "./server"
+import { app, basePath } from "./server"
 import { setupAntigravityToken } from "./lib/token"
 import { getLanguageServerInfo } from "./lib/port-finder"
 import { state } from "./lib/state"
+import { readEnv } from "./lib/env"
 import { initAuth, isAuthenticated, saveAuth, startOAuthLogin } from "./services/antigravity/login"
 import { getProjectID } from "./services/antigravity/oauth"
 import { accountManager } from "./services/antigravity/account-manager"
@@ -21,7 +22,7 @@ import { getSetting } from "./services/settings"
  * 在 Docker/无头环境中静默失败
  */
 function openBrowser(url: string): void {
-    if (process.env.ANTI_API_NO_OPEN === "1") {
+    if (readEnv("ANTI_API_NO_OPEN") === "1") {
         return
     }
     const platform = process.platform
@@ -110,18 +111,101 @@ const start = defineCommand({
         }
 
         // 打印启动 banner
-        const { logStartup, logStartupSuccess } = await import("./lib/logger")
+        const { logStartup, logStartupSuccess, logRouteMap } = await import("./lib/logger")
         logStartup(state.port)
 
+        // 🆕 failover 状态码配置非法（如包含 429）时拒绝启动
+        // 🆕 按端点超时、DNS 覆盖与鉴权链配置非法时同样拒绝启动
+        const { validateFailoverConfig } = await import("./lib/failover")
+        const { describeModelTimeouts, getDefaultUpstreamTimeoutMs, validateUpstreamTimeoutConfig } = await import("./lib/upstream-timeouts")
+        const { getResolveOverrides } = await import("./lib/dns-override")
+        const { getPoolIdleReuseSecs } = await import("./lib/upstream-pool")
+        const { getAuthChain } = await import("./lib/authenticator")
+        try {
+            validateFailoverConfig()
+            validateUpstreamTimeoutConfig()
+            const modelTimeouts = describeModelTimeouts()
+            if (modelTimeouts.length > 0) {
+                consola.info(`Upstream timeouts: default=${getDefaultUpstreamTimeoutMs()}ms, ${modelTimeouts.join(", ")}`)
+            }
+            const authChain = getAuthChain()
+            if (authChain.length > 0) consola.info(`Auth chain: ${authChain.map(authenticator => authenticator.name).join(" -> ")}`)
+            const idleReuseSecs = getPoolIdleReuseSecs()
+            consola.info(`Upstream idle connection reuse: ${idleReuseSecs > 0 ? `fresh connection after ${idleReuseSecs}s idle` : "unlimited"}`)
+            for (const override of getResolveOverrides()) {
+                consola.info(`DNS override: ${override.host}${override.port ? `:${override.port}` : ""} -> ${override.address}`)
+            }
+        } catch (error) {
+            consola.error((error as Error).message)
+            process.exit(1)
+        }
+
+        // 🆕 入站 HTTPS / mTLS（ANTI_API_TLS_CERT、ANTI_API_TLS_KEY、ANTI_API_MTLS_CA）
+        const { getMinTlsVersion, getServerTlsOptions } = await import("./lib/server-tls")
+        let tls: ReturnType<typeof getServerTlsOptions>
+        try {
+            tls = getServerTlsOptions()
+        } catch (error) {
+            consola.error((error as Error).message)
+            process.exit(1)
+        }
+
         // 启动服务器
-        Bun.serve({
-            fetch: server.fetch,
+        // 🆕 同时配置 ANTI_API_TLS_PORT 时：--port 为明文 HTTP，TLS_PORT 为 HTTPS（共享同一个 app）
+        const tlsPort = Number.parseInt(readEnv("ANTI_API_TLS_PORT") || "", 10)
+        if (Number.isFinite(tlsPort) && !tls) {
+            consola.error("ANTI_API_TLS_PORT requires ANTI_API_TLS_CERT and ANTI_API_TLS_KEY")
+            process.exit(1)
+        }
+        const listenerSpecs: Array<{ port: number; scheme: "http" | "https" }> = tls && Number.isFinite(tlsPort)
+            ? [{ port: state.port, scheme: "http" }, { port: tlsPort, scheme: "https" }]
+            : [{ port: state.port, scheme: tls ? "https" : "http" }]
+        const listeners = listenerSpecs.map(spec => Bun.serve({
+            fetch: app.fetch,
             hostname: "0.0.0.0",
-            port: state.port,
+            port: spec.port,
             idleTimeout: 120,  // 2分钟超时，适应慢速 API 响应
-        })
+            ...(spec.scheme === "https" ? { tls } : {}),
+        }))
+
+        // 🆕 收到退出信号时停止接收新连接，等待所有监听器上的在途请求结束
+        const shutdown = async (signal: string) => {
+            consola.info(`${signal} received, draining ${listeners.length} listener(s)...`)
+            await Promise.all(listeners.map(listener => listener.stop()))
+            process.exit(0)
+        }
+        process.once("SIGTERM", () => void shutdown("SIGTERM"))
+        process.once("SIGINT", () => void shutdown("SIGINT"))
+
+        logStartupSuccess(listenerSpecs, basePath)
+        if (tls) consola.info(`TLS minimum version: ${getMinTlsVersion()}`)
+        if (tls?.requestCert) consola.info("mTLS enabled: client certificates are required")
+        // 🆕 故障注入只能通过环境变量 ANTI_API_FAULT_INJECT=1 显式开启
+        const { getFaultRate, isFaultInjectionEnabled } = await import("./lib/fault-inject")
+        if (isFaultInjectionEnabled()) {
+            consola.warn(`FAULT INJECTION ENABLED (rate=${getFaultRate()}; X-Fault-Inject header honoured) - do not use in production`)
+        }
+        const { getConfiguredDelayMs } = await import("./lib/delay-inject")
+        if (getConfiguredDelayMs() > 0) {
+            consola.warn(`DELAY INJECTION ENABLED: every upstream call waits ${getConfiguredDelayMs()}ms first - do not use in production`)
+        }
+
+        // 🆕 启动并发爬坡（ANTI_API_RAMP_SECS）
+        const { startConcurrencyRamp } = await import("./lib/concurrency")
+        startConcurrencyRamp()
+
+        // 🆕 按 429 比例自动调节并发与间隔（ANTI_API_AUTOTUNE）
+        const { startAutoTuner } = await import("./lib/auto-tune")
+        startAutoTuner()
+
+        // 🆕 事件循环延迟采样（ANTI_API_SHED_LAG_MS）
+        const { startLagMonitor } = await import("./lib/load-shed")
+        startLagMonitor()
 
-        logStartupSuccess(state.port)
+        // 🆕 配置热加载（SIGHUP / ANTI_API_CONFIG_WATCH）
+        const { startConfigReloadListeners } = await import("./lib/config-reload")
+        startConfigReloadListeners()
+        logRouteMap(app.routes)
 
         // 🆕 启动 Token 后台刷新服务
         if (accountManager.count() > 0) {
@@ -146,7 +230,7 @@ const start = defineCommand({
 
         // 根据设置决定是否自动打开面板
         if (getSetting("autoOpenDashboard")) {
-            openBrowser(`http://localhost:${state.port}/quota`)
+            openBrowser(`http://localhost:${state.port}${basePath}/quota`)
         }
     },
 })
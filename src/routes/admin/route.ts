@@ -0,0 +1,72 @@
+/**
+ * 管理接口（需要 ANTI_API_ADMIN_KEY）
+ */
+
+import { Hono } from "hono"
+import { requireAdmin } from "~/lib/admin-auth"
+import { getMaintenanceState, setMaintenance } from "~/lib/maintenance"
+import { reloadConfig } from "~/lib/config-reload"
+import { clearCooldowns, listCooldowns } from "~/services/cooldowns"
+import { ADMIN_UI_HTML } from "./ui"
+
+export const adminRouter = new Hono()
+
+adminRouter.use(requireAdmin)
+
+// 🆕 管理面板页面：与其他管理接口一样需要管理密钥，未设置密钥时 404、密钥缺失或错误时 401
+adminRouter.get("/ui", (c) => {
+    c.header("Cache-Control", "no-store")
+    return c.html(ADMIN_UI_HTML)
+})
+
+adminRouter.get("/maintenance", (c) => {
+    return c.json(getMaintenanceState())
+})
+
+adminRouter.post("/maintenance", async (c) => {
+    let body: { enabled?: boolean; status?: number; message?: string; retryAfterSeconds?: number } = {}
+    try {
+        body = await c.req.json()
+    } catch {
+        return c.json({ error: { type: "invalid_request_error", message: "Body must be JSON" } }, 400)
+    }
+    if (typeof body.enabled !== "boolean") {
+        return c.json({ error: { type: "invalid_request_error", message: "enabled must be a boolean" } }, 400)
+    }
+    const updated = setMaintenance(body)
+    console.log(`[Admin] maintenance ${updated.enabled ? "enabled" : "disabled"}`)
+    return c.json(updated)
+})
+
+// 🆕 冷却状态（key 为账号哈希，不含 token）
+adminRouter.get("/cooldowns", (c) => {
+    return c.json({ cooldowns: listCooldowns() })
+})
+
+adminRouter.post("/cooldowns/clear", async (c) => {
+    let body: { key?: string } = {}
+    try {
+        body = await c.req.json()
+    } catch {
+        body = {}
+    }
+    if (body.key !== undefined && typeof body.key !== "string") {
+        return c.json({ error: { type: "invalid_request_error", message: "key must be a string" } }, 400)
+    }
+    const cleared = clearCooldowns(body.key || undefined)
+    if (cleared === null) {
+        return c.json({ error: { type: "not_found", message: "No cooldown matches this key" } }, 404)
+    }
+    console.log(`[Admin] cleared ${cleared} cooldown(s)${body.key ? ` for ${body.key}` : ""}`)
+    return c.json({ success: true, cleared })
+})
+
+// 🆕 与 SIGHUP 相同的配置热加载；解析失败返回 400 且不应用任何变更
+adminRouter.post("/reload", (c) => {
+    const result = reloadConfig()
+    if (result.error) {
+        return c.json({ error: { type: "config_error", message: result.error }, path: result.path }, 400)
+    }
+    console.log(`[Admin] config reloaded (${result.changed.length} changed)`)
+    return c.json(result)
+})
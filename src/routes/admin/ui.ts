@@ -0,0 +1,111 @@
+/**
+ * 🆕 内置管理面板（/admin/ui）：单个内嵌 HTML 页面，无构建步骤、无外部依赖
+ * 页面本身同样受 requireAdmin 保护（打开时需由反向代理或浏览器扩展附带 X-Admin-Key）；
+ * 页面脚本读不到导航请求头，因此再向用户索取管理密钥（仅存于 sessionStorage），带 X-Admin-Key 轮询 /stats 与 /debug/recent
+ */
+
+export const ADMIN_UI_HTML = `<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>anti-api status</title>
+<style>
+body { font: 13px/1.4 ui-monospace, Menlo, Consolas, monospace; margin: 16px; color: #222; background: #fafafa; }
+h1 { font-size: 16px; margin: 0 0 12px; }
+h2 { font-size: 14px; margin: 18px 0 6px; }
+table { border-collapse: collapse; width: 100%; background: #fff; }
+th, td { border: 1px solid #ddd; padding: 3px 6px; text-align: left; white-space: nowrap; }
+th { background: #f0f0f0; }
+.bad { color: #b00020; }
+.ok { color: #1b7f3b; }
+#error { color: #b00020; margin: 8px 0; }
+#auth { margin-bottom: 12px; }
+</style>
+</head>
+<body>
+<h1>anti-api status <span id="updated"></span></h1>
+<form id="auth"><input id="key" type="password" placeholder="Admin key" size="32"> <button>Connect</button></form>
+<div id="error"></div>
+<h2>Concurrency</h2><div id="concurrency"></div>
+<h2>Status counts</h2><div id="statuses"></div>
+<h2>Endpoints</h2><div id="endpoints"></div>
+<h2>Cooldowns</h2><div id="cooldowns"></div>
+<h2>Recent requests</h2><div id="recent"></div>
+<script>
+const POLL_MS = 3000
+const base = location.pathname.replace(/\\/admin\\/ui\\/?$/, "")
+let key = sessionStorage.getItem("anti-api-admin-key") || ""
+
+function escapeHtml(value) {
+    return String(value ?? "").replace(/[&<>"]/g, ch => ({ "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;" })[ch])
+}
+
+function table(rows, columns) {
+    if (!rows.length) return "<em>none</em>"
+    const head = columns.map(col => "<th>" + escapeHtml(col[0]) + "</th>").join("")
+    const body = rows.map(row => "<tr>" + columns.map(col => "<td>" + col[1](row) + "</td>").join("") + "</tr>").join("")
+    return "<table><tr>" + head + "</tr>" + body + "</table>"
+}
+
+async function load(path) {
+    const res = await fetch(base + path, { headers: { "X-Admin-Key": key } })
+    if (!res.ok) throw new Error(path + " returned " + res.status)
+    return res.json()
+}
+
+async function refresh() {
+    if (!key) return
+    try {
+        const [stats, recent] = await Promise.all([load("/stats"), load("/debug/recent?n=50")])
+        const c = stats.concurrency
+        document.getElementById("concurrency").textContent =
+            "in use " + c.in_use + " / " + (c.limit || "unlimited") + ", waiting " + c.waiting + ", uptime " + stats.uptime_seconds + "s"
+        const statuses = Object.entries(stats.status_counts || {})
+        document.getElementById("statuses").innerHTML = table(statuses, [
+            ["status", s => '<span class="' + (s[0] === "429" || s[0] >= "500" ? "bad" : "ok") + '">' + escapeHtml(s[0]) + "</span>"],
+            ["count", s => escapeHtml(s[1])],
+        ])
+        document.getElementById("endpoints").innerHTML = table(stats.endpoints || [], [
+            ["endpoint", e => escapeHtml(e.endpoint)],
+            ["state", e => '<span class="' + (e.state === "unhealthy" ? "bad" : "ok") + '">' + escapeHtml(e.state) + "</span>"],
+            ["last status", e => escapeHtml(e.lastStatus)],
+            ["latency ms", e => escapeHtml(e.latencyMs)],
+            ["checked", e => escapeHtml(e.checkedAt)],
+            ["error", e => escapeHtml(e.error)],
+        ])
+        document.getElementById("cooldowns").innerHTML = table(stats.cooldowns || [], [
+            ["key", e => escapeHtml(e.key)],
+            ["scope", e => escapeHtml(e.scope)],
+            ["model", e => escapeHtml(e.model)],
+            ["remaining s", e => escapeHtml(Math.ceil(e.remainingMs / 1000))],
+        ])
+        document.getElementById("recent").innerHTML = table(recent.requests || [], [
+            ["time", r => escapeHtml(r.time)],
+            ["status", r => '<span class="' + (r.status >= 400 ? "bad" : "ok") + '">' + escapeHtml(r.status) + "</span>"],
+            ["model", r => escapeHtml(r.model)],
+            ["client", r => escapeHtml(r.clientId)],
+            ["stream", r => escapeHtml(r.stream)],
+            ["latency ms", r => escapeHtml(r.latencyMs)],
+            ["endpoint", r => escapeHtml(r.endpoint)],
+            ["request id", r => escapeHtml(r.requestId)],
+        ])
+        document.getElementById("error").textContent = ""
+        document.getElementById("updated").textContent = "(" + new Date().toLocaleTimeString() + ")"
+    } catch (error) {
+        document.getElementById("error").textContent = error.message
+    }
+}
+
+document.getElementById("auth").addEventListener("submit", event => {
+    event.preventDefault()
+    key = document.getElementById("key").value
+    sessionStorage.setItem("anti-api-admin-key", key)
+    refresh()
+})
+refresh()
+setInterval(refresh, POLL_MS)
+</script>
+</body>
+</html>
+`
@@ -7,6 +7,7 @@ import { isAuthenticated, getUserInfo, setAuth, clearAuth, startOAuthLogin, poll
 import { accountManager } from "~/services/antigravity/account-manager"
 import { state } from "~/lib/state"
 import { authStore } from "~/services/auth/store"
+import { validateAccessToken } from "~/lib/validation"
 
 export const authRouter = new Hono()
 
@@ -66,6 +67,12 @@ authRouter.post("/login", async (c) => {
         }
 
         // Fallback for direct token auth
+        const tokenCheck = validateAccessToken(body.accessToken)
+        const refreshCheck = body.refreshToken ? validateAccessToken(body.refreshToken, "refreshToken") : tokenCheck
+        if (!tokenCheck.valid || !refreshCheck.valid) {
+            const error = tokenCheck.error || refreshCheck.error
+            return c.json({ success: false, error, error_code: "invalid_token" }, 400)
+        }
         setAuth(body.accessToken, body.refreshToken, body.email, body.name)
         accountManager.load()
         accountManager.addAccount({
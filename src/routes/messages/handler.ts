@@ -9,12 +9,23 @@ import type { Context } from "hono"
 import { streamSSE } from "hono/streaming"
 import consola from "consola"
 
-import { createRoutedCompletion, createRoutedCompletionStream, RoutingError, isOfficialModel } from "~/services/routing/router"
+import { assertRoutableModel, createBufferedCompletionStream, createRoutedCompletion, createRoutedCompletionStream, RoutingError, isOfficialModel } from "~/services/routing/router"
 import { mapModel } from "../openai/translator"
 import type { ClaudeMessage, ClaudeTool } from "~/lib/translator"
 import { rateLimiter } from "~/lib/rate-limiter"
+import { acquireRequestPermit, acquireStreamSlot } from "~/lib/concurrency"
+import { withDeadline } from "~/lib/deadline"
+import { sessionTicketFor, withSupersede, type SessionTicket } from "~/lib/supersede"
+import { resolveStreamMode, resolveUpstreamMode } from "~/lib/stream-mode"
+import { isRequestLogSampled } from "~/lib/logger"
+import { getClientId } from "~/lib/client-id"
+import { updateRequestContext } from "~/lib/request-context"
+import { envBool } from "~/lib/env"
+import { rewriteSseModel } from "~/lib/sse-rewrite"
 import { validateAnthropicRequest } from "~/lib/validation"
 import { UpstreamError } from "~/lib/error"
+import { formatStreamErrorFrame } from "~/lib/stream-error"
+import { capStreamBytes } from "~/lib/stream-byte-cap"
 import { state } from "~/lib/state"
 import type {
     AnthropicMessagesPayload,
@@ -68,6 +79,9 @@ function generateMessageId(): string {
  * 🆕 在 HTTP 层获取全局锁，确保所有请求串行化
  */
 export async function handleCompletion(c: Context): Promise<Response> {
+    let releasePermit: (() => void) | null = null
+    let releaseStream: (() => void) | null = null
+    let sessionTicket: SessionTicket | null = null
     try {
         const payload = await c.req.json<AnthropicMessagesPayload>()
 
@@ -76,12 +90,10 @@ export async function handleCompletion(c: Context): Promise<Response> {
         if (!validation.valid) {
             return c.json({ error: { type: "invalid_request_error", message: validation.error } }, 400)
         }
-
-        await rateLimiter.wait()
+        // 🆕 未显式指定 stream 时按 Accept 头决定
+        payload.stream = resolveStreamMode(payload.stream, c.req.header("Accept"))
         let anthropicModel = mapModel(payload.model)
 
-        console.log(`[Incoming] model="${payload.model}" stream=${!!payload.stream}`)
-
         // 🆕 自动检测 Anthropic 特有的 thinking 字段并升级模型 ID
         if (payload.thinking?.type === "enabled" && !anthropicModel.endsWith("-thinking")) {
             const upgraded = `${anthropicModel}-thinking`
@@ -90,9 +102,32 @@ export async function handleCompletion(c: Context): Promise<Response> {
             }
         }
 
+        // 🆕 不可路由的模型在限流与许可之前拒绝，无效请求不占用节流名额
+        try {
+            assertRoutableModel(anthropicModel)
+        } catch (error) {
+            if (error instanceof RoutingError) {
+                return c.json({ error: { type: "invalid_request_error", message: error.message } }, error.status as any)
+            }
+            throw error
+        }
+
+        // 🆕 限流与许可等待计入端到端截止时间
+        // 🆕 同一会话的新请求会取代仍在排队的旧请求（ANTI_API_SUPERSEDE_BY_SESSION）
+        sessionTicket = sessionTicketFor(c)
+        await withSupersede(sessionTicket, withDeadline(rateLimiter.wait()))
+        releasePermit = await withSupersede(sessionTicket, withDeadline(acquireRequestPermit(), release => release()), release => release())
+        sessionTicket?.dispatch()
+        // 🆕 流式请求另占一个流式许可；已满时按配置拒绝或降级为非流式
+        releaseStream = payload.stream ? acquireStreamSlot() : null
+        if (payload.stream && !releaseStream) payload.stream = false
+
+        if (isRequestLogSampled()) console.log(`[Incoming] model="${payload.model}" stream=${!!payload.stream} client=${getClientId(c)}`)
+
         if (payload.model !== anthropicModel) {
-            console.log(`[Incoming] model remapped: "${payload.model}" -> "${anthropicModel}"`)
+            if (isRequestLogSampled()) console.log(`[Incoming] model remapped: "${payload.model}" -> "${anthropicModel}"`)
         }
+        updateRequestContext({ model: anthropicModel, stream: !!payload.stream, extraQuery: payload.extra_query })
 
         const messages = translateMessages(payload)
         const tools = extractTools(payload)
@@ -117,7 +152,16 @@ export async function handleCompletion(c: Context): Promise<Response> {
 
         // 检查是否流式
         if (payload.stream) {
-            return handleStreamCompletion(c, payload, anthropicModel, messages, tools, toolChoice)
+            // 流式请求的全局许可在流结束时释放
+            const permit = releasePermit
+            const streamSlot = releaseStream
+            releasePermit = null
+            releaseStream = null
+            const release = () => {
+                permit()
+                streamSlot?.()
+            }
+            return handleStreamCompletion(c, payload, anthropicModel, messages, tools, toolChoice, release)
         }
 
         // 非流式请求
@@ -170,9 +214,24 @@ export async function handleCompletion(c: Context): Promise<Response> {
 
         // Note: Usage recording is handled in chat.ts with the actual native model ID
 
+        if (result.upstreamRequestId) {
+            response.upstream_request_id = result.upstreamRequestId
+            c.header("X-Upstream-Request-Id", result.upstreamRequestId)
+        }
+        if (result.fallbackModel) {
+            response.fallback_model = result.fallbackModel
+            c.header("X-Fallback-Model", result.fallbackModel)
+        }
+        if (result.truncated) {
+            response.truncated = true
+            c.header("X-Response-Truncated", "true")
+        }
+
         return c.json(response)
     } finally {
-        // no-op
+        if (releasePermit) releasePermit()
+        if (releaseStream) releaseStream()
+        sessionTicket?.done()
     }
 }
 
@@ -186,7 +245,8 @@ async function handleStreamCompletion(
     anthropicModel: string,
     messages: ClaudeMessage[],
     tools: ClaudeTool[] | undefined,
-    toolChoice: AnthropicMessagesPayload["tool_choice"] | undefined
+    toolChoice: AnthropicMessagesPayload["tool_choice"] | undefined,
+    releasePermit: () => void
 ): Promise<Response> {
     return streamSSE(c, async (stream) => {
         // 🆕 Add headers to disable buffering in proxies (Nginx, etc.)
@@ -198,17 +258,24 @@ async function handleStreamCompletion(
             stream.write(": ping\n\n").catch(() => { })
         }, 15000)
         try {
-            const chatStream = createRoutedCompletionStream({
+            // 🆕 X-Upstream-Mode / 按模型配置 / ANTI_API_STREAM_UPSTREAM_MODE = buffered 时上游缓冲、客户端仍为 SSE
+            const openStream = resolveUpstreamMode(c.req.header("X-Upstream-Mode"), anthropicModel) === "buffered"
+                ? createBufferedCompletionStream
+                : createRoutedCompletionStream
+            // 🆕 ANTI_API_MAX_STREAM_BYTES 超限时以 stream_too_large 错误帧结束
+            const chatStream = capStreamBytes(openStream({
                 model: anthropicModel,
                 messages,
                 tools,
                 toolChoice,
                 maxTokens: payload.max_tokens,
-            })
+            }))
 
             // 直接写入来自翻译器的 SSE 事件
+            // 🆕 ANTI_API_REWRITE_STREAM_MODEL=1 时把帧中的 model 改写为客户端请求的名称
+            const rewriteModel = envBool("ANTI_API_REWRITE_STREAM_MODEL") && payload.model !== anthropicModel
             for await (const event of chatStream) {
-                await stream.write(event)
+                await stream.write(rewriteModel ? rewriteSseModel(event, payload.model) : event)
             }
 
         } catch (error) {
@@ -217,15 +284,11 @@ async function handleStreamCompletion(
             } else {
                 consola.error("Stream error:", error)
             }
-            await stream.writeSSE({
-                event: "error",
-                data: JSON.stringify({
-                    type: "error",
-                    error: { type: "api_error", message: (error as Error).message },
-                }),
-            })
+            // 🆕 以错误帧结束流，客户端可区分截断与正常结束
+            await stream.write(formatStreamErrorFrame(error, "anthropic")).catch(() => { })
         } finally {
             clearInterval(pingInterval)
+            releasePermit()
         }
     })
 }
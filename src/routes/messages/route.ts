@@ -4,10 +4,25 @@
 
 import { Hono } from "hono"
 import { forwardError } from "~/lib/error"
+import { maintenanceGuard } from "~/lib/maintenance"
+import { loadShedGuard } from "~/lib/load-shed"
+import { idempotencyGuard } from "~/lib/idempotency"
+import { diskCacheGuard } from "~/lib/disk-cache"
+import { rateLimitHeaders } from "~/lib/rate-limit-headers"
+import { authGuard } from "~/lib/authenticator"
+import { billingTagGuard } from "~/lib/billing-tag"
 import { handleCompletion } from "./handler"
 
 export const messageRoutes = new Hono()
 
+messageRoutes.use(authGuard)
+messageRoutes.use(billingTagGuard)
+messageRoutes.use(rateLimitHeaders)
+messageRoutes.use(maintenanceGuard)
+messageRoutes.use(loadShedGuard)
+messageRoutes.use(idempotencyGuard)
+messageRoutes.use(diskCacheGuard)
+
 messageRoutes.post("/", async (c) => {
     try {
         return await handleCompletion(c)
@@ -6,6 +6,7 @@
 // 请求类型
 
 export interface AnthropicMessagesPayload {
+    /** 🆕 客户端可省略，校验时由 ANTI_API_DEFAULT_MODEL 补全 */
     model: string
     messages: AnthropicMessage[]
     max_tokens: number
@@ -23,6 +24,8 @@ export interface AnthropicMessagesPayload {
         type: "enabled" | "disabled"
         budget_tokens?: number
     }
+    /** 🆕 附加到上游 URL 的查询参数（需服务端开启） */
+    extra_query?: Record<string, string>
 }
 
 export interface AnthropicMessage {
@@ -84,6 +87,10 @@ export interface AnthropicResponse {
         input_tokens: number
         output_tokens: number
     }
+    upstream_request_id?: string
+    fallback_model?: string
+    /** 🆕 上游 SSE 帧数超限，内容被截断 */
+    truncated?: boolean
 }
 
 export type AnthropicResponseContentBlock = AnthropicTextBlock | AnthropicToolUseBlock
@@ -6,7 +6,7 @@ import type { Context } from "hono"
 import { streamSSE } from "hono/streaming"
 import consola from "consola"
 
-import { createRoutedCompletion, createRoutedCompletionStream, RoutingError } from "~/services/routing/router"
+import { assertRoutableModel, createBufferedCompletionStream, createRoutedCompletion, createRoutedCompletionStream, RoutingError } from "~/services/routing/router"
 import type { OpenAIChatCompletionRequest } from "./types"
 import {
     mapModel,
@@ -19,9 +19,21 @@ import {
 import { translateToolChoice } from "./tool-choice"
 import { validateChatRequest } from "~/lib/validation"
 import { rateLimiter } from "~/lib/rate-limiter"
-import { forwardError, summarizeUpstreamError, UpstreamError } from "~/lib/error"
+import { acquireRequestPermit, acquireStreamSlot } from "~/lib/concurrency"
+import { withDeadline } from "~/lib/deadline"
+import { sessionTicketFor, withSupersede, type SessionTicket } from "~/lib/supersede"
+import { resolveStreamMode, resolveUpstreamMode } from "~/lib/stream-mode"
+import { isRequestLogSampled } from "~/lib/logger"
+import { getClientId } from "~/lib/client-id"
+import { updateRequestContext } from "~/lib/request-context"
+import { AntigravityError, ConcurrencyLimitError, forwardError, summarizeUpstreamError, UpstreamError } from "~/lib/error"
+import { formatStreamErrorFrame } from "~/lib/stream-error"
+import { capStreamBytes } from "~/lib/stream-byte-cap"
 
 export async function handleChatCompletion(c: Context): Promise<Response> {
+    let releasePermit: (() => void) | null = null
+    let releaseStream: (() => void) | null = null
+    let sessionTicket: SessionTicket | null = null
     try {
         const payload = await c.req.json<OpenAIChatCompletionRequest>()
 
@@ -30,20 +42,50 @@ export async function handleChatCompletion(c: Context): Promise<Response> {
         if (!validation.valid) {
             return c.json({ error: { type: "invalid_request_error", message: validation.error } }, 400)
         }
+        // 🆕 未显式指定 stream 时按 Accept 头决定
+        payload.stream = resolveStreamMode(payload.stream, c.req.header("Accept"))
+        const anthropicModel = mapModel(payload.model)
+
+        // 🆕 不可路由的模型在限流与许可之前拒绝，无效请求不占用节流名额
+        try {
+            assertRoutableModel(anthropicModel)
+        } catch (error) {
+            if (error instanceof RoutingError) {
+                return c.json({ error: { type: "invalid_request_error", message: error.message } }, error.status as any)
+            }
+            throw error
+        }
 
-        await rateLimiter.wait()
+        // 🆕 限流与许可等待计入端到端截止时间
+        // 🆕 同一会话的新请求会取代仍在排队的旧请求（ANTI_API_SUPERSEDE_BY_SESSION）
+        sessionTicket = sessionTicketFor(c)
+        await withSupersede(sessionTicket, withDeadline(rateLimiter.wait()))
+        releasePermit = await withSupersede(sessionTicket, withDeadline(acquireRequestPermit(), release => release()), release => release())
+        sessionTicket?.dispatch()
+        // 🆕 流式请求另占一个流式许可；已满时按配置拒绝或降级为非流式
+        releaseStream = payload.stream ? acquireStreamSlot() : null
+        if (payload.stream && !releaseStream) payload.stream = false
 
-        const anthropicModel = mapModel(payload.model)
-        console.log(`[Incoming] model="${payload.model}" stream=${!!payload.stream}`)
+        if (isRequestLogSampled()) console.log(`[Incoming] model="${payload.model}" stream=${!!payload.stream} client=${getClientId(c)}`)
+        updateRequestContext({ model: anthropicModel, stream: !!payload.stream, extraQuery: payload.extra_query })
         if (payload.model !== anthropicModel) {
-            console.log(`[Incoming] model remapped: "${payload.model}" -> "${anthropicModel}"`)
+            if (isRequestLogSampled()) console.log(`[Incoming] model remapped: "${payload.model}" -> "${anthropicModel}"`)
         }
         const messages = translateMessages(payload.messages)
         const tools = translateTools(payload.tools)
         const toolChoice = translateToolChoice(payload.tool_choice)
 
         if (payload.stream) {
-            return handleStreamCompletion(c, payload, anthropicModel, messages, tools, toolChoice)
+            // 流式请求的全局许可在流结束时释放
+            const permit = releasePermit
+            const streamSlot = releaseStream
+            releasePermit = null
+            releaseStream = null
+            const release = () => {
+                permit()
+                streamSlot?.()
+            }
+            return handleStreamCompletion(c, payload, anthropicModel, messages, tools, toolChoice, release)
         }
 
         let result
@@ -100,6 +142,9 @@ export async function handleChatCompletion(c: Context): Promise<Response> {
         // Token counts for response (Usage recording is handled in chat.ts with actual native model ID)
         const inputTokens = chatResponse.usage?.inputTokens || 0
         const outputTokens = chatResponse.usage?.outputTokens || 0
+        if (chatResponse.upstreamRequestId) c.header("X-Upstream-Request-Id", chatResponse.upstreamRequestId)
+        if (chatResponse.fallbackModel) c.header("X-Fallback-Model", chatResponse.fallbackModel)
+        if (chatResponse.truncated) c.header("X-Response-Truncated", "true")
 
         return c.json({
             id: generateChatId(),
@@ -112,15 +157,20 @@ export async function handleChatCompletion(c: Context): Promise<Response> {
                 completion_tokens: outputTokens,
                 total_tokens: inputTokens + outputTokens,
             },
+            ...(chatResponse.upstreamRequestId ? { upstream_request_id: chatResponse.upstreamRequestId } : {}),
+            ...(chatResponse.fallbackModel ? { fallback_model: chatResponse.fallbackModel } : {}),
+            ...(chatResponse.truncated ? { truncated: true } : {}),
         })
     } catch (error) {
-        if (error instanceof UpstreamError) {
+        if (error instanceof UpstreamError || error instanceof ConcurrencyLimitError || error instanceof AntigravityError) {
             return await forwardError(c, error)
         }
         consola.error("OpenAI completion error:", error)
         return c.json({ error: { message: (error as Error).message, type: "api_error" } }, 500)
     } finally {
-        // no-op
+        if (releasePermit) releasePermit()
+        if (releaseStream) releaseStream()
+        sessionTicket?.done()
     }
 }
 
@@ -130,13 +180,19 @@ async function handleStreamCompletion(
     anthropicModel: string,
     messages: any[],
     tools: any[] | undefined,
-    toolChoice: any | undefined
+    toolChoice: any | undefined,
+    releasePermit: () => void
 ): Promise<Response> {
     const chatId = generateChatId()
 
     return streamSSE(c, async (stream) => {
         try {
-            const chatStream = createRoutedCompletionStream({
+            // 🆕 X-Upstream-Mode / 按模型配置 / ANTI_API_STREAM_UPSTREAM_MODE = buffered 时上游缓冲、客户端仍为 SSE
+            const openStream = resolveUpstreamMode(c.req.header("X-Upstream-Mode"), anthropicModel) === "buffered"
+                ? createBufferedCompletionStream
+                : createRoutedCompletionStream
+            // 🆕 ANTI_API_MAX_STREAM_BYTES 超限时以 stream_too_large 错误帧结束
+            const chatStream = capStreamBytes(openStream({
                 model: anthropicModel,
                 messages,
                 tools,
@@ -150,7 +206,7 @@ async function handleStreamCompletion(
                 stop: payload.stop,
                 seed: payload.seed,
                 responseFormat: payload.response_format,
-            })
+            }))
 
             let sentRole = false
             let accumulatedToolCalls: any[] = []
@@ -234,22 +290,14 @@ async function handleStreamCompletion(
             await stream.writeSSE({ data: "[DONE]" })
         } catch (error) {
             if (error instanceof UpstreamError) {
-                const summary = summarizeUpstreamError(error)
-                consola.error("OpenAI stream error:", summary.message)
-                await stream.writeSSE({
-                    data: JSON.stringify({
-                        error: {
-                            type: "upstream_error",
-                            message: summary.message,
-                            provider: error.provider,
-                            ...(summary.reason ? { reason: summary.reason } : {}),
-                        },
-                    }),
-                })
+                consola.error("OpenAI stream error:", summarizeUpstreamError(error).message)
             } else {
                 consola.error("OpenAI stream error:", error)
-                await stream.writeSSE({ data: JSON.stringify({ error: { message: (error as Error).message, type: "api_error" } }) })
             }
-        } finally { }
+            // 🆕 以错误帧结束流（不发送 [DONE]），客户端可区分截断与正常结束
+            await stream.write(formatStreamErrorFrame(error, "openai")).catch(() => { })
+        } finally {
+            releasePermit()
+        }
     })
 }
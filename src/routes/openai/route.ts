@@ -4,10 +4,25 @@
 
 import { Hono } from "hono"
 import { forwardError } from "~/lib/error"
+import { maintenanceGuard } from "~/lib/maintenance"
+import { loadShedGuard } from "~/lib/load-shed"
+import { idempotencyGuard } from "~/lib/idempotency"
+import { diskCacheGuard } from "~/lib/disk-cache"
+import { rateLimitHeaders } from "~/lib/rate-limit-headers"
+import { authGuard } from "~/lib/authenticator"
+import { billingTagGuard } from "~/lib/billing-tag"
 import { handleChatCompletion } from "./handler"
 
 export const openaiRoutes = new Hono()
 
+openaiRoutes.use(authGuard)
+openaiRoutes.use(billingTagGuard)
+openaiRoutes.use(rateLimitHeaders)
+openaiRoutes.use(maintenanceGuard)
+openaiRoutes.use(loadShedGuard)
+openaiRoutes.use(idempotencyGuard)
+openaiRoutes.use(diskCacheGuard)
+
 openaiRoutes.post("/", async (c) => {
     try {
         return await handleChatCompletion(c)
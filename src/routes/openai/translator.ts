@@ -4,9 +4,14 @@
 
 import type { ClaudeMessage, ClaudeTool } from "~/lib/translator"
 import type { OpenAIMessage, OpenAITool } from "./types"
+import { envBool } from "~/lib/env"
 
+/**
+ * 模型名规范化：去除首尾空白，默认转小写（ANTI_API_MODEL_LOWERCASE=0 可关闭）
+ */
 export function mapModel(openaiModel: string): string {
-    return (openaiModel || "").trim().toLowerCase()
+    const trimmed = (openaiModel || "").trim()
+    return envBool("ANTI_API_MODEL_LOWERCASE", true) ? trimmed.toLowerCase() : trimmed
 }
 
 export function translateMessages(messages: OpenAIMessage[]): ClaudeMessage[] {
@@ -3,6 +3,7 @@
  */
 
 export interface OpenAIChatCompletionRequest {
+    /** 🆕 客户端可省略，校验时由 ANTI_API_DEFAULT_MODEL 补全 */
     model: string
     messages: OpenAIMessage[]
     stream?: boolean
@@ -19,6 +20,8 @@ export interface OpenAIChatCompletionRequest {
     }
     tools?: OpenAITool[]
     tool_choice?: "none" | "auto" | "required" | { type: "function"; function: { name: string } }
+    /** 🆕 附加到上游 URL 的查询参数（需服务端开启） */
+    extra_query?: Record<string, string>
 }
 
 export interface OpenAIMessage {
@@ -13,6 +13,7 @@ import { openaiRoutes } from "./routes/openai/route"
 import { authRouter } from "./routes/auth/route"
 import { routingRouter } from "./routes/routing/route"
 import { logsRouter } from "./routes/logs/route"
+import { adminRouter } from "./routes/admin/route"
 import { AVAILABLE_MODELS } from "./lib/config"
 import { getAggregatedQuota } from "./services/quota-aggregator"
 import { initAuth, isAuthenticated } from "./services/antigravity/login"
@@ -20,20 +21,135 @@ import { accountManager } from "./services/antigravity/account-manager"
 import { loadRoutingConfig } from "./services/routing/config"
 import { loadSettings, saveSettings } from "./services/settings"
 import { pingAccount } from "./services/ping"
-import { summarizeUpstreamError, UpstreamError } from "./lib/error"
+import { tryConsumeValidationSlot, validateAccessToken } from "./services/antigravity/token-validate"
+import { handleUncaughtError, summarizeUpstreamError, UpstreamError } from "./lib/error"
 import { authStore } from "./services/auth/store"
 import { getDiscoveredModels, initModelDiscovery, refreshDiscoveredModels } from "./services/model-discovery"
 
 import { getRequestLogContext } from "./lib/logger"
 import { initLogCapture, setLogCaptureEnabled } from "./lib/log-buffer"
+import { envBool, envInt, envString, getFileConfigValues } from "./lib/env"
+import { globalSemaphore } from "./lib/concurrency"
+import { isMaintenanceEnabled } from "./lib/maintenance"
+import { clientIdentity, getClientId, getClientMetricLabel } from "./lib/client-id"
+import { metrics, incrementCounter, observeHistogram, renderMetrics, resetMetrics, setGauge, snapshotMetrics } from "./lib/metrics"
+import { classifyOutcome, endpointIndexLabel, modelLabel } from "./lib/metric-labels"
+import { isFaultInjectionEnabled } from "./lib/fault-inject"
+import { readForcedEndpointHeader } from "./lib/forced-endpoint"
+import { readDelayHeader } from "./lib/delay-inject"
+import { billingTagLabel, formatBillingTag } from "./lib/billing-tag"
+import { CLIENT_CLOSED_REQUEST, getRequestContext, getRequestIdHeader, resolveRequestId, runWithRequestContext, trackResponseCompletion, type RequestContext } from "./lib/request-context"
+import { getRecentRequests, getStatusCounts, recordRecentRequest } from "./lib/recent-requests"
+import { getTrafficStats, recordTraffic } from "./lib/traffic-stats"
+import { endSpan, isTracingEnabled, startTrace } from "./lib/tracing"
+import { getBenchmarkStats, isBenchmarkMode, recordBenchmarkSample, resetBenchmarkStats } from "./lib/benchmark"
+import { requireAdmin } from "./lib/admin-auth"
+import { isEnvelopeEchoEnabled } from "./lib/envelope-echo"
+import { getClientIp, ipConnectionLimit } from "./lib/ip-limit"
+import { responseCompression } from "./lib/compression"
+import { getEndpointHealth } from "./lib/endpoint-health"
+import { listCooldowns } from "./services/cooldowns"
+import { getConfigFileStatus } from "./lib/config-file"
+import { getAntigravityBaseUrls } from "./services/antigravity/chat"
+
+/**
+ * 🆕 路由前缀 (ANTI_API_BASE_PATH)，如 "/anti"；空字符串表示根路径
+ */
+export function normalizeBasePath(raw: string | undefined): string {
+    const trimmed = (raw || "").trim().replace(/\/+$/, "")
+    if (!trimmed) return ""
+    return trimmed.startsWith("/") ? trimmed : `/${trimmed}`
+}
+
+export const basePath = normalizeBasePath(envString("ANTI_API_BASE_PATH"))
 
 export const server = new Hono()
 
+// 🆕 处理器未捕获的异常统一返回结构化 JSON
+server.onError(handleUncaughtError)
+
 initLogCapture()
 setLogCaptureEnabled(loadSettings().captureLogs)
 initModelDiscovery()
 consola.level = 0
 
+// 🆕 请求上下文：分配请求 ID（ANTI_API_REQUEST_ID_HEADER，默认 X-Request-Id），模型请求结束后写入最近请求缓冲并导出追踪
+server.use(async (c, next) => {
+    const requestIdHeader = getRequestIdHeader()
+    const ctx: RequestContext = {
+        requestId: resolveRequestId(c.req.header(requestIdHeader)),
+        startedAt: Date.now(),
+        method: c.req.method,
+        path: c.req.path,
+        upstreamCalls: 0,
+        trace: isTracingEnabled() ? startTrace(c.req.header("traceparent")) : undefined,
+        echoEnvelope: isEnvelopeEchoEnabled(c),
+        faultHeader: isFaultInjectionEnabled() ? c.req.header("X-Fault-Inject") : undefined,
+        forcedEndpoint: readForcedEndpointHeader(c),
+        delayHeader: readDelayHeader(c),
+    }
+    await runWithRequestContext(ctx, next)
+    c.header(requestIdHeader, ctx.requestId)
+    if (!ctx.model) return
+
+    ctx.clientId = getClientId(c)
+    const clientIp = getClientIp(c)
+    const responseStatus = c.res.status
+    const contentLength = Number.parseInt(c.req.header("Content-Length") || "", 10)
+    const requestBytes = Number.isFinite(contentLength) && contentLength >= 0 ? contentLength : null
+    c.res = trackResponseCompletion(c.res, (outcome) => {
+        // 🆕 客户端中途断开记为 499，不算作上游错误或成功
+        const status = outcome.cancelled ? CLIENT_CLOSED_REQUEST : responseStatus
+        if (outcome.cancelled) {
+            incrementCounter("client_disconnects", { route: c.req.routePath })
+            console.log(`${status}: client closed request from ${ctx.model}${ctx.clientId !== "anonymous" ? ` [client=${ctx.clientId}]` : ""}`)
+        }
+        recordRecentRequest({
+            requestId: ctx.requestId,
+            time: new Date(ctx.startedAt).toISOString(),
+            method: ctx.method,
+            path: ctx.path,
+            model: ctx.model,
+            clientId: ctx.clientId,
+            clientIp,
+            stream: ctx.stream,
+            status,
+            latencyMs: Date.now() - ctx.startedAt,
+            endpoint: ctx.endpoint,
+            retryAfterMs: ctx.retryAfterMs ?? null,
+            upstreamRequestId: ctx.upstreamRequestId,
+            ...(ctx.billingTag ? { billingTag: ctx.billingTag } : {}),
+        })
+        if (isBenchmarkMode() && ctx.upstreamCalls > 0) {
+            recordBenchmarkSample(Date.now() - ctx.startedAt - (ctx.syntheticUpstreamMs || 0))
+        }
+        if (ctx.upstreamCalls > 0) observeHistogram("upstream_calls_per_request", ctx.upstreamCalls, { route: c.req.routePath })
+        // 🆕 请求/响应字节数（按模型）
+        const upstreamBytes = ctx.upstreamBytes || 0
+        observeHistogram("upstream_response_bytes", upstreamBytes, { model: ctx.model })
+        if (requestBytes !== null) observeHistogram("request_bytes", requestBytes, { model: ctx.model })
+        recordTraffic(ctx.model!, requestBytes ?? 0, upstreamBytes)
+        if (ctx.trace) {
+            endSpan(ctx.trace, `${ctx.method} ${c.req.routePath}`, ctx.startedAt, {
+                "http.request.method": ctx.method,
+                "http.route": c.req.routePath,
+                "http.response.status_code": status,
+                "anti_api.request_id": ctx.requestId,
+                "anti_api.model": ctx.model,
+                "anti_api.endpoint": ctx.endpoint,
+                "anti_api.retry_count": Math.max(0, ctx.upstreamCalls - 1),
+                "anti_api.stream": ctx.stream,
+            }, status >= 500)
+        }
+    })
+})
+
+// 🆕 单 IP 并发连接上限（ANTI_API_MAX_CONN_PER_IP）
+server.use(ipConnectionLimit)
+
+// 🆕 响应压缩（ANTI_API_RESPONSE_COMPRESSION）
+server.use(responseCompression)
+
 // 中间件 - 请求日志 (只记录重要请求)
 server.use(async (c, next) => {
     await next()
@@ -43,16 +159,49 @@ server.use(async (c, next) => {
     // Only log errors
     if (status >= 400) {
         const ctx = getRequestLogContext()
+        const clientId = getClientId(c)
+        const clientPart = (clientId !== "anonymous" ? ` [client=${clientId}]` : "") + formatBillingTag()
         if (ctx.model && ctx.provider) {
             const providerName = ctx.provider === "antigravity" ? "Antigravity" : ctx.provider
             const accountPart = ctx.account ? ` >> ${ctx.account}` : ""
-            console.log(`${status}: from ${ctx.model} > ${providerName}${accountPart}`)
+            console.log(`${status}: from ${ctx.model} > ${providerName}${accountPart}${clientPart}`)
         } else {
-            console.log(`${status}: ${reason || "error"}`)
+            console.log(`${status}: ${reason || "error"}${clientPart}`)
         }
     }
     // All successful requests are silent (detailed 200 logs are handled elsewhere)
 })
+
+// 🆕 调用方标识 (X-Client-Id) - 只作用于模型相关端点
+for (const path of ["/v1/*", "/v1beta/*", "/messages/*", "/messages", "/models"]) {
+    server.use(path, clientIdentity)
+}
+
+// 🆕 请求指标
+server.use(async (c, next) => {
+    if (metrics.name === "none") return next()
+    const startedAt = performance.now()
+    await next()
+    // 在响应体发送完毕时记录，流式请求的耗时与 499 断开才准确
+    const status = c.res.status
+    const requestCtx = getRequestContext()
+    c.res = trackResponseCompletion(c.res, (outcome) => {
+        const tags = { method: c.req.method, route: c.req.routePath, status: outcome.cancelled ? CLIENT_CLOSED_REQUEST : status, client: getClientMetricLabel(c) }
+        incrementCounter("http_requests_total", tags)
+        observeHistogram("http_request_duration_seconds", (performance.now() - startedAt) / 1000, tags)
+        // 🆕 模型请求按 model / outcome / endpoint_index / billing_tag 细分（见 lib/metric-labels、lib/billing-tag）
+        if (requestCtx?.model) {
+            const modelTags = {
+                model: modelLabel(requestCtx.model),
+                outcome: classifyOutcome(tags.status),
+                endpoint_index: endpointIndexLabel(requestCtx.endpointIndex),
+                billing_tag: billingTagLabel(requestCtx.billingTag),
+            }
+            incrementCounter("model_requests_total", modelTags)
+            observeHistogram("model_request_duration_seconds", (performance.now() - startedAt) / 1000, modelTags)
+        }
+    })
+})
 server.use(cors())
 
 // 启动时自动加载已保存的认证
@@ -61,7 +210,7 @@ server.use(cors())
 // accountManager.load()
 
 // 根路径 - 重定向到配额面板
-server.get("/", (c) => c.redirect("/quota"))
+server.get("/", (c) => c.redirect(`${basePath}/quota`))
 
 // Auth 路由
 server.route("/auth", authRouter)
@@ -72,6 +221,9 @@ server.route("/routing", routingRouter)
 // Logs
 server.route("/logs", logsRouter)
 
+// 🆕 管理接口
+server.route("/admin", adminRouter)
+
 // Settings API - 获取设置
 server.get("/settings", (c) => {
     return c.json(loadSettings())
@@ -79,7 +231,10 @@ server.get("/settings", (c) => {
 
 // Settings API - 保存设置
 server.post("/settings", async (c) => {
-    const body = await c.req.json()
+    const body = await c.req.json().catch(() => null)
+    if (!body || typeof body !== "object") {
+        return c.json({ error: { type: "invalid_request_error", message: "Invalid JSON body" } }, 400)
+    }
     const updated = saveSettings(body)
     setLogCaptureEnabled(updated.captureLogs)
     return c.json(updated)
@@ -97,6 +252,28 @@ server.post("/usage/reset", (c) => {
     return c.json({ success: true })
 })
 
+// 🆕 运行统计：并发占用、按模型的字节数、端点健康、状态码计数与冷却（需管理密钥；/admin/ui 轮询此接口）
+server.get("/stats", requireAdmin, (c) => {
+    return c.json({
+        uptime_seconds: Math.round(process.uptime()),
+        concurrency: {
+            in_use: globalSemaphore.inUse,
+            waiting: globalSemaphore.waiting,
+            limit: globalSemaphore.capacity,
+        },
+        traffic: getTrafficStats(),
+        endpoints: getEndpointHealth(getAntigravityBaseUrls()),
+        status_counts: getStatusCounts(),
+        cooldowns: listCooldowns(),
+    })
+})
+
+// 🆕 配置文件状态与已加载的键名（需管理密钥；不返回值，避免泄露密钥类配置）
+server.get("/config", requireAdmin, (c) => c.json({
+    ...getConfigFileStatus(),
+    keys: Object.keys(getFileConfigValues()).sort(),
+}))
+
 // OpenAI 兼容端点
 server.route("/v1/chat/completions", openaiRoutes)
 
@@ -192,6 +369,26 @@ server.get("/quota/json", async (c) => {
     }
 })
 
+// 🆕 校验 access token（不发起生成请求，单独限流）
+server.post("/validate-token", async (c) => {
+    const body = await c.req.json().catch(() => null) as { access_token?: unknown; project?: unknown } | null
+    if (!body || typeof body.access_token !== "string" || !body.access_token.trim()) {
+        return c.json({ error: { type: "invalid_request_error", message: "access_token is required" } }, 400)
+    }
+    if (body.project !== undefined && typeof body.project !== "string") {
+        return c.json({ error: { type: "invalid_request_error", message: "project must be a string" } }, 400)
+    }
+    if (!tryConsumeValidationSlot()) {
+        c.header("Retry-After", "60")
+        return c.json({ error: { type: "rate_limit_error", message: "Too many token validation requests" } }, 429)
+    }
+    try {
+        return c.json(await validateAccessToken(body.access_token.trim(), body.project || undefined))
+    } catch (error) {
+        return c.json({ valid: false, status: "error", upstream_status: 0, message: (error as Error).message }, 502)
+    }
+})
+
 // Ping model availability for a specific account
 server.post("/accounts/ping", async (c) => {
     let body: { provider?: string; accountId?: string; modelId?: string } = {}
@@ -311,14 +508,61 @@ server.post("/v1/responses", (c) => c.json({
     error: { type: "not_supported", message: "Responses API not supported" }
 }, 501))
 
+// 🆕 容量查询 - 可选 ?wait=1 长轮询直到有空闲许可（最长 ANTI_API_CAPACITY_MAX_WAIT_MS）
+server.get("/capacity", async (c) => {
+    const snapshot = () => ({
+        unlimited: globalSemaphore.isUnlimited(),
+        limit: globalSemaphore.capacity,
+        inUse: globalSemaphore.inUse,
+        waiting: globalSemaphore.waiting,
+        available: globalSemaphore.isUnlimited() ? null : globalSemaphore.available,
+    })
+
+    const wait = c.req.query("wait")
+    if (wait !== "1" && wait !== "true") {
+        return c.json(snapshot())
+    }
+
+    const maxWaitMs = Math.max(0, envInt("ANTI_API_CAPACITY_MAX_WAIT_MS", 30000))
+    const requested = Number.parseInt(c.req.query("timeout_ms") || "", 10)
+    const timeoutMs = Number.isFinite(requested) && requested >= 0 ? Math.min(requested, maxWaitMs) : maxWaitMs
+    const freed = await globalSemaphore.waitForAvailable(timeoutMs)
+    if (!freed) {
+        c.header("Retry-After", "1")
+        return c.json(snapshot(), 503)
+    }
+    return c.json(snapshot())
+})
+
 // 健康检查
-server.get("/health", (c) => c.json({
+const healthRouter = new Hono()
+
+healthRouter.get("/", (c) => c.json({
     status: "ok",
     authenticated: isAuthenticated(),
 }))
 
+// 🆕 就绪检查：返回各依赖状态；维护模式或所有端点均不健康时返回 503
+healthRouter.get("/ready", (c) => {
+    const maintenance = isMaintenanceEnabled()
+    const endpoints = getEndpointHealth(getAntigravityBaseUrls())
+    const endpointAvailable = endpoints.some(endpoint => endpoint.state !== "unhealthy")
+    const configStatus = getConfigFileStatus()
+    const ready = !maintenance && endpointAvailable
+    return c.json({
+        ready,
+        maintenance,
+        endpoints,
+        config: {
+            path: configStatus.path,
+            loaded: configStatus.loaded,
+            ...(configStatus.error ? { error: configStatus.error } : {}),
+        },
+    }, ready ? 200 : 503)
+})
+
 // 🆕 断路器状态监控
-server.get("/health/circuit-breakers", async (c) => {
+healthRouter.get("/circuit-breakers", async (c) => {
     const { accountCircuitBreakers } = await import("./lib/circuit-breaker")
     const metrics = accountCircuitBreakers.getAllMetrics()
     
@@ -347,8 +591,81 @@ server.get("/health/circuit-breakers", async (c) => {
 })
 
 // 🆕 重置所有断路器
-server.post("/health/circuit-breakers/reset", async (c) => {
+healthRouter.post("/circuit-breakers/reset", async (c) => {
     const { accountCircuitBreakers } = await import("./lib/circuit-breaker")
     accountCircuitBreakers.resetAll()
     return c.json({ success: true, message: "All circuit breakers reset" })
 })
+
+server.route("/health", healthRouter)
+
+// 🆕 Prometheus 指标（其他后端返回 404）
+const metricsRouter = new Hono()
+
+metricsRouter.get("/", (c) => {
+    setGauge("concurrency_in_use", globalSemaphore.inUse)
+    setGauge("concurrency_waiting", globalSemaphore.waiting)
+    const body = renderMetrics()
+    if (body === null) {
+        return c.json({ error: { type: "not_found", message: `Metrics backend "${metrics.name}" has no scrape endpoint` } }, 404)
+    }
+    return c.text(body, 200, { "Content-Type": "text/plain; version=0.0.4" })
+})
+
+// 🆕 清空内存中的指标（需管理密钥）
+metricsRouter.post("/reset", requireAdmin, (c) => {
+    if (!resetMetrics()) {
+        return c.json({ error: { type: "not_found", message: `Metrics backend "${metrics.name}" keeps no local state` } }, 404)
+    }
+    return c.json({ success: true })
+})
+
+server.route("/metrics", metricsRouter)
+
+// 🆕 同一份指标的 JSON 导出，供无法抓取 Prometheus 文本格式的工具使用（需管理密钥：标签含调用方标识）
+server.get("/metrics.json", requireAdmin, (c) => {
+    setGauge("concurrency_in_use", globalSemaphore.inUse)
+    setGauge("concurrency_waiting", globalSemaphore.waiting)
+    const snapshot = snapshotMetrics()
+    if (snapshot === null) {
+        return c.json({ error: { type: "not_found", message: `Metrics backend "${metrics.name}" has no local registry` } }, 404)
+    }
+    return c.json(snapshot)
+})
+
+// 🆕 调试接口（需管理密钥）：最近请求摘要，不含 token
+const debugRouter = new Hono()
+debugRouter.use(requireAdmin)
+
+debugRouter.get("/recent", (c) => {
+    const requested = Number.parseInt(c.req.query("n") || "", 10)
+    const limit = Number.isFinite(requested) && requested > 0 ? requested : 50
+    return c.json({ requests: getRecentRequests(limit) })
+})
+
+// 🆕 基准测试模式：代理自身开销分位数
+debugRouter.get("/benchmark", (c) => c.json(getBenchmarkStats()))
+
+debugRouter.post("/benchmark/reset", (c) => {
+    resetBenchmarkStats()
+    return c.json({ success: true })
+})
+
+server.route("/debug", debugRouter)
+
+/**
+ * 🆕 对外入口：按 basePath 挂载全部路由
+ * ANTI_API_INFRA_AT_ROOT=1 时健康检查与指标额外保留在根路径，供基础设施探针使用
+ */
+export const app = new Hono()
+app.onError(handleUncaughtError)
+
+if (basePath) {
+    app.route(basePath, server)
+    if (envBool("ANTI_API_INFRA_AT_ROOT")) {
+        app.route("/health", healthRouter)
+        app.route("/metrics", metricsRouter)
+    }
+} else {
+    app.route("/", server)
+}
@@ -10,10 +10,12 @@ import * as path from "path"
 import consola from "consola"
 import { authStore } from "~/services/auth/store"
 import { parseRetryDelay } from "~/lib/retry"
-import { MIN_REQUEST_INTERVAL_MS } from "~/lib/constants"
+import { getMinRequestIntervalMs } from "~/lib/rate-limiter"
 import { fetchAntigravityModels, pickResetTime } from "./quota-fetch"
 import { UpstreamError } from "~/lib/error"
 import { getDataDir } from "~/lib/data-dir"
+import { Semaphore } from "~/lib/semaphore"
+import { acquirePermit, getPerAccountConcurrencyLimit } from "~/lib/concurrency"
 
 type RateLimitReason =
     | "quota_exhausted"
@@ -151,9 +153,8 @@ class AccountManager {
     private lastUsedAccount: { accountId: string; timestamp: number } | null = null
     // 🆕 粘性账户队列：失败的账户移到队尾，避免反复 429
     private accountQueue: string[] = []
-    // 🆕 账号并发控制（同一账号同一时刻只处理一个请求）
-    private inFlightAccounts = new Set<string>()
-    private accountLocks = new Map<string, Promise<void>>()
+    // 🆕 账号并发控制（默认同一账号同一时刻只处理一个请求，空闲时回收信号量）
+    private accountSemaphores = new Map<string, Semaphore>()
     private lastCallByAccount = new Map<string, number>()
     // 🆕 模型级别的限流状态：key = "accountId:modelId", value = rateLimitedUntil timestamp
     private modelRateLimits = new Map<string, number>()
@@ -307,8 +308,7 @@ class AccountManager {
         if (this.accounts.has(accountIdOrEmail)) {
             this.accounts.delete(accountIdOrEmail)
             removeFromQueue(accountIdOrEmail)
-            this.inFlightAccounts.delete(accountIdOrEmail)
-            this.accountLocks.delete(accountIdOrEmail)
+            this.accountSemaphores.delete(accountIdOrEmail)
             this.lastCallByAccount.delete(accountIdOrEmail)
             this.save()
             authStore.deleteAccount("antigravity", accountIdOrEmail)
@@ -320,8 +320,7 @@ class AccountManager {
             if (acc.email === accountIdOrEmail) {
                 this.accounts.delete(id)
                 removeFromQueue(id)
-                this.inFlightAccounts.delete(id)
-                this.accountLocks.delete(id)
+                this.accountSemaphores.delete(id)
                 this.lastCallByAccount.delete(id)
                 this.save()
                 authStore.deleteAccount("antigravity", id)
@@ -360,43 +359,55 @@ class AccountManager {
      * 🆕 账号是否正在处理请求
      */
     isAccountInFlight(accountId: string): boolean {
-        return this.inFlightAccounts.has(accountId)
+        const semaphore = this.accountSemaphores.get(accountId)
+        return !!semaphore && semaphore.inUse > 0
     }
 
     /**
-     * 🆕 获取账号锁，确保同一账号串行处理
+     * 🆕 账号是否已达到并发上限
      */
-    async acquireAccountLock(accountId: string): Promise<() => void> {
-        this.ensureLoaded()
-        const previous = this.accountLocks.get(accountId) || Promise.resolve()
-        let resolveNext: () => void
-
-        const next = new Promise<void>(resolve => {
-            resolveNext = resolve
-        })
+    isAccountAtCapacity(accountId: string): boolean {
+        const semaphore = this.accountSemaphores.get(accountId)
+        return !!semaphore && semaphore.isFull()
+    }
 
-        const tail = previous.then(() => next)
-        this.accountLocks.set(accountId, tail)
+    private getAccountSemaphore(accountId: string): Semaphore {
+        const limit = getPerAccountConcurrencyLimit()
+        let semaphore = this.accountSemaphores.get(accountId)
+        if (!semaphore) {
+            semaphore = new Semaphore(limit)
+            this.accountSemaphores.set(accountId, semaphore)
+        } else if (semaphore.capacity !== limit) {
+            semaphore.setLimit(limit)
+        }
+        return semaphore
+    }
 
-        await previous
+    /**
+     * 🆕 获取账号并发许可（ANTI_API_MAX_CONCURRENCY_PER_ACCOUNT，默认 1 即串行）
+     * fast-fail 模式下超限抛出 ConcurrencyLimitError
+     */
+    async acquireAccountLock(accountId: string): Promise<() => void> {
+        this.ensureLoaded()
+        const semaphore = this.getAccountSemaphore(accountId)
+        const releasePermit = await acquirePermit(semaphore, "account")
 
+        // 按启动时间排队，保证同一账号请求间隔 ≥ 最小请求间隔
         const lastCall = this.lastCallByAccount.get(accountId) || 0
-        const elapsed = Date.now() - lastCall
-        if (elapsed < MIN_REQUEST_INTERVAL_MS) {
-            await new Promise(resolve => setTimeout(resolve, MIN_REQUEST_INTERVAL_MS - elapsed))
+        const scheduledAt = Math.max(Date.now(), lastCall + getMinRequestIntervalMs())
+        this.lastCallByAccount.set(accountId, scheduledAt)
+        const delay = scheduledAt - Date.now()
+        if (delay > 0) {
+            await new Promise(resolve => setTimeout(resolve, delay))
         }
-        this.lastCallByAccount.set(accountId, Date.now())
-
-        this.inFlightAccounts.add(accountId)
 
         let released = false
         return () => {
             if (released) return
             released = true
-            this.inFlightAccounts.delete(accountId)
-            resolveNext!()
-            if (this.accountLocks.get(accountId) === tail) {
-                this.accountLocks.delete(accountId)
+            releasePermit()
+            if (semaphore.isIdle() && this.accountSemaphores.get(accountId) === semaphore) {
+                this.accountSemaphores.delete(accountId)
             }
         }
     }
@@ -579,6 +590,67 @@ class AccountManager {
         }
     }
 
+    /**
+     * 🆕 账号级冷却概况：cooling 为冷却中的账号数，minRemainingMs 为全部冷却时最短剩余时间（否则 null）
+     */
+    getCoolingSummary(): { total: number; cooling: number; minRemainingMs: number | null } {
+        this.ensureLoaded()
+        const now = Date.now()
+        let cooling = 0
+        let minRemainingMs = Number.POSITIVE_INFINITY
+        for (const account of this.accounts.values()) {
+            if (account.rateLimitedUntil !== null && account.rateLimitedUntil > now) {
+                cooling++
+                minRemainingMs = Math.min(minRemainingMs, account.rateLimitedUntil - now)
+            }
+        }
+        const total = this.accounts.size
+        return { total, cooling, minRemainingMs: total > 0 && cooling === total ? minRemainingMs : null }
+    }
+
+    /**
+     * 🆕 列出仍在冷却中的账号/模型（供管理接口使用）
+     */
+    listCooldowns(): Array<{ accountId: string; modelId?: string; until: number }> {
+        const now = Date.now()
+        const result: Array<{ accountId: string; modelId?: string; until: number }> = []
+        for (const [accountId, account] of this.accounts) {
+            if (account.rateLimitedUntil !== null && account.rateLimitedUntil > now) {
+                result.push({ accountId, until: account.rateLimitedUntil })
+            }
+        }
+        for (const [key, until] of this.modelRateLimits) {
+            if (until <= now) continue
+            const separator = key.indexOf(":")
+            result.push({ accountId: key.slice(0, separator), modelId: key.slice(separator + 1), until })
+        }
+        return result
+    }
+
+    /**
+     * 🆕 清除冷却：不传 accountId 清除全部；传 modelId 只清除该模型
+     */
+    clearCooldowns(accountId?: string, modelId?: string): number {
+        let cleared = 0
+        for (const [id, account] of this.accounts) {
+            if (accountId && id !== accountId) continue
+            if (modelId) continue
+            if (account.rateLimitedUntil !== null) {
+                account.rateLimitedUntil = null
+                account.consecutiveFailures = 0
+                cleared++
+            }
+        }
+        for (const key of Array.from(this.modelRateLimits.keys())) {
+            const separator = key.indexOf(":")
+            if (accountId && key.slice(0, separator) !== accountId) continue
+            if (modelId && key.slice(separator + 1) !== modelId) continue
+            this.modelRateLimits.delete(key)
+            cleared++
+        }
+        return cleared
+    }
+
     /**
      * 🆕 获取所有账户中最短的限流等待时间（毫秒）
      * 返回 null 表示没有账户被限流
@@ -626,7 +698,7 @@ class AccountManager {
             const account = this.accounts.get(id)
             if (!account) return false
             if (account.rateLimitedUntil && account.rateLimitedUntil > now) return false
-            return !this.inFlightAccounts.has(id)
+            return !this.isAccountInFlight(id)
         })
 
         // 🆕 粘性策略：使用队列顺序，队首账户优先
@@ -635,7 +707,7 @@ class AccountManager {
             const firstId = this.accountQueue[0]
             const firstAccount = this.accounts.get(firstId)
             if (firstAccount && (!firstAccount.rateLimitedUntil || firstAccount.rateLimitedUntil <= now)) {
-                if (hasIdleAccount && this.inFlightAccounts.has(firstId)) {
+                if (hasIdleAccount && this.isAccountInFlight(firstId)) {
                     // Prefer idle accounts when available
                 } else {
                     // 刷新 token 如果需要
@@ -670,7 +742,7 @@ class AccountManager {
                 const waitSeconds = Math.ceil((account.rateLimitedUntil - now) / 1000)
                 continue
             }
-            if (hasIdleAccount && this.inFlightAccounts.has(accountId)) {
+            if (hasIdleAccount && this.isAccountInFlight(accountId)) {
                 continue
             }
 
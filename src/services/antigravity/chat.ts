@@ -9,10 +9,45 @@ import { accountManager } from "./account-manager"
 import { DEFAULT_ANTIGRAVITY_USER_AGENT } from "./constants"
 import { state } from "~/lib/state"
 import { type ClaudeMessage, type ClaudeTool } from "~/lib/translator"
-import { determineRetryStrategy, applyRetryDelay } from "~/lib/retry"
-import { UpstreamError } from "~/lib/error"
+import { determineRetryStrategy, applyRetryDelay, areRetriesEnabled, retryBudget } from "~/lib/retry"
+import { AntigravityError, UpstreamError } from "~/lib/error"
 import { cleanJsonSchemaForGemini } from "~/lib/json-schema-cleaner"
-import { formatLogTime, setRequestLogContext } from "~/lib/logger"
+import { formatLogTime, isRequestLogSampled, setRequestLogContext } from "~/lib/logger"
+import { incrementCounter, observeHistogram } from "~/lib/metrics"
+import { openStreamTee } from "~/lib/stream-tee"
+import { getUpstreamTlsOptions, isTlsError } from "~/lib/upstream-tls"
+import { envInt, envList, envString, readEnv } from "~/lib/env"
+import { dumpErrorEvent, shouldDumpStatus } from "~/lib/error-dump"
+import { getRequestContext, trackResponseCompletion, updateRequestContext } from "~/lib/request-context"
+import { formatTraceparent } from "~/lib/tracing"
+import { mirrorToShadow, shouldShadow } from "./shadow"
+import { incompleteResponseError, readCompleteBody } from "~/lib/incomplete-response"
+import { EnvelopeSerializer } from "./envelope"
+import { resolveAllCoolingAccount } from "./cooling-policy"
+import { extractSseEventData, findEmbeddedSseError, isSseValidationEnabled } from "~/lib/sse-embedded-error"
+import { bufferedBodyToSse, getMaxSseFrameBytes, getMaxSseFrames, getOversizedSseFrameMode, isEventStreamContentType, readTextWithIdleTimeout, splitSseFrames, SseFrameSplitter } from "~/lib/sse-frames"
+import { isFailoverStatus, isSuccessStatus } from "~/lib/failover"
+import { getEndpointTimeoutMs } from "~/lib/upstream-timeouts"
+import { buildUpstreamUrl } from "~/lib/extra-query"
+import { recordEndpointResult } from "~/lib/endpoint-health"
+import { recordAutoTuneStatus } from "~/lib/auto-tune"
+import { noteRequestTooLarge } from "~/lib/request-too-large"
+import { isBenchmarkMode, syntheticUpstreamResponse } from "~/lib/benchmark"
+import { injectFault, pickFault } from "~/lib/fault-inject"
+import { applyResolveOverride } from "~/lib/dns-override"
+import { applyOverflowRouting } from "~/lib/overflow"
+import { assertTokenNotExpired } from "~/lib/token-exp"
+import { assertTokenMatchesProject } from "~/lib/token-project"
+import { applyRequestFieldPolicy } from "~/lib/request-field-policy"
+import { isConnectError, withConnectRetry } from "~/lib/connect-retry"
+import { recordEndpointFailure, statusFailureKind, toAllEndpointsFailedError } from "~/lib/endpoint-failures"
+import { shouldReusePooledConnection } from "~/lib/upstream-pool"
+import { selectForcedEndpoint } from "~/lib/forced-endpoint"
+import { formatBillingTag } from "~/lib/billing-tag"
+import { getUpstreamSseEncodingHeaders } from "~/lib/compression"
+import { getInjectedDelayMs, injectDelay } from "~/lib/delay-inject"
+import { globalSemaphore } from "~/lib/concurrency"
+import { checkDeadline, clampToDeadline, deadlineExceededError, isDeadlineExceeded, withDeadline } from "~/lib/deadline"
 // 🆕 导入新的稳定性功能
 import { classifyError, ErrorCategory } from "~/lib/error-categories"
 import { accountCircuitBreakers } from "~/lib/circuit-breaker"
@@ -20,18 +55,56 @@ import { fetchWithTimeout as enhancedFetchWithTimeout, STREAMING_TIMEOUT_CONFIG,
 
 accountManager.load()
 
-const ANTIGRAVITY_BASE_URLS = [
+const DEFAULT_ANTIGRAVITY_BASE_URLS = [
     "https://daily-cloudcode-pa.googleapis.com",       // v2.0.1: 优先使用 daily 端点（更稳定）
     "https://daily-cloudcode-pa.sandbox.googleapis.com",
     "https://cloudcode-pa.googleapis.com",
 ]
+
+/**
+ * 🆕 上游端点列表（ANTI_API_ENDPOINTS 逗号分隔，可热加载）；每次请求开始时取一次快照
+ */
+export function getAntigravityBaseUrls(): string[] {
+    const configured = envList("ANTI_API_ENDPOINTS").map(url => url.replace(/\/+$/, ""))
+    return configured.length > 0 ? configured : DEFAULT_ANTIGRAVITY_BASE_URLS
+}
 const STREAM_ENDPOINT = "/v1internal:streamGenerateContent"
 const DEFAULT_USER_AGENT = DEFAULT_ANTIGRAVITY_USER_AGENT
 const MAX_RETRY_ATTEMPTS = 1  // v2.0.1 恢复：简化重试，避免级联 429
 const MAX_NON_QUOTA_429_RETRIES = 2  // Non-quota 429 retries before switching accounts
 const MAX_NON_QUOTA_429_WAIT_MS = 4000  // Upper bound for non-quota 429 wait time
 const NON_QUOTA_429_COOLDOWN_MS = 8000  // Cooldown before retrying a rate-limited account
-const FETCH_TIMEOUT_MS = 30000  // 防止上游请求长期卡住
+
+/**
+ * 🆕 200 但 body 为空时的处理方式：failover（换下一个端点，默认）或 error（直接返回 502）
+ */
+function shouldFailoverOnEmptyResponse(): boolean {
+    return envString("ANTI_API_EMPTY_RESPONSE_MODE", "failover").toLowerCase() !== "error"
+}
+
+/**
+ * 🆕 流式首个数据帧超时（ANTI_API_TTFT_TIMEOUT_MS，默认 0 = 关闭），从收到响应头开始计时
+ */
+function getTtftTimeoutMs(): number {
+    return Math.max(0, envInt("ANTI_API_TTFT_TIMEOUT_MS", 0))
+}
+
+/**
+ * 🆕 流式空闲超时（ANTI_API_STREAM_IDLE_TIMEOUT_SECS，默认 900，0 = 关闭）
+ * 两次收到上游字节之间的最长间隔（心跳也算字节），与首帧超时和端到端截止时间相互独立；
+ * 尚未向客户端输出时切换端点，已输出时以 stream_idle_timeout 错误帧结束
+ */
+function getStreamIdleTimeoutMs(): number {
+    return Math.max(0, envInt("ANTI_API_STREAM_IDLE_TIMEOUT_SECS", 900)) * 1000
+}
+
+function streamIdleTimeoutError(timeoutMs: number): AntigravityError {
+    return new AntigravityError(`No data from upstream for ${Math.round(timeoutMs / 1000)}s`, "stream_idle_timeout", 504)
+}
+
+function emptyResponseError(): AntigravityError {
+    return new AntigravityError("Upstream returned an empty response", "empty_response", 502)
+}
 
 const SIGNATURE_CACHE_MAX_SIZE = 1000
 const signatureCache = new Map<string, string>()
@@ -146,9 +219,39 @@ function parseDurationString(s: string): number | null {
     return totalMs > 0 ? totalMs : null
 }
 
+/**
+ * 🆕 本次请求的端点列表（[下标, URL]）：X-Endpoint-Index 强制单个端点；
+ * 否则在途请求超过 ANTI_API_OVERFLOW_THRESHOLD 时优先使用溢出端点
+ */
+function resolveUpstreamEndpoints(): Array<[number, string]> {
+    const forced = getRequestContext()?.forcedEndpoint
+    if (forced !== undefined) return selectForcedEndpoint(getAntigravityBaseUrls(), forced)
+    return applyOverflowRouting(getAntigravityBaseUrls(), globalSemaphore.inUse)
+}
+
+/**
+ * 上游请求统一入口
+ * 注意：Bun 的 fetch 由运行时维护全局连接池，不支持按主机创建独立 client/连接池，
+ * 因此无法按 endpoint 隔离连接池；各 endpoint 共享运行时连接池
+ */
 async function fetchWithTimeout(url: string, options: RequestInit, timeoutMs: number): Promise<Response> {
+    // 🆕 单次超时不超过端到端剩余预算
+    checkDeadline()
+    const ctx = getRequestContext()
+    if (ctx) {
+        // 🆕 单个请求的上游调用总数上限（重试 + 切换 + 模型回退共享）
+        const maxCalls = envInt("ANTI_API_MAX_UPSTREAM_CALLS", 0)
+        if (maxCalls > 0 && ctx.upstreamCalls >= maxCalls) {
+            throw new AntigravityError(`Upstream call budget exhausted (${maxCalls} calls)`, "upstream_call_budget_exceeded", 503)
+        }
+        ctx.upstreamCalls++
+    }
+    if (ctx?.echoEnvelope && typeof options.body === "string") ctx.upstreamEnvelope = options.body
+    // 🆕 JWT 令牌已过期时不再发往上游（ANTI_API_CHECK_TOKEN_EXP=1）
+    assertTokenNotExpired(options.headers)
+    if (isBenchmarkMode()) return syntheticUpstreamResponse()
     const controller = new AbortController()
-    const timeoutId = setTimeout(() => controller.abort(), timeoutMs)
+    const timeoutId = setTimeout(() => controller.abort(), clampToDeadline(timeoutMs))
     if (options.signal) {
         if (options.signal.aborted) {
             controller.abort()
@@ -156,13 +259,87 @@ async function fetchWithTimeout(url: string, options: RequestInit, timeoutMs: nu
             options.signal.addEventListener("abort", () => controller.abort(), { once: true })
         }
     }
+    const tls = getUpstreamTlsOptions()
+    const headers = ctx?.trace
+        ? { ...(options.headers as Record<string, string>), traceparent: formatTraceparent(ctx.trace) }
+        : options.headers
+    // 🆕 ANTI_API_RESOLVE 主机覆盖：直连指定 IP，保留 Host 头与 SNI
+    const resolved = applyResolveOverride(url)
+    const fetchUrl = resolved ? resolved.url : url
+    const fetchHeaders = resolved ? { ...(headers as Record<string, string>), Host: resolved.host } : headers
+    const fetchTls = resolved ? { ...tls, serverName: resolved.serverName } : tls
+    const endpoint = new URL(url).origin
+    // 🆕 端点空闲超过 ANTI_API_POOL_IDLE_REUSE_SECS 时不复用池化连接
+    const keepalive = shouldReusePooledConnection(endpoint)
+    const fetchStartedAt = performance.now()
     try {
-        return await fetch(url, { ...options, signal: controller.signal })
+        // 🆕 测试用人为延迟（ANTI_API_INJECT_DELAY_MS / X-Inject-Delay-Ms），计入本次超时
+        await injectDelay(getInjectedDelayMs(ctx?.delayHeader), controller.signal)
+        // 🆕 测试模式故障注入（ANTI_API_FAULT_INJECT=1）
+        const fault = pickFault(ctx?.faultHeader)
+        const response = fault
+            ? await injectFault(fault, url, controller.signal)
+            : await withConnectRetry(
+                () => fetch(fetchUrl, { ...options, headers: fetchHeaders, signal: controller.signal, keepalive, ...(fetchTls ? { tls: fetchTls } : {}) }),
+                controller.signal,
+                endpoint,
+            )
+        recordEndpointResult(endpoint, response.status, performance.now() - fetchStartedAt)
+        recordAutoTuneStatus(response.status)
+        if (response.status === 413) noteRequestTooLarge(options.body)
+        if (!isSuccessStatus(response.status)) recordEndpointFailure(endpoint, statusFailureKind(response.status), { status: response.status })
+        // 🆕 每个请求只在首次上游调用时按比例复制到影子端点
+        if (ctx?.upstreamCalls === 1 && shouldShadow()) {
+            mirrorToShadow(url, { ...options, headers, signal: undefined }, response.status)
+        }
+        // 🆕 上游响应字节数随读取累加，不额外缓冲
+        if (!ctx) return response
+        return trackResponseCompletion(response, ({ bytes }) => {
+            ctx.upstreamBytes = (ctx.upstreamBytes || 0) + bytes
+        })
+    } catch (error) {
+        if (controller.signal.aborted && isDeadlineExceeded()) {
+            throw deadlineExceededError()
+        }
+        recordEndpointResult(endpoint, null, performance.now() - fetchStartedAt, (error as Error).message)
+        // 🆕 超时（本次调用的计时器触发，而不是调用方中止）、建连/TLS 失败分别归类
+        const failureKind = controller.signal.aborted && !options.signal?.aborted
+            ? "timeout"
+            : isConnectError(error) || isTlsError(error) ? "connect_error" : "other"
+        recordEndpointFailure(endpoint, failureKind, { message: (error as Error).message })
+        if (tls && isTlsError(error)) {
+            throw new AntigravityError(`Upstream TLS validation failed: ${(error as Error).message}`, "tls_error")
+        }
+        throw error
     } finally {
         clearTimeout(timeoutId)
     }
 }
 
+const DEFAULT_REQUEST_ID_PREFIX = "agent"
+const REQUEST_ID_PREFIX_PATTERN = /^[A-Za-z0-9_-]{1,32}$/
+
+/**
+ * 🆕 请求 ID 前缀（ANTI_API_REQUEST_ID_PREFIX，默认 "agent"，仅允许字母数字、"_" 与 "-"）
+ */
+export function getRequestIdPrefix(): string {
+    const configured = readEnv("ANTI_API_REQUEST_ID_PREFIX")
+    if (!configured) return DEFAULT_REQUEST_ID_PREFIX
+    if (!REQUEST_ID_PREFIX_PATTERN.test(configured)) {
+        consola.warn(`Invalid ANTI_API_REQUEST_ID_PREFIX "${configured.slice(0, 40)}", using "${DEFAULT_REQUEST_ID_PREFIX}"`)
+        return DEFAULT_REQUEST_ID_PREFIX
+    }
+    return configured
+}
+
+/**
+ * 上游 requestId = 前缀 + X-Request-Id，便于两端对照
+ */
+export function buildUpstreamRequestId(): string {
+    const correlationId = getRequestContext()?.requestId ?? crypto.randomUUID()
+    return `${getRequestIdPrefix()}-${correlationId}`
+}
+
 function getAntigravityModelName(userModel: string): string {
     const { isOfficialModel } = require("../routing/models")
     if (!userModel) return "gemini-3-flash"
@@ -212,6 +389,11 @@ export interface ChatResponse {
     contentBlocks: ContentBlock[]
     stopReason: string | null
     usage?: { inputTokens: number; outputTokens: number }
+    upstreamRequestId?: string
+    /** 🆕 由 ANTI_API_MODEL_FALLBACKS 中的回退模型响应 */
+    fallbackModel?: string
+    /** 🆕 上游 SSE 帧数超过 ANTI_API_MAX_SSE_FRAMES，聚合被截断 */
+    truncated?: boolean
 }
 
 function generateStableSessionId(messages: ClaudeMessage[]): string {
@@ -558,7 +740,7 @@ export function claudeToAntigravity(
         userAgent: "antigravity",
         requestType: "agent",
         project: projectId,
-        requestId: "agent-" + crypto.randomUUID(),
+        requestId: buildUpstreamRequestId(),
         request: innerRequest,
     }
 }
@@ -637,9 +819,23 @@ export function parseApiResponse(rawResponse: string): ChatResponse {
     }
 }
 
+/**
+ * 读取上游 trace id（必须在消费 body 之前调用）
+ */
+function getUpstreamRequestId(response: Response): string | undefined {
+    return response.headers.get("x-goog-request-id") || undefined
+}
+
+function formatRequestIds(requestId: string | undefined, upstreamRequestId: string | undefined): string {
+    if (!upstreamRequestId) return ""
+    return ` [request_id=${requestId || "unknown"} upstream_request_id=${upstreamRequestId}]`
+}
+
 // 429 is handled separately - it's account-specific, not endpoint-specific
+// 🆕 状态码集合由 ANTI_API_FAILOVER_STATUSES 配置
 function shouldTryNextEndpoint(statusCode: number): boolean {
-    return statusCode === 408 || statusCode === 404 || statusCode >= 500
+    // 🆕 未列入 ANTI_API_SUCCESS_STATUSES 的 2xx 同样切换端点
+    return isFailoverStatus(statusCode) || (statusCode >= 200 && statusCode < 300)
 }
 
 async function sendRequestSse(
@@ -649,23 +845,30 @@ async function sendRequestSse(
     accountId?: string,
     allowRotation: boolean = true,
     modelName?: string
-): Promise<string> {
+): Promise<{ body: string; upstreamRequestId?: string }> {
     const startTime = Date.now()
     let lastError: Error | null = null
     let lastStatusCode = 0
     let lastErrorText = ""
     let lastRetryAfterHeader: string | undefined
+    let lastUpstreamRequestId: string | undefined
     let currentAccessToken = accessToken
     let currentAccountId = accountId
     let nonQuota429Count = 0
 
     const rotationBudget = allowRotation ? Math.max(0, accountManager.count() - 1) : 0
     const maxAttempts = Math.max(MAX_RETRY_ATTEMPTS, MAX_NON_QUOTA_429_RETRIES + 1 + rotationBudget)
+    const upstreamEndpoints = resolveUpstreamEndpoints()
+    // 🆕 request 部分只序列化一次，重试/切换端点时复用
+    const envelope = new EnvelopeSerializer(antigravityRequest)
     for (let attempt = 0; attempt < maxAttempts; attempt++) {
+        checkDeadline()
         // 锁已在 handler.ts HTTP 层获取，这里不需要
-        for (const baseUrl of ANTIGRAVITY_BASE_URLS) {
-            const url = baseUrl + endpoint + "?alt=sse"
+        for (const [endpointIndex, baseUrl] of upstreamEndpoints) {
+            const url = buildUpstreamUrl(baseUrl, endpoint)
             try {
+                // 🆕 JWT 令牌声明的 project 与请求不符时本地拒绝（ANTI_API_CHECK_TOKEN_PROJECT）
+                assertTokenMatchesProject(currentAccessToken, antigravityRequest.project)
                 const response = await fetchWithTimeout(url, {
                     method: "POST",
                     headers: {
@@ -674,28 +877,79 @@ async function sendRequestSse(
                         "User-Agent": DEFAULT_USER_AGENT,
                         "Accept": "text/event-stream",
                     },
-                    body: JSON.stringify(antigravityRequest),
-                }, FETCH_TIMEOUT_MS)
+                    body: envelope.serialize(),
+                }, getEndpointTimeoutMs(baseUrl, endpointIndex, getRequestContext()?.model))
+                const upstreamRequestId = getUpstreamRequestId(response)
+                incrementCounter("upstream_responses_total", { status: response.status })
+                updateRequestContext({ endpoint: baseUrl, endpointIndex, upstreamRequestId })
+
+                if (isSuccessStatus(response.status)) {
+                    // 🆕 响应体被截断时按端点失败处理并切换，不把半截内容当作成功
+                    const body = await readCompleteBody(response)
+                    if (body === null) {
+                        incrementCounter("upstream_incomplete_responses_total")
+                        recordEndpointFailure(baseUrl, "incomplete_response", { status: response.status })
+                        consola.warn(`[AntigravityChat] Truncated ${response.status} body from ${baseUrl}${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}`)
+                        lastError = incompleteResponseError()
+                        continue
+                    }
+                    if (!body.trim()) {
+                        incrementCounter("upstream_empty_responses_total", { mode: "buffered" })
+                        consola.warn(`[AntigravityChat] Empty 200 body from ${baseUrl}${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}`)
+                        if (!shouldFailoverOnEmptyResponse()) throw emptyResponseError()
+                        lastError = emptyResponseError()
+                        continue
+                    }
+
+                    // 🆕 200 中内嵌的错误帧按上游错误处理（ANTI_API_VALIDATE_SSE）
+                    const embedded = isSseValidationEnabled() ? findEmbeddedSseError(body) : null
+                    if (embedded) {
+                        incrementCounter("upstream_embedded_errors", { status: embedded.status })
+                        consola.warn(`[AntigravityChat] Embedded error in 200 stream (${embedded.status}): ${embedded.message.slice(0, 200)}${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}`)
+                        throw new UpstreamError("antigravity", embedded.status, embedded.raw, undefined, upstreamRequestId)
+                    }
 
-                if (response.ok) {
                     if (currentAccountId) accountManager.markSuccess(currentAccountId)
+                    retryBudget.recordSuccess()
 
                     // Log 200 success with actual account used and elapsed time (green)
-                    const elapsed = ((Date.now() - startTime) / 1000).toFixed(1)
-                    const account = currentAccountId ? await accountManager.getAccountById(currentAccountId) : null
-                    const accountPart = account?.email ? ` >> ${account.email}` : (currentAccountId ? ` >> ${currentAccountId}` : "")
-                    console.log(`\x1b[32m[${formatLogTime()}] 200: from ${modelName || "unknown"} > Antigravity${accountPart} (${elapsed}s)\x1b[0m`)
+                    // 🆕 成功日志按 ANTI_API_LOG_SAMPLE_RATE 采样
+                    if (isRequestLogSampled()) {
+                        const elapsed = ((Date.now() - startTime) / 1000).toFixed(1)
+                        const account = currentAccountId ? await accountManager.getAccountById(currentAccountId) : null
+                        const accountPart = account?.email ? ` >> ${account.email}` : (currentAccountId ? ` >> ${currentAccountId}` : "")
+                        console.log(`\x1b[32m[${formatLogTime()}] 200: from ${modelName || "unknown"} > Antigravity${accountPart} (${elapsed}s)${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}${formatBillingTag()}\x1b[0m`)
+                    }
 
-                    return await response.text()
+                    return { body, upstreamRequestId }
                 }
 
                 lastStatusCode = response.status
                 lastRetryAfterHeader = response.headers.get("retry-after") || undefined
+                lastUpstreamRequestId = upstreamRequestId
                 lastErrorText = await response.text()
+                if (lastStatusCode === 429) {
+                    updateRequestContext({ retryAfterMs: parseRetryDelay(lastErrorText, lastRetryAfterHeader) })
+                }
+                if (shouldDumpStatus(lastStatusCode)) {
+                    dumpErrorEvent({
+                        event: "upstream_error",
+                        model: modelName,
+                        project: antigravityRequest.project,
+                        account: currentAccountId,
+                        endpoint: baseUrl,
+                        status: lastStatusCode,
+                        elapsedMs: Date.now() - startTime,
+                        retryAfterMs: parseRetryDelay(lastErrorText, lastRetryAfterHeader),
+                        requestId: antigravityRequest.requestId,
+                        upstreamRequestId,
+                        detail: lastErrorText,
+                    })
+                }
 
                 // 🆕 使用新的错误分类系统
                 const errorClassification = classifyError(lastStatusCode, lastErrorText, lastRetryAfterHeader)
-                consola.warn(`SSE error ${response.status} [${errorClassification.category}]`, lastErrorText.substring(0, 200))
+                consola.warn(`SSE error ${response.status} [${errorClassification.category}]${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}`, lastErrorText.substring(0, 200))
 
                 if (lastStatusCode === 429 && currentAccountId) {
                     const quotaExhausted = isQuotaExhaustedErrorText(lastErrorText)
@@ -762,12 +1016,12 @@ async function sendRequestSse(
                                 break
                             }
                         }
-                        const upstream = new UpstreamError("antigravity", 429, lastErrorText, lastRetryAfterHeader)
+                        const upstream = new UpstreamError("antigravity", 429, lastErrorText, lastRetryAfterHeader, lastUpstreamRequestId)
                             ; (upstream as any).retryable = true
                         throw upstream
                     }
                     // Non-quota 429 with no rotation path
-                    throw new UpstreamError("antigravity", 429, lastErrorText, lastRetryAfterHeader)
+                    throw new UpstreamError("antigravity", 429, lastErrorText, lastRetryAfterHeader, lastUpstreamRequestId)
                 }
 
                 // 🆕 401 处理：刷新 token 并重试
@@ -794,7 +1048,7 @@ async function sendRequestSse(
                             break
                         }
                     }
-                    throw new UpstreamError("antigravity", 401, lastErrorText, lastRetryAfterHeader)
+                    throw new UpstreamError("antigravity", 401, lastErrorText, lastRetryAfterHeader, lastUpstreamRequestId)
                 }
 
                 if (shouldTryNextEndpoint(lastStatusCode)) {
@@ -809,10 +1063,11 @@ async function sendRequestSse(
                 if (response.status === 404) {
                     consola.error(`[AntigravityChat] 404 Details - URL: ${url}, Project: ${antigravityRequest.project}`)
                 }
-                throw new UpstreamError("antigravity", response.status, lastErrorText, lastRetryAfterHeader)
+                throw new UpstreamError("antigravity", response.status, lastErrorText, lastRetryAfterHeader, lastUpstreamRequestId)
             } catch (e) {
                 // 🆕 UpstreamError (包括 429) 立即重新抛出，不继续尝试
                 if (e instanceof UpstreamError) throw e
+                if (e instanceof AntigravityError) throw e
                 lastError = e as Error
                 continue
             }
@@ -826,17 +1081,22 @@ async function sendRequestSse(
             continue
         }
 
-        if (lastStatusCode > 0) {
+        // 🆕 整轮重试受 ANTI_API_RETRIES 与重试预算约束；切换到下一个端点不消耗预算
+        if (lastStatusCode > 0 && areRetriesEnabled() && attempt < MAX_RETRY_ATTEMPTS - 1) {
+            if (!retryBudget.tryConsume()) {
+                consola.warn("[AntigravityChat] Retry budget exhausted, not retrying")
+                break
+            }
             const strategy = determineRetryStrategy(lastStatusCode, lastErrorText, lastRetryAfterHeader)
             await applyRetryDelay(strategy, attempt)
-            if (attempt < MAX_RETRY_ATTEMPTS - 1) continue
+            continue
         }
         break
     }
-    if (lastStatusCode > 0) {
-        throw new UpstreamError("antigravity", lastStatusCode, lastErrorText, lastRetryAfterHeader)
-    }
-    throw lastError || new Error("All endpoints failed")
+    // 🆕 所有端点失败时按原因汇总（503 + error_code + endpoints）
+    throw toAllEndpointsFailedError(lastStatusCode > 0
+        ? new UpstreamError("antigravity", lastStatusCode, lastErrorText, lastRetryAfterHeader, lastUpstreamRequestId)
+        : lastError || new Error("All endpoints failed"))
 }
 
 /**
@@ -853,9 +1113,10 @@ async function* sendRequestSseStreaming(
     modelName?: string
 ): AsyncGenerator<string, void, unknown> {
     const startTime = Date.now()
-    const IDLE_TIMEOUT_MS = 900000  // 超过 15 分钟无数据则中断
-    const IDLE_CHECK_MS = 5000
+    const idleTimeoutMs = getStreamIdleTimeoutMs()
+    const idleCheckMs = Math.min(5000, Math.max(100, Math.floor(idleTimeoutMs / 5)))
     let lastError: UpstreamError | null = null
+    let sawEmptyResponse = false
     let currentAccessToken = accessToken
     let currentAccountId = accountId
     let nonQuota429Count = 0
@@ -866,10 +1127,14 @@ async function* sendRequestSseStreaming(
     let lastErrorText = ""
     let lastRetryAfterHeader: string | undefined
 
+    const upstreamEndpoints = resolveUpstreamEndpoints()
+    // 🆕 request 部分只序列化一次，重试/切换端点时复用
+    const envelope = new EnvelopeSerializer(antigravityRequest)
     for (let attempt = 0; attempt < maxAttempts; attempt++) {
+        checkDeadline()
         let retryAttempt = false
-        for (const baseUrl of ANTIGRAVITY_BASE_URLS) {
-            const url = baseUrl + endpoint + "?alt=sse"
+        for (const [endpointIndex, baseUrl] of upstreamEndpoints) {
+            const url = buildUpstreamUrl(baseUrl, endpoint)
 
             let hasYielded = false
             let lastChunkAt = Date.now()
@@ -877,6 +1142,8 @@ async function* sendRequestSseStreaming(
             const idleController = new AbortController()
 
             try {
+                // 🆕 JWT 令牌声明的 project 与请求不符时本地拒绝（ANTI_API_CHECK_TOKEN_PROJECT）
+                assertTokenMatchesProject(currentAccessToken, antigravityRequest.project)
                 const response = await fetchWithTimeout(url, {
                     method: "POST",
                     headers: {
@@ -884,16 +1151,39 @@ async function* sendRequestSseStreaming(
                         "Authorization": "Bearer " + currentAccessToken,
                         "User-Agent": DEFAULT_USER_AGENT,
                         "Accept": "text/event-stream",
+                        // 🆕 流式请求默认不协商压缩，避免事件流被整块压缩后卡顿/错帧
+                        ...getUpstreamSseEncodingHeaders(),
                     },
-                    body: JSON.stringify(antigravityRequest),
+                    body: envelope.serialize(),
                     signal: idleController.signal,
-                }, FETCH_TIMEOUT_MS)
+                }, getEndpointTimeoutMs(baseUrl, endpointIndex, getRequestContext()?.model))
+                const upstreamRequestId = getUpstreamRequestId(response)
+                incrementCounter("upstream_responses_total", { status: response.status })
+                updateRequestContext({ endpoint: baseUrl, endpointIndex, upstreamRequestId })
 
-                if (!response.ok) {
+                if (!isSuccessStatus(response.status)) {
                     const errorText = await response.text()
                     lastStatusCode = response.status
                     lastErrorText = errorText
                     lastRetryAfterHeader = response.headers.get("retry-after") || undefined
+                    if (lastStatusCode === 429) {
+                        updateRequestContext({ retryAfterMs: parseRetryDelay(errorText, lastRetryAfterHeader) })
+                    }
+                    if (shouldDumpStatus(lastStatusCode)) {
+                        dumpErrorEvent({
+                            event: "upstream_error",
+                            model: modelName,
+                            project: antigravityRequest.project,
+                            account: currentAccountId,
+                            endpoint: baseUrl,
+                            status: lastStatusCode,
+                            elapsedMs: Date.now() - startTime,
+                            retryAfterMs: parseRetryDelay(errorText, lastRetryAfterHeader),
+                            requestId: antigravityRequest.requestId,
+                            upstreamRequestId,
+                            detail: errorText,
+                        })
+                    }
                     if (response.status === 429 && currentAccountId) {
                         const quotaExhausted = isQuotaExhaustedErrorText(errorText)
                         if (quotaExhausted) {
@@ -901,7 +1191,7 @@ async function* sendRequestSseStreaming(
                             if (limitResult?.reason === "quota_exhausted") {
                                 accountManager.moveToEndOfQueue(currentAccountId)
                             }
-                            lastError = new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined)
+                            lastError = new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined, upstreamRequestId)
                             throw lastError
                         }
 
@@ -925,7 +1215,7 @@ async function* sendRequestSseStreaming(
                         if (nonQuota429Count < MAX_NON_QUOTA_429_RETRIES) {
                             await new Promise(resolve => setTimeout(resolve, waitMs))
                             nonQuota429Count += 1
-                            lastError = new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined)
+                            lastError = new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined, upstreamRequestId)
                             retryAttempt = true
                             break
                         }
@@ -942,37 +1232,84 @@ async function* sendRequestSseStreaming(
                                 break
                             }
                         }
-                        const upstream = new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined)
+                        const upstream = new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined, upstreamRequestId)
                             ; (upstream as any).retryable = true
                         throw upstream
                     }
                     if (shouldTryNextEndpoint(response.status)) {
-                        lastError = new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined)
+                        lastError = new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined, upstreamRequestId)
                         if (response.status === 404) {
                             console.error(`[AntigravityChat Streaming] 404 Details - URL: ${url}, Project: ${antigravityRequest.project}`)
                         }
                         retryAttempt = true
                         continue
                     }
-                    consola.error(`[AntigravityChat SSE] Upstream error ${response.status}: ${errorText}`)
-                    throw new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined)
+                    consola.error(`[AntigravityChat SSE] Upstream error ${response.status}${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}: ${errorText}`)
+                    throw new UpstreamError("antigravity", response.status, errorText, response.headers.get("retry-after") || undefined, upstreamRequestId)
                 }
 
                 if (currentAccountId) accountManager.markSuccess(currentAccountId)
+                retryBudget.recordSuccess()
+
+                // 🆕 上游没有按 SSE 返回时整体缓冲，再转换为 SSE 帧交给下面的解析流程
+                let responseBody = response.body
+                const contentType = response.headers.get("content-type")
+                if (!isEventStreamContentType(contentType)) {
+                    incrementCounter("stream_downgrade", { endpoint: new URL(baseUrl).host })
+                    consola.warn(`[SSE Streaming] ${baseUrl} answered with ${contentType} instead of text/event-stream, buffering response${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}`)
+                    // 缓冲期间同样受空闲超时与端到端截止时间约束，避免上游只发响应头后停住
+                    const bufferIdleMs = idleTimeoutMs > 0 ? Math.max(1, clampToDeadline(idleTimeoutMs)) : 0
+                    const buffered = await readTextWithIdleTimeout(response, bufferIdleMs, () => {
+                        idleTimedOut = true
+                        consola.warn(`[SSE Streaming] No data from ${baseUrl} for ${Math.round(bufferIdleMs / 1000)}s while buffering, aborting${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}`)
+                        incrementCounter("stream_idle_timeouts", { started: "false" })
+                        idleController.abort()
+                    })
+                    responseBody = new Response(bufferedBodyToSse(buffered)).body
+                }
 
-                const reader = response.body?.getReader()
+                const reader = responseBody?.getReader()
                 if (!reader) {
                     throw new Error("Response body is not readable")
                 }
 
                 const decoder = new TextDecoder()
-                let buffer = ""
-                const idleTimer = setInterval(() => {
-                    if (Date.now() - lastChunkAt > IDLE_TIMEOUT_MS) {
+                // 🆕 单帧大小上限（ANTI_API_MAX_SSE_FRAME_BYTES），避免无界缓冲
+                const maxFrameBytes = getMaxSseFrameBytes()
+                const frames = new SseFrameSplitter(maxFrameBytes, (bytes) => {
+                    incrementCounter("sse_frames_oversized", { mode: getOversizedSseFrameMode() })
+                    consola.warn(`[SSE Streaming] Frame of ${bytes} bytes exceeds ANTI_API_MAX_SSE_FRAME_BYTES (${maxFrameBytes})`)
+                    if (getOversizedSseFrameMode() === "error") {
+                        throw new AntigravityError(`Upstream SSE frame exceeds ${maxFrameBytes} bytes`, "sse_frame_too_large", 502)
+                    }
+                })
+                const tee = openStreamTee(antigravityRequest.requestId || "unknown")
+                // 每收到一块数据即重置（lastChunkAt），超过空闲上限则中断读取
+                const idleTimer = idleTimeoutMs > 0
+                    ? setInterval(() => {
+                        if (idleTimedOut || Date.now() - lastChunkAt <= idleTimeoutMs) return
                         idleTimedOut = true
+                        incrementCounter("stream_idle_timeouts", { started: String(hasYielded) })
+                        consola.warn(`[SSE Streaming] No data from ${baseUrl} for ${Math.round(idleTimeoutMs / 1000)}s, aborting stream${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}`)
                         idleController.abort()
-                    }
-                }, IDLE_CHECK_MS)
+                    }, idleCheckMs)
+                    : undefined
+                // 🆕 首个数据帧超时（心跳/注释不算数据）
+                const connectedAt = Date.now()
+                const ttftTimeoutMs = getTtftTimeoutMs()
+                let ttftTimedOut = false
+                let ttftTimer: ReturnType<typeof setTimeout> | undefined = ttftTimeoutMs > 0
+                    ? setTimeout(() => {
+                        ttftTimedOut = true
+                        idleController.abort()
+                    }, ttftTimeoutMs)
+                    : undefined
+                const markFirstData = () => {
+                    if (hasYielded) return
+                    if (ttftTimer) clearTimeout(ttftTimer)
+                    ttftTimer = undefined
+                    observeHistogram("ttft_seconds", (Date.now() - connectedAt) / 1000, { model: modelName })
+                }
 
                 try {
                     while (true) {
@@ -980,8 +1317,12 @@ async function* sendRequestSseStreaming(
                         try {
                             result = await reader.read()
                         } catch (readError) {
+                            if (ttftTimedOut) {
+                                throw new AntigravityError(`No data within ${ttftTimeoutMs}ms of connecting`, "ttft_timeout", 504)
+                            }
                             if (idleTimedOut) {
-                                throw new Error("Stream idle timeout")
+                                // 已输出内容时无法切换端点，以错误帧结束
+                                throw hasYielded ? streamIdleTimeoutError(idleTimeoutMs) : new Error("Stream idle timeout")
                             }
                             consola.warn("[SSE Streaming] Read error:", readError)
                             throw readError
@@ -992,12 +1333,10 @@ async function* sendRequestSseStreaming(
 
                         if (value && value.length > 0) {
                             lastChunkAt = Date.now()
+                            tee?.write(value)
                         }
-                        buffer += decoder.decode(value, { stream: true })
-
                         // Parse SSE by event blocks to handle multi-line data payloads
-                        const events = buffer.split(/\r?\n\r?\n/)
-                        buffer = events.pop() || ""  // Keep incomplete tail
+                        const events = frames.push(decoder.decode(value, { stream: true }))
 
                         for (const event of events) {
                             const data = extractSseEventData(event)
@@ -1006,38 +1345,51 @@ async function* sendRequestSseStreaming(
                             const trimmed = data.trim()
                             if (!trimmed || trimmed === "[DONE]") continue
 
+                            let parsed: unknown
                             try {
-                                const parsed = JSON.parse(trimmed)
-                                yield JSON.stringify(parsed)
-                                hasYielded = true
+                                parsed = JSON.parse(trimmed)
                             } catch {
                                 // Ignore non-JSON payloads
+                                continue
                             }
+                            markFirstData()
+                            yield JSON.stringify(parsed)
+                            hasYielded = true
                         }
                     }
 
                     // Handle any leftover event data
-                    const tailData = extractSseEventData(buffer)
+                    const tailData = extractSseEventData(frames.flush())
                     if (tailData) {
                         const trimmed = tailData.trim()
                         if (trimmed && trimmed !== "[DONE]") {
+                            let parsed: unknown
+                            let isJson = true
                             try {
-                                const parsed = JSON.parse(trimmed)
+                                parsed = JSON.parse(trimmed)
+                            } catch {
+                                isJson = false
+                            }
+                            if (isJson) {
+                                markFirstData()
                                 yield JSON.stringify(parsed)
                                 hasYielded = true
-                            } catch {
-                                // ignore
                             }
                         }
                     }
 
                     // 如果没有产出任何数据，抛出错误
                     if (!hasYielded) {
+                        incrementCounter("upstream_empty_responses_total", { mode: "stream" })
+                        sawEmptyResponse = true
+                        if (!shouldFailoverOnEmptyResponse()) throw emptyResponseError()
                         throw new Error("Stream completed without yielding any data")
                     }
 
                 } finally {
-                    clearInterval(idleTimer)
+                    if (idleTimer) clearInterval(idleTimer)
+                    if (ttftTimer) clearTimeout(ttftTimer)
+                    tee?.close()
                     try {
                         reader.releaseLock()
                     } catch {
@@ -1046,10 +1398,13 @@ async function* sendRequestSseStreaming(
                 }
 
                 // 成功完成 - 在 return 之前记录日志
-                const elapsed = ((Date.now() - startTime) / 1000).toFixed(1)
-                const account = currentAccountId ? await accountManager.getAccountById(currentAccountId) : null
-                const accountPart = account?.email ? ` >> ${account.email}` : (currentAccountId ? ` >> ${currentAccountId}` : "")
-                console.log(`\x1b[32m[${formatLogTime()}] 200: from ${modelName || "unknown"} > Antigravity${accountPart} (${elapsed}s)\x1b[0m`)
+                // 🆕 成功日志按 ANTI_API_LOG_SAMPLE_RATE 采样
+                if (isRequestLogSampled()) {
+                    const elapsed = ((Date.now() - startTime) / 1000).toFixed(1)
+                    const account = currentAccountId ? await accountManager.getAccountById(currentAccountId) : null
+                    const accountPart = account?.email ? ` >> ${account.email}` : (currentAccountId ? ` >> ${currentAccountId}` : "")
+                    console.log(`\x1b[32m[${formatLogTime()}] 200: from ${modelName || "unknown"} > Antigravity${accountPart} (${elapsed}s)${formatRequestIds(antigravityRequest.requestId, upstreamRequestId)}${formatBillingTag()}\x1b[0m`)
+                }
                 return
 
             } catch (error) {
@@ -1060,6 +1415,8 @@ async function* sendRequestSseStreaming(
                     throw error
                 }
                 if (hasYielded) throw error
+                if (error instanceof AntigravityError) throw error
+                if (idleTimedOut) recordEndpointFailure(new URL(baseUrl).origin, "timeout", { message: "Stream idle timeout" })
                 consola.warn("[SSE Streaming] Error on", baseUrl, error)
                 continue
             }
@@ -1073,9 +1430,15 @@ async function* sendRequestSseStreaming(
         }
 
         if (retryAttempt && lastStatusCode > 0) {
+            if (attempt >= maxAttempts - 1) break
+            // 🆕 5xx 整轮重试受 ANTI_API_RETRIES 与重试预算约束；切换端点与 429 等待/换号不消耗预算
+            if (lastStatusCode !== 429 && (!areRetriesEnabled() || !retryBudget.tryConsume())) {
+                consola.warn("[AntigravityChat SSE] Retries disabled or retry budget exhausted, not retrying")
+                break
+            }
             const strategy = determineRetryStrategy(lastStatusCode, lastErrorText, lastRetryAfterHeader)
             await applyRetryDelay(strategy, attempt)
-            if (attempt < maxAttempts - 1) continue
+            continue
         }
 
         if (!retryAttempt) {
@@ -1083,17 +1446,25 @@ async function* sendRequestSseStreaming(
         }
     }
 
+    // 🆕 所有端点失败时按原因汇总（503 + error_code + endpoints）
     if (lastError) {
-        throw lastError
+        throw toAllEndpointsFailedError(lastError)
     }
-    throw new Error("All endpoints failed")
+    if (sawEmptyResponse) throw emptyResponseError()
+    throw toAllEndpointsFailedError(new Error("All endpoints failed"))
 }
 
-function collectSseChunks(rawSse: string): any[] {
+function collectSseChunks(rawSse: string): { chunks: any[]; truncated: boolean } {
     const chunks: any[] = []
 
     // Parse SSE by event blocks to handle multi-line data payloads
-    const events = rawSse.split(/\r?\n\r?\n/)
+    // 🆕 帧数上限 ANTI_API_MAX_SSE_FRAMES，超出后停止聚合并标记 truncated
+    const { frames: events, truncated } = splitSseFrames(rawSse, getMaxSseFrames())
+    observeHistogram("sse_decode_frames", events.length)
+    if (truncated) {
+        incrementCounter("sse_frame_limit_truncations")
+        consola.warn(`[AntigravityChat] Upstream SSE exceeded ${events.length} frames, aggregation truncated`)
+    }
     for (const event of events) {
         const data = extractSseEventData(event)
         if (!data) continue
@@ -1107,20 +1478,7 @@ function collectSseChunks(rawSse: string): any[] {
         }
     }
 
-    return chunks
-}
-
-function extractSseEventData(event: string): string | null {
-    const dataLines: string[] = []
-    const lines = event.split(/\r?\n/)
-    for (const line of lines) {
-        if (!line.startsWith("data:")) continue
-        let value = line.slice(5)
-        if (value.startsWith(" ")) value = value.slice(1)
-        dataLines.push(value)
-    }
-    if (dataLines.length === 0) return null
-    return dataLines.join("\n")
+    return { chunks, truncated }
 }
 
 export async function createChatCompletion(request: ChatRequest): Promise<ChatResponse> {
@@ -1147,7 +1505,8 @@ export async function createChatCompletionWithOptions(
         projectId = account.projectId
         accountEmail = account.email
     } else {
-        const account = await accountManager.getNextAvailableAccount()
+        // 🆕 全部账号冷却时按 ANTI_API_ALL_COOLING_MODE 快速失败或有限等待
+        const account = await accountManager.getNextAvailableAccount() ?? await resolveAllCoolingAccount()
         if (account) {
             accessToken = account.accessToken
             accountId = account.accountId
@@ -1164,7 +1523,7 @@ export async function createChatCompletionWithOptions(
     setRequestLogContext({ model: request.model, provider: "antigravity", account: accountEmail })
 
     if (accountId) {
-        releaseAccountLock = await accountManager.acquireAccountLock(accountId)
+        releaseAccountLock = await withDeadline(accountManager.acquireAccountLock(accountId), release => release())
     }
 
     try {
@@ -1185,8 +1544,10 @@ export async function createChatCompletionWithOptions(
         )
 
         if (projectId) antigravityRequest.project = projectId
+        // 🆕 字段允许/拒绝/删除策略（ANTI_API_REQUEST_FIELD_*）
+        applyRequestFieldPolicy(antigravityRequest.request)
 
-        const rawSse = await sendRequestSse(
+        const { body: rawSse, upstreamRequestId } = await sendRequestSse(
             STREAM_ENDPOINT,
             antigravityRequest,
             accessToken,
@@ -1194,10 +1555,12 @@ export async function createChatCompletionWithOptions(
             options.allowRotation ?? true,
             request.model
         )
-        const sseChunks = collectSseChunks(rawSse)
+        const { chunks: sseChunks, truncated } = collectSseChunks(rawSse)
         const rawResponse = sseChunks.length > 0 ? JSON.stringify(sseChunks) : rawSse
 
         const result = parseApiResponse(rawResponse)
+        if (upstreamRequestId) result.upstreamRequestId = upstreamRequestId
+        if (truncated) result.truncated = true
 
         // Record usage (fire-and-forget) - use actual native model ID
         const inputTokens = result.usage?.inputTokens || 0
@@ -1239,7 +1602,8 @@ export async function* createChatCompletionStreamWithOptions(
         projectId = account.projectId
         accountEmail = account.email
     } else {
-        const account = await accountManager.getNextAvailableAccount()
+        // 🆕 全部账号冷却时按 ANTI_API_ALL_COOLING_MODE 快速失败或有限等待
+        const account = await accountManager.getNextAvailableAccount() ?? await resolveAllCoolingAccount()
         if (account) {
             accessToken = account.accessToken
             accountId = account.accountId
@@ -1253,7 +1617,7 @@ export async function* createChatCompletionStreamWithOptions(
     }
 
     if (accountId) {
-        releaseAccountLock = await accountManager.acquireAccountLock(accountId)
+        releaseAccountLock = await withDeadline(accountManager.acquireAccountLock(accountId), release => release())
     }
 
     try {
@@ -1274,6 +1638,8 @@ export async function* createChatCompletionStreamWithOptions(
         )
 
         if (projectId && projectId !== "unknown") antigravityRequest.project = projectId
+        // 🆕 字段允许/拒绝/删除策略（ANTI_API_REQUEST_FIELD_*）
+        applyRequestFieldPolicy(antigravityRequest.request)
 
         const sseStream = sendRequestSseStreaming(
             STREAM_ENDPOINT,
@@ -0,0 +1,56 @@
+/**
+ * 🆕 所有账号都在冷却时的处理策略
+ * - ANTI_API_ALL_COOLING_MODE = fail | wait（默认 fail）
+ *   fail: 立即返回 429 all_accounts_cooling，并带上最短剩余冷却时间
+ *   wait: 最多等待 ANTI_API_ALL_COOLING_MAX_WAIT_MS（默认 10000）直到有账号解除冷却
+ */
+
+import consola from "consola"
+import { envInt, envString } from "~/lib/env"
+import { AntigravityError } from "~/lib/error"
+import { getRemainingBudgetMs } from "~/lib/deadline"
+import { setGauge } from "~/lib/metrics"
+import { accountManager } from "./account-manager"
+
+type Account = NonNullable<Awaited<ReturnType<typeof accountManager.getNextAvailableAccount>>>
+
+export function getAllCoolingMode(): "fail" | "wait" {
+    return envString("ANTI_API_ALL_COOLING_MODE", "fail").toLowerCase() === "wait" ? "wait" : "fail"
+}
+
+export function allAccountsCoolingError(minRemainingMs: number, cooling: number): AntigravityError {
+    const retryAfterMs = Math.max(0, Math.ceil(minRemainingMs))
+    return new AntigravityError(
+        `All ${cooling} account(s) are cooling down; retry in ${Math.ceil(retryAfterMs / 1000)}s`,
+        "all_accounts_cooling",
+        429,
+        retryAfterMs,
+    )
+}
+
+/**
+ * 在 getNextAvailableAccount 返回 null 后调用
+ * 返回 null 表示并非全部冷却（例如没有账号），由调用方沿用原有回退逻辑
+ */
+export async function resolveAllCoolingAccount(): Promise<Account | null> {
+    let summary = accountManager.getCoolingSummary()
+    setGauge("accounts_cooling", summary.cooling)
+    if (summary.minRemainingMs === null) return null
+
+    if (getAllCoolingMode() === "wait") {
+        const budget = getRemainingBudgetMs()
+        const maxWaitMs = Math.min(Math.max(0, envInt("ANTI_API_ALL_COOLING_MAX_WAIT_MS", 10000)), budget ?? Number.POSITIVE_INFINITY)
+        const waitUntil = Date.now() + maxWaitMs
+        while (summary.minRemainingMs !== null && Date.now() + summary.minRemainingMs <= waitUntil) {
+            consola.warn(`All accounts cooling, waiting ${Math.ceil(summary.minRemainingMs / 1000)}s for one to free`)
+            await new Promise(resolve => setTimeout(resolve, summary.minRemainingMs! + 10))
+            const account = await accountManager.getNextAvailableAccount()
+            if (account) return account
+            summary = accountManager.getCoolingSummary()
+        }
+        setGauge("accounts_cooling", summary.cooling)
+        if (summary.minRemainingMs === null) return accountManager.getNextAvailableAccount()
+    }
+
+    throw allAccountsCoolingError(summary.minRemainingMs, summary.cooling)
+}
@@ -0,0 +1,35 @@
+/**
+ * 🆕 上游请求体（envelope）序列化缓存
+ * 同一请求在重试、端点切换、账号轮换时会多次发送，原先每次都对整个 envelope 调用 JSON.stringify；
+ * 其中体积最大的 `request`（对话历史、工具定义）在发送过程中不会变化，只有 project 等顶层字段可能被改写
+ * 这里只序列化一次 `request`，每次发送时按当前顶层字段拼接，输出与 JSON.stringify(envelope) 逐字节一致
+ * 约定：创建后不得原地修改 envelope.request 的内容（整体替换会自动重新序列化）
+ * 基准测试：bun run bench:envelope
+ */
+
+export class EnvelopeSerializer {
+    private cachedRequest: unknown
+    private cachedRequestJson: string | undefined
+
+    constructor(private readonly envelope: Record<string, unknown>) { }
+
+    private requestJson(): string | undefined {
+        const request = this.envelope.request
+        if (request !== this.cachedRequest || this.cachedRequestJson === undefined) {
+            this.cachedRequest = request
+            this.cachedRequestJson = JSON.stringify(request)
+        }
+        return this.cachedRequestJson
+    }
+
+    serialize(): string {
+        const parts: string[] = []
+        for (const key of Object.keys(this.envelope)) {
+            const json = key === "request" ? this.requestJson() : JSON.stringify(this.envelope[key])
+            // 与 JSON.stringify 一致：undefined / 函数值的字段直接省略
+            if (json === undefined) continue
+            parts.push(`${JSON.stringify(key)}:${json}`)
+        }
+        return `{${parts.join(",")}}`
+    }
+}
@@ -18,6 +18,7 @@ import {
 } from "./oauth"
 import { generateMockProjectId } from "./project-id"
 import { ensureDataDir, getDataDir, getLegacyProjectDataDir } from "~/lib/data-dir"
+import { envString } from "~/lib/env"
 import { accountManager } from "./account-manager"
 
 const AUTH_FILE = join(getDataDir(), "auth.json")
@@ -168,7 +169,7 @@ export async function startAntigravityDeviceFlow(): Promise<{ authUrl: string; s
 
     // 2. 生成授权 URL 和会话 ID
     const sessionId = generateState() // 使用 state 作为 sessionId
-    const redirectUri = process.env.ANTI_API_OAUTH_REDIRECT_URL || `http://localhost:${port}/oauth-callback`
+    const redirectUri = envString("ANTI_API_OAUTH_REDIRECT_URL") || `http://localhost:${port}/oauth-callback`
     const authUrl = generateAuthURL(redirectUri, sessionId)
     consola.debug(`[Auth] Session ${sessionId}: Auth URL created: ${authUrl}`)
 
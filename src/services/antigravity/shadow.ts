@@ -0,0 +1,82 @@
+/**
+ * 🆕 影子流量（金丝雀）
+ * 按 ANTI_API_SHADOW_PCT（0-100）比例把请求复制到 ANTI_API_SHADOW_ENDPOINT，
+ * 丢弃影子响应，仅记录与主请求的状态码差异；不经过限流，也不阻塞客户端响应
+ * 🆕 影子请求有独立并发上限 ANTI_API_SHADOW_MAX_CONCURRENCY（默认 4），满时直接跳过（不排队），
+ * 且不复用主请求的 keep-alive 连接
+ */
+
+import consola from "consola"
+import { envInt, envString, readEnv } from "~/lib/env"
+import { incrementCounter } from "~/lib/metrics"
+import { Semaphore } from "~/lib/semaphore"
+
+const SHADOW_TIMEOUT_MS = 60000
+
+export function getShadowMaxConcurrency(): number {
+    return Math.max(1, envInt("ANTI_API_SHADOW_MAX_CONCURRENCY", 4))
+}
+
+const shadowSemaphore = new Semaphore(getShadowMaxConcurrency())
+
+export function getShadowEndpoint(): string | undefined {
+    return readEnv("ANTI_API_SHADOW_ENDPOINT")?.replace(/\/+$/, "")
+}
+
+export function getShadowPercent(): number {
+    const parsed = Number.parseFloat(envString("ANTI_API_SHADOW_PCT", "0"))
+    if (!Number.isFinite(parsed)) return 0
+    return Math.min(100, Math.max(0, parsed))
+}
+
+export function shouldShadow(random: number = Math.random()): boolean {
+    const percent = getShadowPercent()
+    return !!getShadowEndpoint() && percent > 0 && random * 100 < percent
+}
+
+/**
+ * 把主请求 URL 的 origin 换成影子端点（保留路径与查询串）
+ */
+export function buildShadowUrl(primaryUrl: string, shadowEndpoint: string): string {
+    const primary = new URL(primaryUrl)
+    return shadowEndpoint + primary.pathname + primary.search
+}
+
+/**
+ * 异步发送影子请求；调用方不等待
+ */
+export function mirrorToShadow(primaryUrl: string, init: RequestInit, primaryStatus: number): void {
+    const shadowEndpoint = getShadowEndpoint()
+    if (!shadowEndpoint) return
+    const shadowUrl = buildShadowUrl(primaryUrl, shadowEndpoint)
+
+    const limit = getShadowMaxConcurrency()
+    if (shadowSemaphore.capacity !== limit) shadowSemaphore.setLimit(limit)
+    const release = shadowSemaphore.tryAcquire()
+    if (!release) {
+        incrementCounter("shadow_requests_skipped", { reason: "concurrency" })
+        return
+    }
+
+    void (async () => {
+        try {
+            // keepalive: false 使用独立连接，不占用主请求的连接池
+            const response = await fetch(shadowUrl, { ...init, keepalive: false, signal: AbortSignal.timeout(SHADOW_TIMEOUT_MS) })
+            await response.body?.cancel().catch(() => { })
+            const match = response.status === primaryStatus
+            incrementCounter("shadow_requests_total", { match: match ? "true" : "false" })
+            if (!match) {
+                consola.warn(`[Shadow] Status divergence: primary=${primaryStatus} shadow=${response.status} (${shadowEndpoint})`)
+            }
+        } catch (error) {
+            incrementCounter("shadow_requests_total", { match: "error" })
+            consola.warn(`[Shadow] Request to ${shadowEndpoint} failed: ${(error as Error).message}`)
+        } finally {
+            release()
+        }
+    })()
+}
+
+export function getShadowInFlight(): number {
+    return shadowSemaphore.inUse
+}
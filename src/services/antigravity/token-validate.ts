@@ -0,0 +1,82 @@
+/**
+ * 🆕 Token 预校验：调用 loadCodeAssist（不消耗生成配额）判断 access token 是否可用
+ * 与生成流量分开限流：ANTI_API_VALIDATE_TOKEN_PER_MIN（默认 30 次/分钟）
+ */
+
+import { envInt } from "~/lib/env"
+import { fetchInsecureJson, OAUTH_CONFIG } from "./oauth"
+import { DEFAULT_ANTIGRAVITY_USER_AGENT } from "./constants"
+
+export type TokenStatus = "valid" | "expired" | "invalid" | "forbidden" | "rate_limited" | "error"
+
+export interface TokenValidationResult {
+    valid: boolean
+    status: TokenStatus
+    upstream_status: number
+    project?: string | null
+    /** 传入的 project 与 token 所属项目不一致 */
+    project_mismatch?: boolean
+    message?: string
+}
+
+/**
+ * 按上游状态码与错误体分类
+ */
+export function classifyTokenCheck(status: number, body: string): TokenStatus {
+    if (status >= 200 && status < 300) return "valid"
+    if (status === 429) return "rate_limited"
+    if (status === 401) {
+        return /expired|ACCESS_TOKEN_EXPIRED/i.test(body) ? "expired" : "invalid"
+    }
+    if (status === 403) return "forbidden"
+    if (status === 400 && /invalid.*(token|credential)/i.test(body)) return "invalid"
+    return "error"
+}
+
+const WINDOW_MS = 60_000
+const recentChecks: number[] = []
+
+/**
+ * 滑动窗口限流；超出时返回 false
+ */
+export function tryConsumeValidationSlot(now: number = Date.now()): boolean {
+    const limit = envInt("ANTI_API_VALIDATE_TOKEN_PER_MIN", 30)
+    while (recentChecks.length > 0 && recentChecks[0] <= now - WINDOW_MS) recentChecks.shift()
+    if (limit > 0 && recentChecks.length >= limit) return false
+    recentChecks.push(now)
+    return true
+}
+
+export function resetValidationSlots(): void {
+    recentChecks.length = 0
+}
+
+export async function validateAccessToken(accessToken: string, project?: string): Promise<TokenValidationResult> {
+    const response = await fetchInsecureJson(OAUTH_CONFIG.projectUrl, {
+        method: "POST",
+        headers: {
+            Authorization: `Bearer ${accessToken}`,
+            "Content-Type": "application/json",
+            "User-Agent": DEFAULT_ANTIGRAVITY_USER_AGENT,
+        },
+        body: JSON.stringify({ metadata: { ideType: "ANTIGRAVITY" } }),
+    })
+    const status = classifyTokenCheck(response.status, response.text)
+    if (status !== "valid") {
+        return {
+            valid: false,
+            status,
+            upstream_status: response.status,
+            message: response.data?.error?.message || response.text.slice(0, 200) || undefined,
+        }
+    }
+
+    const tokenProject = (response.data as { cloudaicompanionProject?: string } | null)?.cloudaicompanionProject || null
+    return {
+        valid: true,
+        status,
+        upstream_status: response.status,
+        project: tokenProject,
+        ...(project && tokenProject ? { project_mismatch: project !== tokenProject } : {}),
+    }
+}
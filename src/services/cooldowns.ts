@@ -0,0 +1,63 @@
+/**
+ * 🆕 冷却状态汇总（账号级、模型级、路由层）
+ * 对外只暴露账号 ID 的哈希（key = hash 或 hash:model），不泄露账号或 token
+ */
+
+import { shortHash } from "~/lib/error-dump"
+import { accountManager } from "./antigravity/account-manager"
+import { clearRouterRateLimits, listRouterRateLimits } from "./routing/rate-limit"
+
+export interface CooldownEntry {
+    key: string
+    scope: "account" | "model" | "router"
+    model?: string
+    remainingMs: number
+}
+
+function buildKey(accountId: string, modelId?: string): string {
+    const hash = shortHash(accountId) ?? ""
+    return modelId ? `${hash}:${modelId}` : hash
+}
+
+export function listCooldowns(): CooldownEntry[] {
+    const now = Date.now()
+    const entries: CooldownEntry[] = []
+    for (const cooldown of accountManager.listCooldowns()) {
+        entries.push({
+            key: buildKey(cooldown.accountId, cooldown.modelId),
+            scope: cooldown.modelId ? "model" : "account",
+            ...(cooldown.modelId ? { model: cooldown.modelId } : {}),
+            remainingMs: cooldown.until - now,
+        })
+    }
+    for (const limit of listRouterRateLimits()) {
+        entries.push({
+            key: buildKey(limit.accountId, limit.modelId),
+            scope: "router",
+            ...(limit.modelId ? { model: limit.modelId } : {}),
+            remainingMs: limit.until - now,
+        })
+    }
+    return entries.sort((a, b) => b.remainingMs - a.remainingMs)
+}
+
+/**
+ * 按 key 清除（hash 清除该账号全部冷却，hash:model 只清除该模型）；不传 key 清除全部
+ * 返回清除的条目数，key 未匹配任何账号时返回 null
+ */
+export function clearCooldowns(key?: string): number | null {
+    if (!key) {
+        return accountManager.clearCooldowns() + clearRouterRateLimits()
+    }
+    const separator = key.indexOf(":")
+    const hash = separator === -1 ? key : key.slice(0, separator)
+    const modelId = separator === -1 ? undefined : key.slice(separator + 1)
+
+    const accountIds = new Set([
+        ...accountManager.listCooldowns().map(cooldown => cooldown.accountId),
+        ...listRouterRateLimits().map(limit => limit.accountId),
+    ])
+    const accountId = Array.from(accountIds).find(id => shortHash(id) === hash)
+    if (!accountId) return null
+    return accountManager.clearCooldowns(accountId, modelId) + clearRouterRateLimits(accountId, modelId)
+}
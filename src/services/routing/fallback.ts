@@ -0,0 +1,29 @@
+/**
+ * 🆕 模型回退映射：ANTI_API_MODEL_FALLBACKS="primary=fallback;primary2=fallback2"
+ * 仅在上游返回"模型不存在"的 400 时使用，且只回退一次
+ */
+
+import { readEnv } from "~/lib/env"
+
+let cachedRaw: string | undefined
+let cachedMap = new Map<string, string>()
+
+export function parseModelFallbacks(raw: string | undefined): Map<string, string> {
+    const map = new Map<string, string>()
+    if (!raw) return map
+    for (const pair of raw.split(/[;,]/)) {
+        const [primary, fallback] = pair.split("=").map(part => (part || "").trim())
+        if (!primary || !fallback || primary === fallback) continue
+        map.set(primary, fallback)
+    }
+    return map
+}
+
+export function getModelFallback(model: string): string | undefined {
+    const raw = readEnv("ANTI_API_MODEL_FALLBACKS")
+    if (raw !== cachedRaw) {
+        cachedRaw = raw
+        cachedMap = parseModelFallbacks(raw)
+    }
+    return cachedMap.get(model)
+}
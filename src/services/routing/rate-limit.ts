@@ -79,3 +79,32 @@ export function clearRouterRateLimit(provider: string, accountId: string, modelI
     const key = getRateLimitKey(provider, accountId, modelId)
     routerRateLimits.delete(key)
 }
+
+/**
+ * 🆕 列出仍在生效的路由层限流（供管理接口使用）
+ */
+export function listRouterRateLimits(): Array<{ provider: string; accountId: string; modelId?: string; until: number }> {
+    const now = Date.now()
+    const result: Array<{ provider: string; accountId: string; modelId?: string; until: number }> = []
+    for (const [key, until] of routerRateLimits) {
+        if (until <= now) continue
+        const [provider, accountId, ...model] = key.split(":")
+        result.push({ provider, accountId, ...(model.length ? { modelId: model.join(":") } : {}), until })
+    }
+    return result
+}
+
+/**
+ * 🆕 批量清除路由层限流：不传 accountId 清除全部
+ */
+export function clearRouterRateLimits(accountId?: string, modelId?: string): number {
+    let cleared = 0
+    for (const key of Array.from(routerRateLimits.keys())) {
+        const [, keyAccountId, ...model] = key.split(":")
+        if (accountId && keyAccountId !== accountId) continue
+        if (modelId && model.join(":") !== modelId) continue
+        routerRateLimits.delete(key)
+        cleared++
+    }
+    return cleared
+}
@@ -1,5 +1,5 @@
-import type { ClaudeMessage, ClaudeTool } from "~/lib/translator"
-import { UpstreamError, summarizeUpstream429 } from "~/lib/error"
+import { buildSseFromResponse, type ClaudeMessage, type ClaudeTool } from "~/lib/translator"
+import { UpstreamError, ConcurrencyLimitError, isModelNotFoundError, summarizeUpstream429 } from "~/lib/error"
 import { createChatCompletionWithOptions, createChatCompletionStreamWithOptions, type ChatResponse } from "~/services/antigravity/chat"
 import { loadRoutingConfig, type AccountRoutingEntry, type RoutingConfig } from "./config"
 import { accountManager } from "~/services/antigravity/account-manager"
@@ -7,6 +7,8 @@ import { authStore } from "~/services/auth/store"
 import { getOfficialModelProviders, isOfficialModel } from "./models"
 import { getAccountStickyState, advanceAccountCursor, isRouterRateLimited, markRouterRateLimited, shouldFallbackOnUpstream } from "./rate-limit"
 import { setRequestLogContext, getAccountDisplay } from "~/lib/logger"
+import { dumpErrorEvent } from "~/lib/error-dump"
+import { getModelFallback } from "./fallback"
 
 /**
  * 根据 429 错误类型决定路由层锁定时间
@@ -122,6 +124,13 @@ export function resolveRoutingEntries(config: RoutingConfig, model: string): Acc
     return available
 }
 
+/**
+ * 🆕 廉价预检：模型不可路由时在限流等待与获取许可之前直接拒绝（不调用上游）
+ */
+export function assertRoutableModel(model: string): void {
+    resolveRoutingEntries(loadRoutingConfig(), model)
+}
+
 function buildAutoEntriesForProvider(provider: string): AccountRoutingEntry[] {
     if (provider === "antigravity") {
         return accountManager.listAccounts().map(id => ({
@@ -169,7 +178,54 @@ async function executeProviderRequest(entry: AccountRoutingEntry, request: Route
     throw new Error(`Unsupported provider: ${entry.provider}`)
 }
 
+/**
+ * 🆕 上游报告模型不存在时，按 ANTI_API_MODEL_FALLBACKS 换用回退模型重试一次
+ */
+function resolveFallbackModel(request: RoutedRequest, error: unknown): string | undefined {
+    if (!isModelNotFoundError(error)) return undefined
+    const fallback = getModelFallback(request.model)
+    if (fallback) console.warn(`[Router] Model "${request.model}" not found upstream, falling back to "${fallback}"`)
+    return fallback
+}
+
 export async function createRoutedCompletion(request: RoutedRequest): Promise<ChatResponse> {
+    try {
+        return await routeCompletion(request)
+    } catch (error) {
+        const fallback = resolveFallbackModel(request, error)
+        if (!fallback) throw error
+        const result = await routeCompletion({ ...request, model: fallback })
+        return { ...result, fallbackModel: fallback }
+    }
+}
+
+export async function* createRoutedCompletionStream(request: RoutedRequest): AsyncGenerator<string, void, unknown> {
+    let yielded = false
+    try {
+        for await (const chunk of routeCompletionStream(request)) {
+            yielded = true
+            yield chunk
+        }
+    } catch (error) {
+        const fallback = yielded ? undefined : resolveFallbackModel(request, error)
+        if (!fallback) throw error
+        // SSE 注释行，客户端会忽略，但可据此得知由回退模型响应
+        yield `: fallback_model=${fallback}\n\n`
+        yield* routeCompletionStream({ ...request, model: fallback })
+    }
+}
+
+/**
+ * 🆕 上游缓冲 + 客户端流式：先完整拿到响应（可干净地 failover），再以 SSE 事件下发
+ */
+export async function* createBufferedCompletionStream(request: RoutedRequest): AsyncGenerator<string, void, unknown> {
+    const result = await createRoutedCompletion(request)
+    if (result.fallbackModel) yield `: fallback_model=${result.fallbackModel}\n\n`
+    if (result.truncated) yield ": truncated=true\n\n"
+    yield* buildSseFromResponse(result.fallbackModel || request.model, result.contentBlocks, result.stopReason, result.usage)
+}
+
+async function routeCompletion(request: RoutedRequest): Promise<ChatResponse> {
     const config = loadRoutingConfig()
     const entries = resolveRoutingEntries(config, request.model)
 
@@ -191,7 +247,7 @@ export async function createRoutedCompletion(request: RoutedRequest): Promise<Ch
             if (isModelLimited || isAccountLimited) {
                 if (entries.length > 1) continue
             }
-            if (entries.length > 1 && accountManager.isAccountInFlight(entry.accountId)) continue
+            if (entries.length > 1 && accountManager.isAccountAtCapacity(entry.accountId)) continue
         } else {
             if (isRouterRateLimited(entry.provider, entry.accountId, request.model)) continue
         }
@@ -202,10 +258,21 @@ export async function createRoutedCompletion(request: RoutedRequest): Promise<Ch
             recordProviderUsage(request.model, result)
             return result
         } catch (error) {
-            console.error(`Route failed [${entry.provider}:${entry.accountId}]:`, error)
             lastError = error as Error
+            // 🆕 账号并发已满（fast-fail 模式）：换下一个账号，不标记限流
+            if (error instanceof ConcurrencyLimitError && error.scope === "account") {
+                continue
+            }
+            console.error(`Route failed [${entry.provider}:${entry.accountId}]:`, error)
 
             if (error instanceof UpstreamError && shouldFallbackOnUpstream(error)) {
+                dumpErrorEvent({
+                    event: "failover",
+                    model: request.model,
+                    account: entry.accountId,
+                    status: error.status,
+                    upstreamRequestId: error.upstreamRequestId,
+                })
                 if (entry.provider === "antigravity") {
                     const lockDuration = getRouterLockDurationMs(error)
                     accountManager.markRateLimitedFromError(entry.accountId, error.status, error.body, error.retryAfter, request.model, { maxDurationMs: lockDuration })
@@ -223,7 +290,7 @@ export async function createRoutedCompletion(request: RoutedRequest): Promise<Ch
     throw lastError || new RoutingError("All routes failed", 503)
 }
 
-export async function* createRoutedCompletionStream(request: RoutedRequest): AsyncGenerator<string, void, unknown> {
+async function* routeCompletionStream(request: RoutedRequest): AsyncGenerator<string, void, unknown> {
     const config = loadRoutingConfig()
     const entries = resolveRoutingEntries(config, request.model)
 
@@ -243,7 +310,7 @@ export async function* createRoutedCompletionStream(request: RoutedRequest): Asy
             if (isModelLimited || isAccountLimited) {
                 if (entries.length > 1) continue
             }
-            if (entries.length > 1 && accountManager.isAccountInFlight(entry.accountId)) continue
+            if (entries.length > 1 && accountManager.isAccountAtCapacity(entry.accountId)) continue
         } else {
             if (isRouterRateLimited(entry.provider, entry.accountId, request.model)) continue
         }
@@ -257,10 +324,21 @@ export async function* createRoutedCompletionStream(request: RoutedRequest): Asy
             }
             return
         } catch (error) {
-            console.error(`Route stream failed [${entry.provider}:${entry.accountId}]:`, error)
             lastError = error as Error
+            // 🆕 账号并发已满（fast-fail 模式）：换下一个账号，不标记限流
+            if (error instanceof ConcurrencyLimitError && error.scope === "account") {
+                continue
+            }
+            console.error(`Route stream failed [${entry.provider}:${entry.accountId}]:`, error)
 
             if (error instanceof UpstreamError && shouldFallbackOnUpstream(error)) {
+                dumpErrorEvent({
+                    event: "failover",
+                    model: request.model,
+                    account: entry.accountId,
+                    status: error.status,
+                    upstreamRequestId: error.upstreamRequestId,
+                })
                 if (entry.provider === "antigravity") {
                     const lockDuration = getRouterLockDurationMs(error)
                     accountManager.markRateLimitedFromError(entry.accountId, error.status, error.body, error.retryAfter, request.model, { maxDurationMs: lockDuration })
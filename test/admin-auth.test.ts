@@ -0,0 +1,45 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { requireAdmin } from "../src/lib/admin-auth"
+import { adminRouter } from "../src/routes/admin/route"
+
+function createApp() {
+    const app = new Hono()
+    app.get("/admin/thing", requireAdmin, (c) => c.json({ ok: true }))
+    return app
+}
+
+describe("requireAdmin", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_ADMIN_KEY
+    })
+
+    test("admin endpoints are hidden when no admin key is configured", async () => {
+        const res = await createApp().request("/admin/thing", { headers: { "X-Admin-Key": "anything" } })
+        expect(res.status).toBe(404)
+    })
+
+    test("accepts only the admin key", async () => {
+        process.env.ANTI_API_ADMIN_KEY = "s3cret"
+        const app = createApp()
+        expect((await app.request("/admin/thing")).status).toBe(401)
+        expect((await app.request("/admin/thing", { headers: { "X-Admin-Key": "wrong" } })).status).toBe(401)
+        expect((await app.request("/admin/thing", { headers: { "X-Admin-Key": "s3cret" } })).status).toBe(200)
+        expect((await app.request("/admin/thing", { headers: { Authorization: "Bearer s3cret" } })).status).toBe(200)
+    })
+
+    test("the admin UI page requires the admin key", async () => {
+        const app = new Hono()
+        app.route("/admin", adminRouter)
+        expect((await app.request("/admin/ui")).status).toBe(404)
+
+        process.env.ANTI_API_ADMIN_KEY = "s3cret"
+        const anonymous = await app.request("/admin/ui")
+        expect(anonymous.status).toBe(401)
+        expect(anonymous.headers.get("Content-Type")).toContain("application/json")
+        expect((await app.request("/admin/ui", { headers: { "X-Admin-Key": "wrong" } })).status).toBe(401)
+        const authorized = await app.request("/admin/ui", { headers: { "X-Admin-Key": "s3cret" } })
+        expect(authorized.status).toBe(200)
+        expect(authorized.headers.get("Content-Type")).toContain("text/html")
+    })
+})
@@ -0,0 +1,115 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { authGuard, buildAuthChain, resetNonceCache, runAuthChain, signHmacRequest, type Authenticator } from "../src/lib/authenticator"
+
+function createApp() {
+    const app = new Hono()
+    app.use(authGuard)
+    app.post("/", async (c) => c.json({ body: await c.req.text() }))
+    return app
+}
+
+afterEach(() => {
+    delete process.env.ANTI_API_AUTH_CHAIN
+    delete process.env.ANTI_API_CLIENT_API_KEYS
+    delete process.env.ANTI_API_HMAC_SECRET
+    delete process.env.ANTI_API_HMAC_REQUIRE_NONCE
+    resetNonceCache()
+})
+
+describe("authGuard", () => {
+    test("empty chain allows everything", async () => {
+        expect((await createApp().request("/", { method: "POST", body: "{}" })).status).toBe(200)
+    })
+
+    test("api_key accepts configured keys only", async () => {
+        process.env.ANTI_API_AUTH_CHAIN = "api_key"
+        process.env.ANTI_API_CLIENT_API_KEYS = "k1,k2"
+        const app = createApp()
+        expect((await app.request("/", { method: "POST", body: "{}" })).status).toBe(401)
+        expect((await app.request("/", { method: "POST", body: "{}", headers: { "x-api-key": "nope" } })).status).toBe(401)
+        expect((await app.request("/", { method: "POST", body: "{}", headers: { Authorization: "Bearer k2" } })).status).toBe(200)
+    })
+
+    test("hmac verifies the signed body and still lets the handler read it", async () => {
+        process.env.ANTI_API_AUTH_CHAIN = "api_key,hmac"
+        process.env.ANTI_API_HMAC_SECRET = "shh"
+        const timestamp = String(Math.floor(Date.now() / 1000))
+        const body = "{\"model\":\"x\"}"
+        const app = createApp()
+        const signed = await app.request("/", {
+            method: "POST",
+            body,
+            headers: { "X-Timestamp": timestamp, "X-Signature": signHmacRequest("shh", timestamp, body) },
+        })
+        expect(signed.status).toBe(200)
+        expect(await signed.json()).toEqual({ body })
+
+        const tampered = await app.request("/", {
+            method: "POST",
+            body: "{}",
+            headers: { "X-Timestamp": timestamp, "X-Signature": signHmacRequest("shh", timestamp, body) },
+        })
+        expect(tampered.status).toBe(401)
+    })
+})
+
+describe("hmac nonce replay protection", () => {
+    function signedRequest(app: Hono, timestamp: string, nonce: string | undefined, body: string = "{}") {
+        const headers: Record<string, string> = { "X-Timestamp": timestamp, "X-Signature": signHmacRequest("shh", timestamp, body, nonce) }
+        if (nonce !== undefined) headers["X-Nonce"] = nonce
+        return app.request("/", { method: "POST", body, headers })
+    }
+
+    test("rejects a replayed nonce and a missing nonce", async () => {
+        process.env.ANTI_API_AUTH_CHAIN = "hmac"
+        process.env.ANTI_API_HMAC_SECRET = "shh"
+        process.env.ANTI_API_HMAC_REQUIRE_NONCE = "true"
+        const app = createApp()
+        const timestamp = String(Math.floor(Date.now() / 1000))
+
+        expect((await signedRequest(app, timestamp, "nonce-0001")).status).toBe(200)
+        const replayed = await signedRequest(app, timestamp, "nonce-0001")
+        expect(replayed.status).toBe(401)
+        expect((await replayed.json()).error.message).toMatch(/Replayed/)
+        expect((await signedRequest(app, timestamp, "nonce-0002")).status).toBe(200)
+        expect((await signedRequest(app, timestamp, undefined)).status).toBe(401)
+    })
+
+    test("rejects stale timestamps before recording the nonce", async () => {
+        process.env.ANTI_API_AUTH_CHAIN = "hmac"
+        process.env.ANTI_API_HMAC_SECRET = "shh"
+        process.env.ANTI_API_HMAC_REQUIRE_NONCE = "true"
+        const app = createApp()
+        const stale = String(Math.floor(Date.now() / 1000) - 3600)
+        expect((await signedRequest(app, stale, "nonce-0003")).status).toBe(401)
+        const fresh = String(Math.floor(Date.now() / 1000))
+        expect((await signedRequest(app, fresh, "nonce-0003")).status).toBe(200)
+    })
+
+    test("the nonce is part of the signature", async () => {
+        process.env.ANTI_API_AUTH_CHAIN = "hmac"
+        process.env.ANTI_API_HMAC_SECRET = "shh"
+        process.env.ANTI_API_HMAC_REQUIRE_NONCE = "true"
+        const timestamp = String(Math.floor(Date.now() / 1000))
+        const response = await createApp().request("/", {
+            method: "POST",
+            body: "{}",
+            headers: { "X-Timestamp": timestamp, "X-Nonce": "nonce-0004", "X-Signature": signHmacRequest("shh", timestamp, "{}") },
+        })
+        expect(response.status).toBe(401)
+    })
+})
+
+describe("runAuthChain", () => {
+    test("first accept wins over earlier rejections", async () => {
+        const reject: Authenticator = { name: "reject", authenticate: () => ({ outcome: "reject", message: "no" }) }
+        const accept: Authenticator = { name: "accept", authenticate: () => ({ outcome: "accept", principal: "p" }) }
+        expect(await runAuthChain([reject, accept], {} as any)).toEqual({ outcome: "accept", principal: "p" })
+        expect(await runAuthChain([reject], {} as any)).toEqual({ outcome: "reject", message: "no" })
+    })
+
+    test("unknown authenticators are rejected at build time", () => {
+        expect(() => buildAuthChain(["jwt"])).toThrow(/Unknown authenticator/)
+    })
+})
@@ -0,0 +1,41 @@
+import { test, expect, describe } from "bun:test"
+import { AutoTuner, type AutoTuneBounds } from "../src/lib/auto-tune"
+
+const bounds: AutoTuneBounds = {
+    minConcurrency: 1,
+    maxConcurrency: 8,
+    minIntervalMs: 100,
+    maxIntervalMs: 2000,
+    intervalStepMs: 100,
+    target429Rate: 0.1,
+}
+
+function windowOf(tuner: AutoTuner, ok: number, throttled: number) {
+    for (let i = 0; i < ok; i++) tuner.record(200)
+    for (let i = 0; i < throttled; i++) tuner.record(429)
+    return tuner.evaluate()
+}
+
+describe("AutoTuner", () => {
+    test("starts relaxed and backs off multiplicatively on a 429 spike", () => {
+        const tuner = new AutoTuner(bounds)
+        expect(tuner.snapshot()).toMatchObject({ concurrency: 8, intervalMs: 100 })
+
+        expect(windowOf(tuner, 5, 5)).toMatchObject({ state: "backing_off", concurrency: 4, intervalMs: 200, last429Rate: 0.5 })
+        expect(windowOf(tuner, 0, 3)).toMatchObject({ concurrency: 2, intervalMs: 400 })
+        for (let i = 0; i < 5; i++) windowOf(tuner, 0, 3)
+        expect(tuner.snapshot()).toMatchObject({ concurrency: 1, intervalMs: 2000 })
+    })
+
+    test("recovers additively while healthy and holds when idle", () => {
+        const tuner = new AutoTuner(bounds)
+        windowOf(tuner, 0, 10)
+        windowOf(tuner, 0, 10)
+        expect(tuner.snapshot()).toMatchObject({ concurrency: 2, intervalMs: 400 })
+
+        expect(windowOf(tuner, 20, 1)).toMatchObject({ state: "relaxing", concurrency: 3, intervalMs: 300 })
+        expect(windowOf(tuner, 0, 0)).toMatchObject({ state: "holding", concurrency: 3, intervalMs: 300 })
+        for (let i = 0; i < 10; i++) windowOf(tuner, 10, 0)
+        expect(tuner.snapshot()).toMatchObject({ concurrency: 8, intervalMs: 100 })
+    })
+})
@@ -0,0 +1,33 @@
+import { test, expect, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { billingTagGuard } from "../src/lib/billing-tag"
+import { getRequestContext, runWithRequestContext } from "../src/lib/request-context"
+import { makeRequestContext } from "./helpers"
+
+function createApp() {
+    const app = new Hono()
+    app.use(async (c, next) => runWithRequestContext(makeRequestContext({ startedAt: 0, path: "/" }), next))
+    app.use(billingTagGuard)
+    app.post("/", (c) => c.json({ tag: getRequestContext()?.billingTag ?? null }))
+    return app
+}
+
+afterEach(() => {
+    delete process.env.ANTI_API_BILLING_TAGS
+})
+
+test("allowlisted tags are recorded on the request context", async () => {
+    process.env.ANTI_API_BILLING_TAGS = "team-a,team-b"
+    const res = await createApp().request("/", { method: "POST", headers: { "X-Billing-Tag": "team-b" } })
+    expect(await res.json()).toEqual({ tag: "team-b" })
+})
+
+test("unknown tags are rejected and the header is ignored without an allowlist", async () => {
+    process.env.ANTI_API_BILLING_TAGS = "team-a"
+    const rejected = await createApp().request("/", { method: "POST", headers: { "X-Billing-Tag": "team-z" } })
+    expect(rejected.status).toBe(400)
+
+    delete process.env.ANTI_API_BILLING_TAGS
+    const ignored = await createApp().request("/", { method: "POST", headers: { "X-Billing-Tag": "team-z" } })
+    expect(await ignored.json()).toEqual({ tag: null })
+})
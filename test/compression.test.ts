@@ -0,0 +1,28 @@
+import { test, expect, describe } from "bun:test"
+import { getUpstreamSseEncodingHeaders, selectEncoding, shouldCompressResponse } from "../src/lib/compression"
+
+describe("response compression", () => {
+    test("picks gzip, then deflate, honouring q=0", () => {
+        expect(selectEncoding("gzip, deflate, br")).toBe("gzip")
+        expect(selectEncoding("gzip;q=0, deflate")).toBe("deflate")
+        expect(selectEncoding("br")).toBeNull()
+        expect(selectEncoding(undefined)).toBeNull()
+    })
+
+    test("skips SSE, already-encoded and small responses", () => {
+        expect(shouldCompressResponse(new Response("x", { headers: { "Content-Type": "text/event-stream" } }))).toBe(false)
+        expect(shouldCompressResponse(new Response("x", { headers: { "Content-Encoding": "gzip" } }))).toBe(false)
+        expect(shouldCompressResponse(new Response("x", { headers: { "Content-Length": "1" } }))).toBe(false)
+        expect(shouldCompressResponse(new Response("x".repeat(2048), { headers: { "Content-Type": "application/json" } }))).toBe(true)
+    })
+})
+
+test("upstream SSE requests disable compression unless overridden", () => {
+    expect(getUpstreamSseEncodingHeaders()).toEqual({ "Accept-Encoding": "identity" })
+    process.env.ANTI_API_SSE_UPSTREAM_COMPRESSION = "1"
+    try {
+        expect(getUpstreamSseEncodingHeaders()).toEqual({})
+    } finally {
+        delete process.env.ANTI_API_SSE_UPSTREAM_COMPRESSION
+    }
+})
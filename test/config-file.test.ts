@@ -0,0 +1,77 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { mkdtempSync, writeFileSync } from "fs"
+import { tmpdir } from "os"
+import { join } from "path"
+import { parseConfigFile } from "../src/lib/config-file"
+import { diffConfigKeys, reloadConfig } from "../src/lib/config-reload"
+import { getFileConfigValues, setFileConfigValues } from "../src/lib/env"
+
+describe("config file", () => {
+    test("maps keys and tables to ANTI_API_ names", () => {
+        const { values, unknown } = parseConfigFile(`
+max_concurrency = 4
+client_ids = ["ci", "bot"]
+infra_at_root = true
+
+[maintenance]
+status = 503
+message = "Back soon"
+`)
+        expect(values.ANTI_API_MAX_CONCURRENCY).toBe("4")
+        expect(values.ANTI_API_CLIENT_IDS).toBe("ci,bot")
+        expect(values.ANTI_API_INFRA_AT_ROOT).toBe("true")
+        expect(values.ANTI_API_MAINTENANCE_STATUS).toBe("503")
+        expect(values.ANTI_API_MAINTENANCE_MESSAGE).toBe("Back soon")
+        expect(unknown).toEqual([])
+    })
+
+    test("reports unknown keys", () => {
+        const { values, unknown } = parseConfigFile(`max_concurency = 4`)
+        expect(values).toEqual({})
+        expect(unknown).toEqual(["max_concurency"])
+    })
+})
+
+describe("config reload diff", () => {
+    test("lists added, removed and changed keys", () => {
+        const changed = diffConfigKeys(
+            { ANTI_API_ENDPOINTS: "https://a", ANTI_API_RAMP_SECS: "10" },
+            { ANTI_API_ENDPOINTS: "https://b", ANTI_API_STATUS_MAP: "400=422" },
+        )
+        expect(changed).toEqual(["ANTI_API_ENDPOINTS", "ANTI_API_RAMP_SECS", "ANTI_API_STATUS_MAP"])
+    })
+})
+
+describe("reloadConfig", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_CONFIG
+        setFileConfigValues({})
+    })
+
+    test("returns old and new values with secrets masked", () => {
+        const path = join(mkdtempSync(join(tmpdir(), "anti-api-config-")), "config.toml")
+        process.env.ANTI_API_CONFIG = path
+        setFileConfigValues({ ANTI_API_MAX_CONCURRENCY: "2" })
+        writeFileSync(path, "max_concurrency = 4\nadmin_key = \"hunter2\"\n")
+
+        const result = reloadConfig()
+        expect(result.error).toBeUndefined()
+        expect(result.diff).toEqual({
+            ANTI_API_ADMIN_KEY: { old: null, new: "***" },
+            ANTI_API_MAX_CONCURRENCY: { old: "2", new: "4" },
+        })
+        expect(getFileConfigValues().ANTI_API_MAX_CONCURRENCY).toBe("4")
+    })
+
+    test("keeps the current config when the file fails to parse", () => {
+        const path = join(mkdtempSync(join(tmpdir(), "anti-api-config-")), "config.toml")
+        process.env.ANTI_API_CONFIG = path
+        setFileConfigValues({ ANTI_API_MAX_CONCURRENCY: "2" })
+        writeFileSync(path, "max_concurrency = = 4")
+
+        const result = reloadConfig()
+        expect(result.error).toBeDefined()
+        expect(result.changed).toEqual([])
+        expect(getFileConfigValues()).toEqual({ ANTI_API_MAX_CONCURRENCY: "2" })
+    })
+})
@@ -0,0 +1,63 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { isConnectError, withConnectRetry } from "../src/lib/connect-retry"
+
+function connectError(code: string): Error {
+    return Object.assign(new Error(`connect failed: ${code}`), { code })
+}
+
+function flaky(failures: Error[]) {
+    let calls = 0
+    return {
+        attempt: async () => {
+            const failure = failures[calls++]
+            if (failure) throw failure
+            return "ok"
+        },
+        calls: () => calls,
+    }
+}
+
+describe("withConnectRetry", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_RETRY_CONNECT_ONLY
+    })
+
+    test("retries a refused connection once when enabled", async () => {
+        process.env.ANTI_API_RETRY_CONNECT_ONLY = "1"
+        const upstream = flaky([connectError("ECONNREFUSED")])
+        expect(await withConnectRetry(upstream.attempt)).toBe("ok")
+        expect(upstream.calls()).toBe(2)
+    })
+
+    test("gives up after a single retry", async () => {
+        process.env.ANTI_API_RETRY_CONNECT_ONLY = "1"
+        const upstream = flaky([connectError("ENOTFOUND"), connectError("ENOTFOUND")])
+        await expect(withConnectRetry(upstream.attempt)).rejects.toThrow("ENOTFOUND")
+        expect(upstream.calls()).toBe(2)
+    })
+
+    test("never retries errors that may happen after bytes were sent", async () => {
+        process.env.ANTI_API_RETRY_CONNECT_ONLY = "1"
+        const reset = flaky([connectError("ECONNRESET")])
+        await expect(withConnectRetry(reset.attempt)).rejects.toThrow("ECONNRESET")
+        expect(reset.calls()).toBe(1)
+
+        const aborted = new AbortController()
+        aborted.abort()
+        const refused = flaky([connectError("ECONNREFUSED")])
+        await expect(withConnectRetry(refused.attempt, aborted.signal)).rejects.toThrow()
+        expect(refused.calls()).toBe(1)
+    })
+
+    test("disabled by default", async () => {
+        const upstream = flaky([connectError("ECONNREFUSED")])
+        await expect(withConnectRetry(upstream.attempt)).rejects.toThrow()
+        expect(upstream.calls()).toBe(1)
+    })
+})
+
+test("isConnectError ignores aborts and generic errors", () => {
+    expect(isConnectError(connectError("ConnectionRefused"))).toBe(true)
+    expect(isConnectError(Object.assign(new Error("aborted"), { name: "AbortError", code: "ECONNREFUSED" }))).toBe(false)
+    expect(isConnectError(new Error("boom"))).toBe(false)
+})
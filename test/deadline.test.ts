@@ -0,0 +1,37 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { clampToDeadline, getRemainingBudgetMs, withDeadline } from "../src/lib/deadline"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { makeRequestContext } from "./helpers"
+
+describe("request deadline", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_TOTAL_DEADLINE_SECS
+    })
+
+    test("is disabled by default", () => {
+        runWithRequestContext(makeRequestContext({ startedAt: Date.now() }), () => {
+            expect(getRemainingBudgetMs()).toBeNull()
+            expect(clampToDeadline(5000)).toBe(5000)
+        })
+    })
+
+    test("shrinks upstream timeouts to the remaining budget", () => {
+        process.env.ANTI_API_TOTAL_DEADLINE_SECS = "10"
+        runWithRequestContext(makeRequestContext({ startedAt: Date.now() - 8000 }), () => {
+            const clamped = clampToDeadline(60000)
+            expect(clamped).toBeLessThanOrEqual(2000)
+            expect(clamped).toBeGreaterThan(1000)
+        })
+    })
+
+    test("fails waits that outlive the deadline and cleans up late results", async () => {
+        process.env.ANTI_API_TOTAL_DEADLINE_SECS = "1"
+        let lateReleased = false
+        await runWithRequestContext(makeRequestContext({ startedAt: Date.now() - 990 }), async () => {
+            const slow = new Promise<string>(resolve => setTimeout(() => resolve("permit"), 50))
+            await expect(withDeadline(slow, () => { lateReleased = true })).rejects.toMatchObject({ code: "deadline_exceeded", status: 504 })
+            await slow
+        })
+        expect(lateReleased).toBe(true)
+    })
+})
@@ -0,0 +1,26 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { getInjectedDelayMs, injectDelay } from "../src/lib/delay-inject"
+
+describe("delay injection", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_INJECT_DELAY_MS
+    })
+
+    test("off by default; header overrides the configured delay", () => {
+        expect(getInjectedDelayMs(undefined)).toBe(0)
+        process.env.ANTI_API_INJECT_DELAY_MS = "250"
+        expect(getInjectedDelayMs(undefined)).toBe(250)
+        expect(getInjectedDelayMs("40")).toBe(40)
+        expect(getInjectedDelayMs("junk")).toBe(250)
+    })
+
+    test("sleeps for the delay and aborts like a slow upstream", async () => {
+        const startedAt = Date.now()
+        await injectDelay(30, new AbortController().signal)
+        expect(Date.now() - startedAt).toBeGreaterThanOrEqual(25)
+
+        const controller = new AbortController()
+        setTimeout(() => controller.abort(), 10)
+        await expect(injectDelay(5000, controller.signal)).rejects.toMatchObject({ name: "AbortError" })
+    })
+})
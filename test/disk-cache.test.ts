@@ -0,0 +1,154 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { mkdtempSync, readdirSync, utimesSync, writeFileSync } from "fs"
+import { tmpdir } from "os"
+import { join } from "path"
+import { buildDiskCacheKey, canonicalJson, diskCacheGuard, evictDiskCache, isDeterministicRequest } from "../src/lib/disk-cache"
+
+function createApp(status: number = 200) {
+    let calls = 0
+    const app = new Hono()
+    app.use(diskCacheGuard)
+    app.post("/", (c) => {
+        calls++
+        return c.json({ call: calls }, status as any)
+    })
+    return { app, calls: () => calls }
+}
+
+const post = (app: Hono, body: unknown) => app.request("/", {
+    method: "POST",
+    body: JSON.stringify(body),
+    headers: { "Content-Type": "application/json" },
+})
+
+describe("disk cache", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_DISK_CACHE_DIR
+        delete process.env.ANTI_API_DISK_CACHE_TTL_SECS
+        delete process.env.ANTI_API_DISK_CACHE_STALE_MAX_AGE_SECS
+    })
+
+    test("key ignores property order and only deterministic requests qualify", () => {
+        expect(canonicalJson({ b: 1, a: [1, { d: 2, c: 3 }] })).toBe("{\"a\":[1,{\"c\":3,\"d\":2}],\"b\":1}")
+        expect(buildDiskCacheKey("/", { a: 1, b: 2 })).toBe(buildDiskCacheKey("/", { b: 2, a: 1 }))
+        expect(isDeterministicRequest({ temperature: 0 }, undefined)).toBe(true)
+        expect(isDeterministicRequest({ temperature: 0.7 }, undefined)).toBe(false)
+        expect(isDeterministicRequest({ temperature: 0, stream: true }, undefined)).toBe(false)
+        expect(isDeterministicRequest({ temperature: 0 }, "text/event-stream")).toBe(false)
+    })
+
+    test("serves repeated deterministic requests from disk and skips errors", async () => {
+        process.env.ANTI_API_DISK_CACHE_DIR = mkdtempSync(join(tmpdir(), "anti-api-cache-"))
+        const { app, calls } = createApp()
+        const first = await post(app, { model: "m", temperature: 0 })
+        expect(first.headers.get("X-Cache")).toBe("MISS")
+        const second = await post(app, { temperature: 0, model: "m" })
+        expect(second.headers.get("X-Cache")).toBe("HIT")
+        expect(await second.json()).toEqual({ call: 1 })
+
+        await post(app, { model: "m", temperature: 1 })
+        expect(calls()).toBe(2)
+
+        const failing = createApp(500)
+        await post(failing.app, { model: "x", temperature: 0 })
+        await post(failing.app, { model: "x", temperature: 0 })
+        expect(failing.calls()).toBe(2)
+    })
+
+    test("serves an expired entry flagged stale when upstream fails", async () => {
+        const dir = mkdtempSync(join(tmpdir(), "anti-api-cache-"))
+        process.env.ANTI_API_DISK_CACHE_DIR = dir
+        const body = { model: "m", temperature: 0 }
+        writeFileSync(join(dir, `${buildDiskCacheKey("/", body)}.json`), JSON.stringify({
+            createdAt: Date.now() - 120000,
+            status: 200,
+            contentType: "application/json",
+            body: JSON.stringify({ answer: 42 }),
+        }))
+        process.env.ANTI_API_DISK_CACHE_TTL_SECS = "60"
+
+        const failing = createApp(503)
+        const withoutStale = await post(failing.app, body)
+        expect(withoutStale.status).toBe(503)
+
+        writeFileSync(join(dir, `${buildDiskCacheKey("/", body)}.json`), JSON.stringify({
+            createdAt: Date.now() - 120000,
+            status: 200,
+            contentType: "application/json",
+            body: JSON.stringify({ answer: 42 }),
+        }))
+        process.env.ANTI_API_DISK_CACHE_STALE_MAX_AGE_SECS = "3600"
+        const stale = await post(failing.app, body)
+        expect(stale.status).toBe(200)
+        expect(stale.headers.get("X-Cache")).toBe("STALE")
+        expect(await stale.json()).toEqual({ answer: 42, stale: true })
+    })
+
+    test("stale window starts at expiry, so it may be shorter than the TTL", async () => {
+        const dir = mkdtempSync(join(tmpdir(), "anti-api-cache-"))
+        process.env.ANTI_API_DISK_CACHE_DIR = dir
+        process.env.ANTI_API_DISK_CACHE_TTL_SECS = "3600"
+        process.env.ANTI_API_DISK_CACHE_STALE_MAX_AGE_SECS = "600"
+        const body = { model: "m", temperature: 0 }
+        const file = join(dir, `${buildDiskCacheKey("/", body)}.json`)
+        const writeEntry = (ageMs: number) => writeFileSync(file, JSON.stringify({
+            createdAt: Date.now() - ageMs,
+            status: 200,
+            contentType: "application/json",
+            body: JSON.stringify({ answer: 42 }),
+        }))
+
+        // 写入 65 分钟、过期 5 分钟：仍在 10 分钟容忍期内
+        writeEntry(65 * 60 * 1000)
+        const failing = createApp(503)
+        const stale = await post(failing.app, body)
+        expect(stale.headers.get("X-Cache")).toBe("STALE")
+        expect(await stale.json()).toEqual({ answer: 42, stale: true })
+
+        // 过期 15 分钟：超出容忍期
+        writeEntry(75 * 60 * 1000)
+        const expired = await post(failing.app, body)
+        expect(expired.status).toBe(503)
+        expect(readdirSync(dir)).toHaveLength(0)
+    })
+
+    test("a plain 500 is not replaced by a stale entry", async () => {
+        const dir = mkdtempSync(join(tmpdir(), "anti-api-cache-"))
+        process.env.ANTI_API_DISK_CACHE_DIR = dir
+        process.env.ANTI_API_DISK_CACHE_TTL_SECS = "60"
+        process.env.ANTI_API_DISK_CACHE_STALE_MAX_AGE_SECS = "3600"
+        const body = { model: "m", temperature: 0 }
+        const writeEntry = () => writeFileSync(join(dir, `${buildDiskCacheKey("/", body)}.json`), JSON.stringify({
+            createdAt: Date.now() - 120000,
+            status: 200,
+            contentType: "application/json",
+            body: JSON.stringify({ answer: 42 }),
+        }))
+
+        writeEntry()
+        const failing = createApp(500)
+        const res = await post(failing.app, body)
+        expect(res.status).toBe(500)
+        expect(res.headers.get("X-Cache")).toBeNull()
+        expect(await res.json()).toEqual({ call: 1 })
+
+        for (const status of [502, 504]) {
+            writeEntry()
+            const unavailable = await post(createApp(status).app, body)
+            expect(unavailable.headers.get("X-Cache")).toBe("STALE")
+        }
+    })
+
+    test("evicts least recently used entries above the cap", async () => {
+        const dir = mkdtempSync(join(tmpdir(), "anti-api-cache-"))
+        for (const [index, name] of ["a", "b", "c"].entries()) {
+            const file = join(dir, `${name}.json`)
+            writeFileSync(file, "{}")
+            const time = new Date(Date.now() - (3 - index) * 60000)
+            utimesSync(file, time, time)
+        }
+        expect(await evictDiskCache(dir, 2)).toBe(1)
+        expect(readdirSync(dir).sort()).toEqual(["b.json", "c.json"])
+    })
+})
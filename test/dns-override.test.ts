@@ -0,0 +1,25 @@
+import { test, expect, describe } from "bun:test"
+import { applyResolveOverride, parseResolveOverrides } from "../src/lib/dns-override"
+
+describe("ANTI_API_RESOLVE overrides", () => {
+    test("parses host:ip, host:port:ip and bracketed IPv6", () => {
+        expect(parseResolveOverrides("a.example.com:10.0.0.1, b.example.com:8443:[::1]")).toEqual([
+            { host: "a.example.com", address: "10.0.0.1" },
+            { host: "b.example.com", port: 8443, address: "::1" },
+        ])
+        expect(() => parseResolveOverrides("a.example.com:not-an-ip")).toThrow(/ANTI_API_RESOLVE/)
+        expect(() => parseResolveOverrides("a.example.com:999.1.1.1")).toThrow()
+    })
+
+    test("rewrites matching URLs and keeps the original host for Host and SNI", () => {
+        const overrides = parseResolveOverrides("cloudcode-pa.googleapis.com:10.0.0.1,other.example.com:8443:[::1]")
+        expect(applyResolveOverride("https://cloudcode-pa.googleapis.com/v1internal:generateContent", overrides)).toEqual({
+            url: "https://10.0.0.1/v1internal:generateContent",
+            host: "cloudcode-pa.googleapis.com",
+            serverName: "cloudcode-pa.googleapis.com",
+        })
+        expect(applyResolveOverride("https://other.example.com:8443/x", overrides)?.url).toBe("https://[::1]:8443/x")
+        expect(applyResolveOverride("https://other.example.com/x", overrides)).toBeNull()
+        expect(applyResolveOverride("https://unlisted.example.com/x", overrides)).toBeNull()
+    })
+})
@@ -0,0 +1,59 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { AntigravityError } from "../src/lib/error"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { createChatCompletionStreamWithOptions, createChatCompletionWithOptions } from "../src/services/antigravity/chat"
+import { dataFrame, installFakeUpstream, sseResponse, type FakeUpstream } from "./fake-upstream"
+import { counterValue, makeRequestContext } from "./helpers"
+
+const request = { model: "claude-sonnet-4-5", messages: [{ role: "user" as const, content: "hi" }] }
+
+async function collect(stream: AsyncGenerator<string>): Promise<string[]> {
+    const events: string[] = []
+    for await (const event of stream) events.push(event)
+    return events
+}
+
+describe("empty upstream 200 bodies", () => {
+    let upstream: FakeUpstream | undefined
+
+    afterEach(() => {
+        upstream?.restore()
+        upstream = undefined
+        delete process.env.ANTI_API_EMPTY_RESPONSE_MODE
+    })
+
+    test("fail over to the next endpoint by default", async () => {
+        upstream = installFakeUpstream((url) => url.hostname === "a.test"
+            ? new Response("  \n", { status: 200, headers: { "Content-Type": "text/event-stream" } })
+            : sseResponse([dataFrame("from b")]))
+        const before = counterValue("upstream_empty_responses_total", { mode: "buffered" })
+
+        const result = await runWithRequestContext(makeRequestContext(), () => createChatCompletionWithOptions(request))
+        expect(result.contentBlocks).toEqual([{ type: "text", text: "from b" }])
+        expect(upstream.calls).toEqual(["a.test", "b.test"])
+        expect(counterValue("upstream_empty_responses_total", { mode: "buffered" })).toBe(before + 1)
+    })
+
+    test("return 502 empty_response when failover is turned off", async () => {
+        process.env.ANTI_API_EMPTY_RESPONSE_MODE = "error"
+        upstream = installFakeUpstream(() => new Response("", { status: 200, headers: { "Content-Type": "text/event-stream" } }))
+
+        const error = await runWithRequestContext(makeRequestContext(), () => createChatCompletionWithOptions(request)).catch(e => e)
+        expect(error).toBeInstanceOf(AntigravityError)
+        expect(error.code).toBe("empty_response")
+        expect(error.status).toBe(502)
+        expect(upstream.calls).toEqual(["a.test"])
+    })
+
+    test("streams count empty bodies under their own mode and fail over", async () => {
+        upstream = installFakeUpstream((url, init) => url.hostname === "a.test"
+            ? sseResponse([": keepalive\n\n"], { signal: init.signal })
+            : sseResponse([dataFrame("from b")]))
+        const before = counterValue("upstream_empty_responses_total", { mode: "stream" })
+
+        const events = await runWithRequestContext(makeRequestContext(), () => collect(createChatCompletionStreamWithOptions(request)))
+        expect(events.join("")).toContain("from b")
+        expect(upstream.calls).toEqual(["a.test", "b.test"])
+        expect(counterValue("upstream_empty_responses_total", { mode: "stream" })).toBe(before + 1)
+    })
+})
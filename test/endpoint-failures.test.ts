@@ -0,0 +1,39 @@
+import { test, expect, describe } from "bun:test"
+import { classifyEndpointFailures, recordEndpointFailure, toAllEndpointsFailedError } from "../src/lib/endpoint-failures"
+import { AllEndpointsFailedError, UpstreamError } from "../src/lib/error"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { makeRequestContext } from "./helpers"
+
+describe("classifyEndpointFailures", () => {
+    test("names a single dominant cause", () => {
+        expect(classifyEndpointFailures([{ endpoint: "a", kind: "timeout" }, { endpoint: "b", kind: "timeout" }])).toBe("all_timeout")
+        expect(classifyEndpointFailures([{ endpoint: "a", kind: "server_error", status: 500 }])).toBe("all_server_error")
+        expect(classifyEndpointFailures([{ endpoint: "a", kind: "connect_error" }])).toBe("all_connect_error")
+        expect(classifyEndpointFailures([{ endpoint: "a", kind: "timeout" }, { endpoint: "b", kind: "server_error" }])).toBe("mixed")
+    })
+})
+
+describe("toAllEndpointsFailedError", () => {
+    test("summarizes recorded failures as a 503", () => {
+        runWithRequestContext(makeRequestContext(), () => {
+            recordEndpointFailure("https://a", "server_error", { status: 502 })
+            recordEndpointFailure("https://b", "server_error", { status: 500 })
+            const error = toAllEndpointsFailedError(new UpstreamError("antigravity", 500, "boom"))
+            expect(error).toBeInstanceOf(AllEndpointsFailedError)
+            const failed = error as AllEndpointsFailedError
+            expect(failed.status).toBe(503)
+            expect(failed.errorCode).toBe("all_server_error")
+            expect(failed.endpoints.map(entry => entry.status)).toEqual([502, 500])
+        })
+    })
+
+    test("keeps actionable 4xx errors and requests without failures as-is", () => {
+        const plain = new Error("All endpoints failed")
+        expect(toAllEndpointsFailedError(plain)).toBe(plain)
+        runWithRequestContext(makeRequestContext(), () => {
+            recordEndpointFailure("https://a", "client_error", { status: 429 })
+            const limited = new UpstreamError("antigravity", 429, "slow down")
+            expect(toAllEndpointsFailedError(limited)).toBe(limited)
+        })
+    })
+})
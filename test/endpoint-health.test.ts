@@ -0,0 +1,21 @@
+import { test, expect, beforeEach } from "bun:test"
+import { getEndpointHealth, recordEndpointResult, resetEndpointHealth } from "../src/lib/endpoint-health"
+
+beforeEach(() => resetEndpointHealth())
+
+test("reports unknown endpoints until observed", () => {
+    const [health] = getEndpointHealth(["https://a.example.com"])
+    expect(health.state).toBe("unknown")
+    expect(health.lastStatus).toBeNull()
+})
+
+test("treats 4xx as reachable and 5xx or network errors as unhealthy", () => {
+    recordEndpointResult("https://a.example.com", 429, 120.4)
+    recordEndpointResult("https://b.example.com", 503, 80)
+    recordEndpointResult("https://c.example.com", null, 30000, "timeout")
+
+    const health = getEndpointHealth(["https://a.example.com", "https://b.example.com", "https://c.example.com"])
+    expect(health.map(h => h.state)).toEqual(["healthy", "unhealthy", "unhealthy"])
+    expect(health[0].latencyMs).toBe(120)
+    expect(health[2].error).toBe("timeout")
+})
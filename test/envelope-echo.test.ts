@@ -0,0 +1,28 @@
+import { test, expect, describe } from "bun:test"
+import { Hono } from "hono"
+import { redactEnvelope } from "../src/lib/envelope-echo"
+import { forwardError, UpstreamError } from "../src/lib/error"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { makeRequestContext } from "./helpers"
+
+describe("envelope echo", () => {
+    test("redacts token-like fields recursively", () => {
+        const envelope = redactEnvelope(JSON.stringify({ project: "p1", request: { access_token: "ya29", contents: [{ apiKey: "k" }] } }))
+        expect(envelope).toEqual({ project: "p1", request: { access_token: "[REDACTED]", contents: [{ apiKey: "[REDACTED]" }] } })
+    })
+
+    test("includes the envelope on upstream 4xx only when enabled", async () => {
+        const app = new Hono()
+        app.get("/err/:status", (c) => forwardError(c, new UpstreamError("antigravity", Number(c.req.param("status")), "bad request")))
+        const envelope = JSON.stringify({ model: "m", request: { contents: [] } })
+
+        const withEcho = await runWithRequestContext(makeRequestContext({ upstreamCalls: 1, echoEnvelope: true, upstreamEnvelope: envelope }), () => app.request("/err/400"))
+        expect((await withEcho.json() as any).error.request_envelope).toEqual({ model: "m", request: { contents: [] } })
+
+        const serverError = await runWithRequestContext(makeRequestContext({ upstreamCalls: 1, echoEnvelope: true, upstreamEnvelope: envelope }), () => app.request("/err/500"))
+        expect((await serverError.json() as any).error.request_envelope).toBeUndefined()
+
+        const disabled = await runWithRequestContext(makeRequestContext({ upstreamCalls: 1, upstreamEnvelope: envelope }), () => app.request("/err/400"))
+        expect((await disabled.json() as any).error.request_envelope).toBeUndefined()
+    })
+})
@@ -0,0 +1,41 @@
+import { test, expect, describe } from "bun:test"
+import { EnvelopeSerializer } from "../src/services/antigravity/envelope"
+
+function buildEnvelope(): Record<string, any> {
+    return {
+        model: "gemini-3-flash",
+        userAgent: "antigravity",
+        requestType: "agent",
+        project: "p-1",
+        requestId: "agent-1",
+        request: {
+            contents: [{ role: "user", parts: [{ text: "hi \"there\"   ✓" }] }],
+            generationConfig: { maxOutputTokens: 16, stopSequences: undefined },
+        },
+    }
+}
+
+describe("EnvelopeSerializer", () => {
+    test("matches JSON.stringify byte for byte", () => {
+        const envelope = buildEnvelope()
+        expect(new EnvelopeSerializer(envelope).serialize()).toBe(JSON.stringify(envelope))
+    })
+
+    test("picks up top-level changes such as a rotated project", () => {
+        const envelope = buildEnvelope()
+        const serializer = new EnvelopeSerializer(envelope)
+        serializer.serialize()
+        envelope.project = "p-2"
+        expect(serializer.serialize()).toBe(JSON.stringify(envelope))
+        envelope.project = undefined
+        expect(serializer.serialize()).toBe(JSON.stringify(envelope))
+    })
+
+    test("re-serializes a replaced request", () => {
+        const envelope = buildEnvelope()
+        const serializer = new EnvelopeSerializer(envelope)
+        serializer.serialize()
+        envelope.request = { contents: [] }
+        expect(serializer.serialize()).toBe(JSON.stringify(envelope))
+    })
+})
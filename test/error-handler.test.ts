@@ -1,5 +1,7 @@
 import { test, expect } from "bun:test"
-import { UpstreamError, AntigravityError, HTTPError } from "../src/lib/error"
+import { Hono } from "hono"
+import { UpstreamError, AntigravityError, HTTPError, parseStatusMap, isModelNotFoundError, handleUncaughtError, classifyUpstreamAuthError } from "../src/lib/error"
+import { parseModelFallbacks } from "../src/services/routing/fallback"
 
 test("UpstreamError constructs with correct properties", () => {
     const error = new UpstreamError("antigravity", 429, "rate limited", "60")
@@ -18,6 +20,12 @@ test("UpstreamError works without retryAfter", () => {
     expect(error.retryAfter).toBeUndefined()
 })
 
+test("UpstreamError keeps upstream request id", () => {
+    const error = new UpstreamError("antigravity", 503, "unavailable", undefined, "goog-req-123")
+
+    expect(error.upstreamRequestId).toBe("goog-req-123")
+})
+
 test("AntigravityError constructs with code", () => {
     const error = new AntigravityError("auth failed", "auth_error")
 
@@ -99,3 +107,86 @@ test("buildLogReason returns internal error for unknown errors", () => {
     expect(buildLogReason(new Error("unknown"))).toBe("internal error")
     expect(buildLogReason("string error")).toBe("internal error")
 })
+
+test("parseStatusMap parses pairs and skips invalid entries", () => {
+    const map = parseStatusMap("400=422; 403=401;bad;700=200")
+
+    expect(map.get(400)).toBe(422)
+    expect(map.get(403)).toBe(401)
+    expect(map.has(700)).toBe(false)
+    expect(map.size).toBe(2)
+})
+
+test("isModelNotFoundError only matches model-not-found 400s", () => {
+    const notFound = JSON.stringify({ error: { code: 400, message: "Model gemini-x is not found for API version v1internal" } })
+    const badRequest = JSON.stringify({ error: { code: 400, message: "Invalid JSON payload received" } })
+
+    expect(isModelNotFoundError(new UpstreamError("antigravity", 400, notFound))).toBe(true)
+    expect(isModelNotFoundError(new UpstreamError("antigravity", 400, badRequest))).toBe(false)
+    expect(isModelNotFoundError(new UpstreamError("antigravity", 404, notFound))).toBe(false)
+})
+
+test("parseModelFallbacks parses primary=fallback pairs", () => {
+    const map = parseModelFallbacks("gemini-3-pro=gemini-2.5-pro; bad; same=same")
+
+    expect(map.get("gemini-3-pro")).toBe("gemini-2.5-pro")
+    expect(map.size).toBe(1)
+})
+
+test("handleUncaughtError turns unexpected throws into internal_panic JSON", async () => {
+    const app = new Hono()
+    app.onError(handleUncaughtError)
+    app.get("/boom", () => {
+        throw new TypeError("cannot read properties of undefined")
+    })
+    app.get("/typed", () => {
+        throw new AntigravityError("too slow", "deadline_exceeded", 504)
+    })
+
+    const res = await app.request("/boom")
+    expect(res.status).toBe(500)
+    const body = await res.json() as any
+    expect(body.error.type).toBe("internal_panic")
+    expect(body.error.message).toBe("Internal server error")
+
+    const typed = await app.request("/typed")
+    expect(typed.status).toBe(504)
+    expect((await typed.json() as any).error.type).toBe("deadline_exceeded")
+})
+
+test("AntigravityError with retryAfterMs sets Retry-After", async () => {
+    const app = new Hono()
+    app.onError(handleUncaughtError)
+    app.get("/cooling", () => {
+        throw new AntigravityError("All 2 account(s) are cooling down; retry in 5s", "all_accounts_cooling", 429, 4200)
+    })
+
+    const res = await app.request("/cooling")
+    expect(res.status).toBe(429)
+    expect(res.headers.get("Retry-After")).toBe("5")
+    const body = await res.json() as any
+    expect(body.error.type).toBe("all_accounts_cooling")
+    expect(body.error.retry_after_ms).toBe(4200)
+})
+
+test("upstream 401 and 403 surface distinct error codes", async () => {
+    expect(classifyUpstreamAuthError(new UpstreamError("antigravity", 401, "expired"))).toEqual({ errorCode: "token_invalid" })
+    const disabled = new UpstreamError("antigravity", 403, JSON.stringify({
+        error: { message: "Cloud Code API has not been used in project 123", details: [{ reason: "SERVICE_DISABLED" }] },
+    }))
+    expect(classifyUpstreamAuthError(disabled)).toEqual({ errorCode: "access_denied", reason: "project_config" })
+    const denied = new UpstreamError("antigravity", 403, JSON.stringify({ error: { message: "The caller does not have permission" } }))
+    expect(classifyUpstreamAuthError(denied)).toEqual({ errorCode: "access_denied", reason: "permission_denied" })
+    expect(classifyUpstreamAuthError(new UpstreamError("antigravity", 500, "boom"))).toBeNull()
+
+    const app = new Hono()
+    app.onError(handleUncaughtError)
+    app.get("/denied", () => {
+        throw disabled
+    })
+    const res = await app.request("/denied")
+    expect(res.status).toBe(403)
+    const body = await res.json() as any
+    expect(body.error.error_code).toBe("access_denied")
+    expect(body.error.reason).toBe("project_config")
+})
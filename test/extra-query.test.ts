@@ -0,0 +1,16 @@
+import { test, expect } from "bun:test"
+import { buildUpstreamUrl } from "../src/lib/extra-query"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { makeRequestContext } from "./helpers"
+
+test("buildUpstreamUrl appends configured and per-request params with encoding", () => {
+    process.env.ANTI_API_EXTRA_QUERY = "feature=a&alt=json"
+    try {
+        const url = runWithRequestContext(makeRequestContext({
+            extraQuery: { feature: "b c", tag: "x&y" },
+        }), () => buildUpstreamUrl("https://cloudcode-pa.googleapis.com", "/v1internal:streamGenerateContent"))
+        expect(url).toBe("https://cloudcode-pa.googleapis.com/v1internal:streamGenerateContent?alt=sse&feature=b+c&tag=x%26y")
+    } finally {
+        delete process.env.ANTI_API_EXTRA_QUERY
+    }
+})
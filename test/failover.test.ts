@@ -0,0 +1,56 @@
+import { test, expect, describe } from "bun:test"
+import { isSuccessStatus, matchesFailoverStatus, parseFailoverStatuses, parseSuccessStatuses, DEFAULT_FAILOVER_STATUSES } from "../src/lib/failover"
+
+describe("parseFailoverStatuses", () => {
+    test("default keeps 404, 408 and all 5xx", () => {
+        const set = parseFailoverStatuses(DEFAULT_FAILOVER_STATUSES)
+        for (const status of [404, 408, 500, 502, 503, 599]) {
+            expect(matchesFailoverStatus(set, status)).toBe(true)
+        }
+        for (const status of [400, 401, 403, 429]) {
+            expect(matchesFailoverStatus(set, status)).toBe(false)
+        }
+    })
+
+    test("accepts explicit codes", () => {
+        const set = parseFailoverStatuses("403, 502")
+        expect(matchesFailoverStatus(set, 403)).toBe(true)
+        expect(matchesFailoverStatus(set, 502)).toBe(true)
+        expect(matchesFailoverStatus(set, 500)).toBe(false)
+    })
+
+    test("rejects 429 directly or via 4xx", () => {
+        expect(() => parseFailoverStatuses("5xx,429")).toThrow(/429/)
+        expect(() => parseFailoverStatuses("4xx")).toThrow(/429/)
+    })
+
+    test("rejects malformed entries", () => {
+        expect(() => parseFailoverStatuses("abc")).toThrow()
+        expect(() => parseFailoverStatuses("700")).toThrow()
+    })
+})
+
+describe("success statuses", () => {
+    test("default treats every 2xx as success", () => {
+        delete process.env.ANTI_API_SUCCESS_STATUSES
+        expect(isSuccessStatus(200)).toBe(true)
+        expect(isSuccessStatus(206)).toBe(true)
+        expect(isSuccessStatus(301)).toBe(false)
+    })
+
+    test("an explicit list excludes other 2xx codes", () => {
+        process.env.ANTI_API_SUCCESS_STATUSES = "200"
+        try {
+            expect(isSuccessStatus(200)).toBe(true)
+            expect(isSuccessStatus(206)).toBe(false)
+        } finally {
+            delete process.env.ANTI_API_SUCCESS_STATUSES
+        }
+    })
+
+    test("rejects non-2xx and empty lists", () => {
+        expect(() => parseSuccessStatuses("200,404")).toThrow()
+        expect(() => parseSuccessStatuses("3xx")).toThrow()
+        expect(() => parseSuccessStatuses(" , ")).toThrow()
+    })
+})
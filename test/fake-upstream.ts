@@ -0,0 +1,75 @@
+/**
+ * 测试用的假上游：替换 globalThis.fetch，并让 accountManager 只提供一个测试账号
+ * 端点通过 ANTI_API_ENDPOINTS 指向 https://a.test、https://b.test 等不存在的主机，不会真正联网
+ */
+
+import { spyOn } from "bun:test"
+import { accountManager } from "../src/services/antigravity/account-manager"
+
+export const TEST_ENDPOINTS = ["https://a.test", "https://b.test"]
+
+const TEST_ACCOUNT = { accountId: "test-account", accessToken: "test-token", projectId: "test-project", email: "test@example.com" }
+
+export type UpstreamHandler = (url: URL, init: RequestInit) => Response | Promise<Response>
+
+export interface FakeUpstream {
+    /** 每次上游调用的主机名，按调用顺序 */
+    readonly calls: string[]
+    restore(): void
+}
+
+export function installFakeUpstream(handler: UpstreamHandler): FakeUpstream {
+    const originalFetch = globalThis.fetch
+    const calls: string[] = []
+    globalThis.fetch = (async (input: string | URL | Request, init?: RequestInit) => {
+        const url = new URL(input instanceof Request ? input.url : String(input))
+        calls.push(url.hostname)
+        return handler(url, init ?? {})
+    }) as typeof fetch
+    const spies = [
+        spyOn(accountManager, "count").mockReturnValue(1),
+        spyOn(accountManager, "hasAccount").mockReturnValue(true),
+        spyOn(accountManager, "listAccounts").mockReturnValue([TEST_ACCOUNT.accountId]),
+        spyOn(accountManager, "getNextAvailableAccount").mockResolvedValue(TEST_ACCOUNT),
+        spyOn(accountManager, "getAccountById").mockResolvedValue(TEST_ACCOUNT),
+        spyOn(accountManager, "acquireAccountLock").mockResolvedValue(() => { }),
+        spyOn(accountManager, "markSuccess").mockImplementation(() => { }),
+    ]
+    process.env.ANTI_API_ENDPOINTS = TEST_ENDPOINTS.join(",")
+    return {
+        calls,
+        restore() {
+            globalThis.fetch = originalFetch
+            for (const spy of spies) spy.mockRestore()
+            delete process.env.ANTI_API_ENDPOINTS
+        },
+    }
+}
+
+export function dataFrame(text: string): string {
+    return `data: ${JSON.stringify({ response: { candidates: [{ content: { parts: [{ text }] } }] } })}\n\n`
+}
+
+/**
+ * SSE 响应；stall 为 true 时发完 frames 后不再结束，直到请求被中止（与真实 fetch 一样以 AbortError 失败）
+ */
+export function sseResponse(frames: string[], options: { signal?: AbortSignal | null; stall?: boolean } = {}): Response {
+    const encoder = new TextEncoder()
+    const body = new ReadableStream<Uint8Array>({
+        start(controller) {
+            for (const frame of frames) controller.enqueue(encoder.encode(frame))
+            if (!options.stall) {
+                controller.close()
+                return
+            }
+            options.signal?.addEventListener("abort", () => {
+                controller.error(new DOMException("The operation was aborted.", "AbortError"))
+            }, { once: true })
+        },
+    })
+    return new Response(body, { status: 200, headers: { "Content-Type": "text/event-stream" } })
+}
+
+export function statusResponse(status: number, body: string = ""): Response {
+    return new Response(body, { status, headers: { "Content-Type": "application/json" } })
+}
@@ -0,0 +1,38 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { injectFault, pickFault } from "../src/lib/fault-inject"
+
+describe("fault injection", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_FAULT_INJECT
+        delete process.env.ANTI_API_FAULT_INJECT_RATE
+        delete process.env.ANTI_API_FAULT_INJECT_KINDS
+    })
+
+    test("does nothing without the explicit flag", () => {
+        process.env.ANTI_API_FAULT_INJECT_RATE = "1"
+        expect(pickFault("429", () => 0)).toBeNull()
+    })
+
+    test("header forces a fault kind, rate picks configured kinds", () => {
+        process.env.ANTI_API_FAULT_INJECT = "1"
+        expect(pickFault("drop")).toBe("drop")
+        expect(pickFault(undefined, () => 0)).toBeNull()
+
+        process.env.ANTI_API_FAULT_INJECT_RATE = "0.5"
+        process.env.ANTI_API_FAULT_INJECT_KINDS = "500"
+        expect(pickFault(undefined, () => 0.1)).toBe("500")
+        expect(pickFault(undefined, () => 0.9)).toBeNull()
+    })
+
+    test("injected faults mimic upstream failures", async () => {
+        const signal = new AbortController().signal
+        expect((await injectFault("429", "https://a.example.com/x", signal)).status).toBe(429)
+        expect((await injectFault("500", "https://a.example.com/x", signal)).status).toBe(500)
+        await expect(injectFault("drop", "https://a.example.com/x", signal)).rejects.toThrow(/closed unexpectedly/)
+
+        const controller = new AbortController()
+        const pending = injectFault("timeout", "https://a.example.com/x", controller.signal)
+        controller.abort()
+        await expect(pending).rejects.toThrow(/aborted/)
+    })
+})
@@ -0,0 +1,39 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { readForcedEndpointHeader, selectForcedEndpoint } from "../src/lib/forced-endpoint"
+
+const endpoints = ["https://a.example", "https://b.example"]
+
+describe("selectForcedEndpoint", () => {
+    test("keeps the configured index", () => {
+        expect(selectForcedEndpoint(endpoints, "1")).toEqual([[1, "https://b.example"]])
+    })
+
+    test("rejects out-of-range and malformed indices", () => {
+        for (const value of ["2", "-1", "1.5", "x"]) {
+            expect(() => selectForcedEndpoint(endpoints, value)).toThrow(/X-Endpoint-Index/)
+        }
+    })
+})
+
+describe("readForcedEndpointHeader", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_ADMIN_KEY
+    })
+
+    function createApp() {
+        const app = new Hono()
+        app.get("/", (c) => c.json({ forced: readForcedEndpointHeader(c) ?? null }))
+        return app
+    }
+
+    test("ignored without a valid admin key", async () => {
+        process.env.ANTI_API_ADMIN_KEY = "s3cret"
+        const app = createApp()
+        const anonymous = await app.request("/", { headers: { "X-Endpoint-Index": "0" } })
+        expect(await anonymous.json()).toEqual({ forced: null })
+
+        const admin = await app.request("/", { headers: { "X-Endpoint-Index": "0", "X-Admin-Key": "s3cret" } })
+        expect(await admin.json()).toEqual({ forced: "0" })
+    })
+})
@@ -0,0 +1,28 @@
+/**
+ * 测试共用的构造工具
+ */
+
+import { snapshotMetrics } from "../src/lib/metrics"
+import type { RequestContext } from "../src/lib/request-context"
+
+/**
+ * 构造未签名的 JWT（只有 payload 有意义）
+ */
+export function jwt(payload: Record<string, unknown>): string {
+    const encode = (value: unknown) => Buffer.from(JSON.stringify(value)).toString("base64url")
+    return `${encode({ alg: "RS256" })}.${encode(payload)}.signature`
+}
+
+export function makeRequestContext(overrides: Partial<RequestContext> = {}): RequestContext {
+    return { requestId: "r1", startedAt: Date.now(), method: "POST", path: "/v1/messages", upstreamCalls: 0, ...overrides }
+}
+
+/**
+ * 计数器当前值（labels 为子集匹配，对匹配的样本求和）
+ */
+export function counterValue(name: string, labels: Record<string, string> = {}): number {
+    const samples = snapshotMetrics()?.counters[`anti_api_${name}`] ?? []
+    return samples
+        .filter(sample => Object.entries(labels).every(([key, value]) => sample.labels[key] === value))
+        .reduce((sum, sample) => sum + sample.value, 0)
+}
@@ -0,0 +1,81 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { idempotencyGuard, isCompleteBody, resetIdempotencyCache } from "../src/lib/idempotency"
+
+function createApp(status: number = 200) {
+    let calls = 0
+    const app = new Hono()
+    app.use(idempotencyGuard)
+    app.post("/", async (c) => {
+        calls++
+        await new Promise(resolve => setTimeout(resolve, 5))
+        return c.json({ call: calls }, status as any)
+    })
+    return { app, calls: () => calls }
+}
+
+function post(app: Hono, body: string, headers: Record<string, string> = {}) {
+    return app.request("/", { method: "POST", body, headers: { "Content-Type": "application/json", ...headers } })
+}
+
+describe("idempotencyGuard", () => {
+    afterEach(() => {
+        resetIdempotencyCache()
+        delete process.env.ANTI_API_IDEMPOTENCY_TTL_SECS
+    })
+
+    test("replays the first result for a repeated key", async () => {
+        const { app, calls } = createApp()
+        const first = await post(app, "{}", { "Idempotency-Key": "abc" })
+        await first.text()
+        await new Promise(resolve => setTimeout(resolve, 5))
+        const second = await post(app, "{}", { "Idempotency-Key": "abc" })
+
+        expect(await second.json()).toEqual({ call: 1 })
+        expect(second.headers.get("Idempotent-Replayed")).toBe("true")
+        expect(calls()).toBe(1)
+    })
+
+    test("concurrent duplicates wait for the in-flight request", async () => {
+        const { app, calls } = createApp()
+        const [a, b] = await Promise.all([
+            post(app, "{}", { "Idempotency-Key": "same" }),
+            post(app, "{}", { "Idempotency-Key": "same" }),
+        ])
+        expect(await a.json()).toEqual({ call: 1 })
+        expect(await b.json()).toEqual({ call: 1 })
+        expect(calls()).toBe(1)
+    })
+
+    test("keys are scoped per credential and reject a different body", async () => {
+        const { app, calls } = createApp()
+        await (await post(app, "{}", { "Idempotency-Key": "k", Authorization: "Bearer one" })).text()
+        await new Promise(resolve => setTimeout(resolve, 5))
+        const other = await post(app, "{}", { "Idempotency-Key": "k", Authorization: "Bearer two" })
+        expect(await other.json()).toEqual({ call: 2 })
+
+        const mismatch = await post(app, "{\"x\":1}", { "Idempotency-Key": "k", Authorization: "Bearer one" })
+        expect(mismatch.status).toBe(422)
+        expect(calls()).toBe(2)
+    })
+
+    test("errors are not cached and TTL 0 disables the cache", async () => {
+        const failing = createApp(500)
+        await (await post(failing.app, "{}", { "Idempotency-Key": "e" })).text()
+        await (await post(failing.app, "{}", { "Idempotency-Key": "e" })).text()
+        expect(failing.calls()).toBe(2)
+
+        process.env.ANTI_API_IDEMPOTENCY_TTL_SECS = "0"
+        const { app, calls } = createApp()
+        await (await post(app, "{}", { "Idempotency-Key": "off" })).text()
+        await (await post(app, "{}", { "Idempotency-Key": "off" })).text()
+        expect(calls()).toBe(2)
+    })
+})
+
+test("isCompleteBody requires a terminal frame for SSE", () => {
+    expect(isCompleteBody("application/json", "{}")).toBe(true)
+    expect(isCompleteBody("text/event-stream", "event: message_stop\ndata: {}\n\n")).toBe(true)
+    expect(isCompleteBody("text/event-stream", "data: {}\n\ndata: [DONE]\n\n")).toBe(true)
+    expect(isCompleteBody("text/event-stream", "event: error\ndata: {}\n\n")).toBe(false)
+})
@@ -0,0 +1,47 @@
+import { test, expect, describe } from "bun:test"
+import { readCompleteBody } from "../src/lib/incomplete-response"
+import { classifyEndpointFailures } from "../src/lib/endpoint-failures"
+
+describe("readCompleteBody", () => {
+    test("returns null when the server closes before Content-Length bytes arrive", async () => {
+        const server = Bun.listen({
+            hostname: "127.0.0.1",
+            port: 0,
+            socket: {
+                data(socket) {
+                    socket.write("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 100\r\n\r\n{\"response\":")
+                    socket.end()
+                },
+            },
+        })
+        try {
+            const response = await fetch(`http://127.0.0.1:${server.port}/`)
+            expect(await readCompleteBody(response)).toBeNull()
+        } finally {
+            server.stop(true)
+        }
+    })
+
+    test("returns null when the body stream errors midway", async () => {
+        const stream = new ReadableStream<Uint8Array>({
+            start(controller) {
+                controller.enqueue(new TextEncoder().encode("{\"partial\":"))
+                controller.error(new Error("connection reset"))
+            },
+        })
+        expect(await readCompleteBody(new Response(stream))).toBeNull()
+    })
+
+    test("returns the body when it is complete", async () => {
+        const body = "{\"ok\":true}"
+        const response = new Response(body, { headers: { "Content-Length": String(body.length) } })
+        expect(await readCompleteBody(response)).toBe(body)
+    })
+
+    test("all-truncated failures classify as incomplete_response", () => {
+        expect(classifyEndpointFailures([
+            { endpoint: "a", kind: "incomplete_response", status: 200 },
+            { endpoint: "b", kind: "incomplete_response", status: 200 },
+        ])).toBe("incomplete_response")
+    })
+})
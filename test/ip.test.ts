@@ -0,0 +1,49 @@
+import { test, expect, describe } from "bun:test"
+import { isInCidr, isLoopback, parseCidr, parseCidrList, parseIp, resolveClientIp } from "../src/lib/ip"
+
+describe("ip utilities", () => {
+    test("parses IPv4, IPv6 and mapped addresses", () => {
+        expect(parseIp("10.0.0.1")).toEqual({ version: 4, value: 0x0a000001n })
+        expect(parseIp("::1")).toEqual({ version: 6, value: 1n })
+        expect(parseIp("::ffff:10.0.0.1")).toEqual({ version: 4, value: 0x0a000001n })
+        expect(parseIp("2001:db8::1")?.version).toBe(6)
+        expect(parseIp("256.0.0.1")).toBeNull()
+        expect(parseIp("not-an-ip")).toBeNull()
+    })
+
+    test("matches CIDR ranges", () => {
+        const v4 = parseCidr("10.1.0.0/16")!
+        expect(isInCidr(parseIp("10.1.2.3")!, v4)).toBe(true)
+        expect(isInCidr(parseIp("10.2.0.1")!, v4)).toBe(false)
+
+        const v6 = parseCidr("2001:db8::/32")!
+        expect(isInCidr(parseIp("2001:db8:1::5")!, v6)).toBe(true)
+        expect(isInCidr(parseIp("10.1.2.3")!, v6)).toBe(false)
+    })
+
+    test("detects loopback", () => {
+        expect(isLoopback(parseIp("127.0.0.5")!)).toBe(true)
+        expect(isLoopback(parseIp("::1")!)).toBe(true)
+        expect(isLoopback(parseIp("192.168.1.1")!)).toBe(false)
+    })
+})
+
+describe("resolveClientIp", () => {
+    const trusted = parseCidrList(["10.0.0.0/8", "192.168.1.1"])
+
+    test("ignores X-Forwarded-For from untrusted peers", () => {
+        expect(resolveClientIp("203.0.113.9", "1.2.3.4", trusted)).toBe("203.0.113.9")
+    })
+
+    test("returns the first untrusted hop from the right", () => {
+        expect(resolveClientIp("10.0.0.5", "198.51.100.7, 1.2.3.4, 192.168.1.1", trusted)).toBe("1.2.3.4")
+    })
+
+    test("stops at malformed entries", () => {
+        expect(resolveClientIp("10.0.0.5", "garbage, 10.1.1.1", trusted)).toBe("10.1.1.1")
+    })
+
+    test("uses the peer when no proxies are trusted", () => {
+        expect(resolveClientIp("10.0.0.5", "1.2.3.4", [])).toBe("10.0.0.5")
+    })
+})
@@ -0,0 +1,18 @@
+import { test, expect, describe } from "bun:test"
+import { computeShedFraction } from "../src/lib/load-shed"
+
+describe("computeShedFraction", () => {
+    test("no shedding below the threshold or when disabled", () => {
+        expect(computeShedFraction(40, 50, 0.9)).toBe(0)
+        expect(computeShedFraction(500, 0, 0.9)).toBe(0)
+    })
+
+    test("scales with overload severity", () => {
+        expect(computeShedFraction(75, 50, 0.9)).toBeCloseTo(0.5)
+        expect(computeShedFraction(60, 50, 0.9)).toBeCloseTo(0.2)
+    })
+
+    test("never exceeds the max fraction", () => {
+        expect(computeShedFraction(1000, 50, 0.9)).toBe(0.9)
+    })
+})
@@ -0,0 +1,31 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { getLogSampleRate, isRequestLogSampled } from "../src/lib/logger"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { makeRequestContext } from "./helpers"
+
+describe("log sampling", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_LOG_SAMPLE_RATE
+    })
+
+    test("defaults to logging everything and clamps the rate", () => {
+        expect(getLogSampleRate()).toBe(1)
+        process.env.ANTI_API_LOG_SAMPLE_RATE = "2"
+        expect(getLogSampleRate()).toBe(1)
+        process.env.ANTI_API_LOG_SAMPLE_RATE = "-1"
+        expect(getLogSampleRate()).toBe(0)
+    })
+
+    test("rate 0 suppresses request logs", () => {
+        process.env.ANTI_API_LOG_SAMPLE_RATE = "0"
+        expect(isRequestLogSampled()).toBe(false)
+    })
+
+    test("decision is sticky within a request", () => {
+        process.env.ANTI_API_LOG_SAMPLE_RATE = "0.5"
+        runWithRequestContext(makeRequestContext(), () => {
+            const first = isRequestLogSampled()
+            for (let i = 0; i < 20; i++) expect(isRequestLogSampled()).toBe(first)
+        })
+    })
+})
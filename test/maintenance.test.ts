@@ -0,0 +1,54 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { maintenanceGuard, setMaintenance } from "../src/lib/maintenance"
+import { requireAdmin, safeCompare } from "../src/lib/admin-auth"
+
+function buildApp() {
+    const app = new Hono()
+    app.use("/chat", maintenanceGuard)
+    app.post("/chat", (c) => c.json({ ok: true }))
+    app.use("/admin/*", requireAdmin)
+    app.get("/admin/ping", (c) => c.json({ ok: true }))
+    return app
+}
+
+describe("maintenance mode", () => {
+    afterEach(() => {
+        setMaintenance({ enabled: false })
+    })
+
+    test("passes requests through when disabled", async () => {
+        const res = await buildApp().request("/chat", { method: "POST" })
+        expect(res.status).toBe(200)
+    })
+
+    test("short-circuits with configured status, message and Retry-After", async () => {
+        setMaintenance({ enabled: true, status: 503, message: "upgrading", retryAfterSeconds: 120 })
+        const res = await buildApp().request("/chat", { method: "POST" })
+        expect(res.status).toBe(503)
+        expect(res.headers.get("Retry-After")).toBe("120")
+        const body = await res.json() as any
+        expect(body.error.type).toBe("maintenance")
+        expect(body.error.message).toBe("upgrading")
+    })
+})
+
+describe("admin auth", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_ADMIN_KEY
+    })
+
+    test("safeCompare matches only identical strings", () => {
+        expect(safeCompare("secret", "secret")).toBe(true)
+        expect(safeCompare("secret", "secreT")).toBe(false)
+        expect(safeCompare("secret", "secret-longer")).toBe(false)
+    })
+
+    test("rejects requests without a valid admin key", async () => {
+        process.env.ANTI_API_ADMIN_KEY = "s3cret"
+        const app = buildApp()
+        expect((await app.request("/admin/ping")).status).toBe(401)
+        expect((await app.request("/admin/ping", { headers: { "X-Admin-Key": "wrong" } })).status).toBe(401)
+        expect((await app.request("/admin/ping", { headers: { Authorization: "Bearer s3cret" } })).status).toBe(200)
+    })
+})
@@ -0,0 +1,32 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { classifyOutcome, endpointIndexLabel, modelLabel, OTHER_MODEL_LABEL, resetModelLabels } from "../src/lib/metric-labels"
+
+describe("metric labels", () => {
+    afterEach(() => {
+        resetModelLabels()
+        delete process.env.ANTI_API_METRICS_MAX_MODELS
+    })
+
+    test("classifies outcomes by status", () => {
+        expect(classifyOutcome(200)).toBe("success")
+        expect(classifyOutcome(429)).toBe("rate_limited")
+        expect(classifyOutcome(401)).toBe("auth_error")
+        expect(classifyOutcome(403)).toBe("auth_error")
+        expect(classifyOutcome(400)).toBe("bad_request")
+        expect(classifyOutcome(502)).toBe("server_error")
+        expect(classifyOutcome(499)).toBe("failed")
+    })
+
+    test("caps distinct model labels and buckets the rest into other", () => {
+        process.env.ANTI_API_METRICS_MAX_MODELS = "2"
+        expect(modelLabel("a")).toBe("a")
+        expect(modelLabel("b")).toBe("b")
+        expect(modelLabel("c")).toBe(OTHER_MODEL_LABEL)
+        expect(modelLabel("a")).toBe("a")
+    })
+
+    test("endpoint index label", () => {
+        expect(endpointIndexLabel(undefined)).toBe("none")
+        expect(endpointIndexLabel(1)).toBe("1")
+    })
+})
@@ -0,0 +1,48 @@
+import { test, expect } from "bun:test"
+import { estimateQuantile, incrementCounter, observeHistogram, renderMetrics, snapshotMetrics } from "../src/lib/metrics"
+
+test("prometheus backend renders counters with sorted labels", () => {
+    incrementCounter("test_events_total", { status: 200, method: "POST" })
+    incrementCounter("test_events_total", { method: "POST", status: 200 }, 2)
+
+    const body = renderMetrics()
+    expect(body).toContain("# TYPE anti_api_test_events_total counter")
+    expect(body).toContain('anti_api_test_events_total{method="POST",status="200"} 3')
+})
+
+test("prometheus backend renders histogram buckets", () => {
+    observeHistogram("test_duration_seconds", 0.2, { route: "/v1/messages" })
+
+    const body = renderMetrics()!
+    expect(body).toContain('anti_api_test_duration_seconds_bucket{route="/v1/messages",le="0.25"} 1')
+    expect(body).toContain('anti_api_test_duration_seconds_bucket{route="/v1/messages",le="0.1"} 0')
+    expect(body).toContain('anti_api_test_duration_seconds_count{route="/v1/messages"} 1')
+})
+
+test("byte histograms use byte-sized buckets", () => {
+    observeHistogram("test_payload_bytes", 3000, { model: "m" })
+
+    const body = renderMetrics()!
+    expect(body).toContain('anti_api_test_payload_bytes_bucket{model="m",le="1024"} 0')
+    expect(body).toContain('anti_api_test_payload_bytes_bucket{model="m",le="4096"} 1')
+    expect(body).toContain('anti_api_test_payload_bytes_sum{model="m"} 3000')
+})
+
+test("json snapshot mirrors the prometheus registry", () => {
+    incrementCounter("test_snapshot_total", { kind: "a" }, 4)
+    observeHistogram("test_snapshot_seconds", 0.3)
+
+    const snapshot = snapshotMetrics()!
+    expect(snapshot.counters.anti_api_test_snapshot_total).toEqual([{ labels: { kind: "a" }, value: 4 }])
+    const [histogram] = snapshot.histograms.anti_api_test_snapshot_seconds
+    expect(histogram.count).toBe(1)
+    expect(histogram.buckets.at(-1)).toEqual({ le: "+Inf", count: 1 })
+    expect(histogram.quantiles.p50).toBeGreaterThan(0.25)
+    expect(histogram.quantiles.p50).toBeLessThanOrEqual(0.5)
+})
+
+test("quantiles interpolate within buckets", () => {
+    expect(estimateQuantile(0.5, [1, 2], [0, 4], 4)).toBe(1.5)
+    expect(estimateQuantile(0.99, [1, 2], [0, 4], 5)).toBe(2)
+    expect(estimateQuantile(0.5, [1], [0], 0)).toBeNull()
+})
@@ -0,0 +1,38 @@
+import { test, expect, afterEach } from "bun:test"
+import { endpointIndexLabel } from "../src/lib/metric-labels"
+import { applyOverflowRouting, OVERFLOW_ENDPOINT_INDEX } from "../src/lib/overflow"
+import { getEndpointTimeoutMs } from "../src/lib/upstream-timeouts"
+
+const PRIMARY = ["https://primary.example.com", "https://backup.example.com"]
+
+afterEach(() => {
+    delete process.env.ANTI_API_OVERFLOW_ENDPOINT
+    delete process.env.ANTI_API_OVERFLOW_THRESHOLD
+    delete process.env.ANTI_API_ENDPOINT_TIMEOUTS_MS
+})
+
+test("overflow endpoint is used only above the in-flight threshold", () => {
+    expect(applyOverflowRouting(PRIMARY, 100)).toEqual([[0, PRIMARY[0]], [1, PRIMARY[1]]])
+
+    process.env.ANTI_API_OVERFLOW_ENDPOINT = "https://spill.example.com/"
+    process.env.ANTI_API_OVERFLOW_THRESHOLD = "4"
+    expect(applyOverflowRouting(PRIMARY, 4)).toEqual([[0, PRIMARY[0]], [1, PRIMARY[1]]])
+    expect(applyOverflowRouting(PRIMARY, 5)).toEqual([
+        [OVERFLOW_ENDPOINT_INDEX, "https://spill.example.com"],
+        [0, PRIMARY[0]],
+        [1, PRIMARY[1]],
+    ])
+})
+
+test("primary endpoints keep their own index, timeout and label while overflowing", () => {
+    process.env.ANTI_API_OVERFLOW_ENDPOINT = "https://spill.example.com"
+    process.env.ANTI_API_OVERFLOW_THRESHOLD = "1"
+    process.env.ANTI_API_ENDPOINT_TIMEOUTS_MS = "0=8000,1=60000,overflow=3000"
+
+    const routed = applyOverflowRouting(PRIMARY, 2)
+    expect(routed.map(([index, url]) => [endpointIndexLabel(index), getEndpointTimeoutMs(url, index)])).toEqual([
+        ["overflow", 3000],
+        ["0", 8000],
+        ["1", 60000],
+    ])
+})
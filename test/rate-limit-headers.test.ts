@@ -0,0 +1,28 @@
+import { test, expect, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { rateLimitHeaders } from "../src/lib/rate-limit-headers"
+import { globalSemaphore } from "../src/lib/concurrency"
+
+afterEach(() => {
+    delete process.env.ANTI_API_RATELIMIT_HEADERS
+    globalSemaphore.setLimit(0)
+})
+
+test("rate-limit headers are opt-in and reflect permit state", async () => {
+    const app = new Hono()
+    app.use(rateLimitHeaders)
+    app.post("/", (c) => c.json({ ok: true }))
+
+    const silent = await app.request("/", { method: "POST" })
+    expect(silent.headers.get("X-RateLimit-Min-Interval-Ms")).toBeNull()
+
+    process.env.ANTI_API_RATELIMIT_HEADERS = "1"
+    globalSemaphore.setLimit(4)
+    const release = globalSemaphore.tryAcquire()!
+    const res = await app.request("/", { method: "POST" })
+    release()
+    expect(res.headers.get("X-RateLimit-Limit")).toBe("4")
+    expect(res.headers.get("X-RateLimit-Remaining")).toBe("3")
+    expect(res.headers.get("X-RateLimit-Min-Interval-Ms")).not.toBeNull()
+    expect(res.headers.get("X-RateLimit-Streams-Remaining")).toBeNull()
+})
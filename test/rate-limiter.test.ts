@@ -1,4 +1,6 @@
 import { test, expect } from "bun:test"
+import { RateLimiter } from "../src/lib/rate-limiter"
+import { MockClock } from "../src/lib/clock"
 
 // Create a test-specific RateLimiter class to avoid singleton issues
 class TestRateLimiter {
@@ -140,3 +142,30 @@ test("RateLimiter handles concurrent requests sequentially", async () => {
     // All should complete in order
     expect(results.length).toBe(3)
 })
+
+test("RateLimiter enforces the interval against an injected clock", async () => {
+    const clock = new MockClock(1_000)
+    const limiter = new RateLimiter(1000, clock)
+
+    await limiter.wait()
+    let secondDone = false
+    const second = limiter.wait().then(() => { secondDone = true })
+
+    await clock.advance(999)
+    expect(secondDone).toBe(false)
+
+    await clock.advance(1)
+    await second
+    expect(secondDone).toBe(true)
+    expect(clock.now()).toBe(2_000)
+})
+
+test("RateLimiter does not wait once the interval has already elapsed", async () => {
+    const clock = new MockClock(0)
+    const limiter = new RateLimiter(500, clock)
+
+    await limiter.wait()
+    await clock.advance(600)
+    await limiter.wait()
+    expect(clock.pendingSleepers).toBe(0)
+})
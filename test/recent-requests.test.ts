@@ -0,0 +1,66 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { RingBuffer } from "../src/lib/recent-requests"
+import { getRequestIdHeader, trackResponseCompletion } from "../src/lib/request-context"
+import { getBenchmarkStats, recordBenchmarkSample, resetBenchmarkStats } from "../src/lib/benchmark"
+
+describe("RingBuffer", () => {
+    test("keeps the newest entries first and drops the oldest", () => {
+        const buffer = new RingBuffer<number>(3)
+        for (let i = 1; i <= 5; i++) buffer.push(i)
+        expect(buffer.size).toBe(3)
+        expect(buffer.recent()).toEqual([5, 4, 3])
+        expect(buffer.recent(2)).toEqual([5, 4])
+    })
+
+    test("zero capacity stores nothing", () => {
+        const buffer = new RingBuffer<number>(0)
+        buffer.push(1)
+        expect(buffer.recent()).toEqual([])
+    })
+})
+
+describe("trackResponseCompletion", () => {
+    test("reports byte count once the body is consumed", async () => {
+        let outcome: { cancelled: boolean; bytes: number } | null = null
+        const res = trackResponseCompletion(new Response("hello"), o => { outcome = o })
+        expect(await res.text()).toBe("hello")
+        expect(outcome).toEqual({ cancelled: false, bytes: 5 })
+    })
+
+    test("reports cancellation when the client goes away", async () => {
+        let outcome: { cancelled: boolean; bytes: number } | null = null
+        const res = trackResponseCompletion(new Response("hello"), o => { outcome = o })
+        await res.body!.cancel()
+        expect(outcome!.cancelled).toBe(true)
+    })
+})
+
+describe("benchmark stats", () => {
+    test("reports overhead percentiles", () => {
+        resetBenchmarkStats()
+        for (let i = 1; i <= 100; i++) recordBenchmarkSample(i)
+        const stats = getBenchmarkStats()
+        expect(stats.samples).toBe(100)
+        expect(stats.overheadMs).toEqual({ p50: 50, p90: 90, p99: 99, max: 100 })
+
+        resetBenchmarkStats()
+        expect(getBenchmarkStats().overheadMs.p50).toBeNull()
+    })
+})
+
+describe("getRequestIdHeader", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_REQUEST_ID_HEADER
+    })
+
+    test("defaults to X-Request-Id and accepts valid header names", () => {
+        expect(getRequestIdHeader()).toBe("X-Request-Id")
+        process.env.ANTI_API_REQUEST_ID_HEADER = "X-Correlation-Id"
+        expect(getRequestIdHeader()).toBe("X-Correlation-Id")
+    })
+
+    test("ignores invalid header names", () => {
+        process.env.ANTI_API_REQUEST_ID_HEADER = "bad header: x"
+        expect(getRequestIdHeader()).toBe("X-Request-Id")
+    })
+})
@@ -0,0 +1,32 @@
+import { test, expect, describe } from "bun:test"
+import { applyRequestFieldPolicy } from "../src/lib/request-field-policy"
+
+function sampleRequest() {
+    return {
+        contents: [{ role: "user", parts: [{ text: "hi" }] }],
+        tools: [{ functionDeclarations: [] }],
+        generationConfig: { maxOutputTokens: 64000, temperature: 1 },
+    }
+}
+
+describe("request field policy", () => {
+    test("denied fields are rejected with the offending path", () => {
+        expect(() => applyRequestFieldPolicy(sampleRequest(), { deny: ["tools"], strip: [], allow: [] }))
+            .toThrow(/"tools"/)
+        expect(() => applyRequestFieldPolicy(sampleRequest(), { deny: ["toolConfig"], strip: [], allow: [] }))
+            .not.toThrow()
+    })
+
+    test("stripped nested fields are removed", () => {
+        const request = sampleRequest()
+        expect(applyRequestFieldPolicy(request, { deny: [], strip: ["generationConfig.maxOutputTokens"], allow: [] }))
+            .toEqual(["generationConfig.maxOutputTokens"])
+        expect(request.generationConfig).toEqual({ temperature: 1 })
+    })
+
+    test("allowlist drops unlisted top-level fields", () => {
+        const request: Record<string, unknown> = sampleRequest()
+        applyRequestFieldPolicy(request, { deny: [], strip: [], allow: ["contents", "generationConfig"] })
+        expect(Object.keys(request).sort()).toEqual(["contents", "generationConfig"])
+    })
+})
@@ -0,0 +1,39 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { forwardError, UpstreamError } from "../src/lib/error"
+import { classifyOutcome } from "../src/lib/metric-labels"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { measureRequestSize, noteRequestTooLarge } from "../src/lib/request-too-large"
+import { makeRequestContext } from "./helpers"
+
+function createApp() {
+    const app = new Hono()
+    app.post("/", (c) => {
+        noteRequestTooLarge(JSON.stringify({ model: "m", request: { contents: ["x".repeat(100)] } }))
+        return forwardError(c, new UpstreamError("antigravity", 413, "Request Entity Too Large"))
+    })
+    return app
+}
+
+describe("upstream 413", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_REPORT_REQUEST_SIZE
+    })
+
+    test("surfaces request_too_large and a distinct metrics outcome", async () => {
+        const response = await runWithRequestContext(makeRequestContext({ upstreamCalls: 1 }), () => createApp().request("/", { method: "POST" }))
+        expect(response.status).toBe(413)
+        const body = await response.json() as any
+        expect(body.error.error_code).toBe("request_too_large")
+        expect(body.error.request_size).toBeUndefined()
+        expect(classifyOutcome(413)).toBe("request_too_large")
+    })
+
+    test("includes the computed sizes when configured", async () => {
+        process.env.ANTI_API_REPORT_REQUEST_SIZE = "true"
+        const response = await runWithRequestContext(makeRequestContext({ upstreamCalls: 1 }), () => createApp().request("/", { method: "POST" }))
+        const body = await response.json() as any
+        expect(body.error.request_size).toEqual(measureRequestSize(JSON.stringify({ model: "m", request: { contents: ["x".repeat(100)] } })))
+        expect(body.error.request_size.request_bytes).toBeLessThan(body.error.request_size.body_bytes)
+    })
+})
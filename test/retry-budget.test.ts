@@ -0,0 +1,46 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { retryBudget } from "../src/lib/retry"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { createChatCompletionWithOptions } from "../src/services/antigravity/chat"
+import { dataFrame, installFakeUpstream, sseResponse, statusResponse, type FakeUpstream } from "./fake-upstream"
+import { makeRequestContext } from "./helpers"
+
+const request = { model: "claude-sonnet-4-5", messages: [{ role: "user" as const, content: "hi" }] }
+
+describe("retry budget", () => {
+    let upstream: FakeUpstream | undefined
+
+    afterEach(() => {
+        upstream?.restore()
+        upstream = undefined
+        delete process.env.ANTI_API_RETRIES
+        delete process.env.ANTI_API_RETRY_BUDGET
+        delete process.env.ANTI_API_RETRY_BUDGET_MAX
+        retryBudget.reset()
+    })
+
+    test("defaults to on only when retries are enabled", () => {
+        process.env.ANTI_API_RETRY_BUDGET_MAX = "0"
+        retryBudget.reset()
+        expect(retryBudget.tryConsume()).toBe(false)
+
+        process.env.ANTI_API_RETRIES = "false"
+        expect(retryBudget.tryConsume()).toBe(true)
+
+        process.env.ANTI_API_RETRY_BUDGET = "true"
+        expect(retryBudget.tryConsume()).toBe(false)
+    })
+
+    test("an empty budget does not stop failover to the next endpoint", async () => {
+        process.env.ANTI_API_RETRY_BUDGET_MAX = "0"
+        retryBudget.reset()
+        upstream = installFakeUpstream((url) => url.hostname === "a.test"
+            ? statusResponse(503, "unavailable")
+            : sseResponse([dataFrame("from b")]))
+
+        const result = await runWithRequestContext(makeRequestContext(), () => createChatCompletionWithOptions(request))
+        expect(result.contentBlocks).toEqual([{ type: "text", text: "from b" }])
+        expect(upstream.calls).toEqual(["a.test", "b.test"])
+        expect(retryBudget.remaining).toBe(0)
+    })
+})
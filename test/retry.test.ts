@@ -1,5 +1,5 @@
 import { test, expect } from "bun:test"
-import { parseDurationMs, parseRetryDelay } from "../src/lib/retry"
+import { parseDurationMs, parseRetryDelay, RetryBudget } from "../src/lib/retry"
 
 test("parseDurationMs handles composite durations", () => {
     expect(parseDurationMs("200ms")).toBe(200)
@@ -46,3 +46,22 @@ test("parseRetryDelay reads quotaResetDelay metadata", () => {
 test("parseRetryDelay parses text fallback", () => {
     expect(parseRetryDelay("try again in 2m 3s")).toBe(123000)
 })
+
+test("RetryBudget fails fast when empty and refills on success", () => {
+    const budget = new RetryBudget(() => 2, () => 0.5, () => true)
+
+    expect(budget.tryConsume()).toBe(true)
+    expect(budget.tryConsume()).toBe(true)
+    expect(budget.tryConsume()).toBe(false)
+
+    budget.recordSuccess()
+    expect(budget.tryConsume()).toBe(false)
+    budget.recordSuccess()
+    expect(budget.tryConsume()).toBe(true)
+})
+
+test("RetryBudget always allows retries when disabled", () => {
+    const budget = new RetryBudget(() => 0, () => 0, () => false)
+
+    expect(budget.tryConsume()).toBe(true)
+})
@@ -0,0 +1,105 @@
+import { test, expect, describe } from "bun:test"
+import { Semaphore } from "../src/lib/semaphore"
+
+describe("Semaphore", () => {
+    test("limits concurrent holders and hands permits to waiters in order", async () => {
+        const semaphore = new Semaphore(1)
+        const order: string[] = []
+
+        const releaseA = await semaphore.acquire()
+        const pendingB = semaphore.acquire().then(release => {
+            order.push("b")
+            return release
+        })
+        expect(semaphore.inUse).toBe(1)
+        expect(semaphore.waiting).toBe(1)
+        expect(semaphore.tryAcquire()).toBeNull()
+
+        releaseA()
+        const releaseB = await pendingB
+        expect(order).toEqual(["b"])
+        expect(semaphore.inUse).toBe(1)
+
+        releaseB()
+        expect(semaphore.isIdle()).toBe(true)
+    })
+
+    test("release is idempotent", async () => {
+        const semaphore = new Semaphore(2)
+        const release = await semaphore.acquire()
+        release()
+        release()
+        expect(semaphore.inUse).toBe(0)
+    })
+
+    test("zero limit means unlimited", () => {
+        const semaphore = new Semaphore(0)
+        for (let i = 0; i < 10; i++) {
+            expect(semaphore.tryAcquire()).not.toBeNull()
+        }
+        expect(semaphore.inUse).toBe(10)
+    })
+
+    test("raising the limit wakes waiters", async () => {
+        const semaphore = new Semaphore(1)
+        await semaphore.acquire()
+        const pending = semaphore.acquire()
+        semaphore.setLimit(2)
+        await pending
+        expect(semaphore.inUse).toBe(2)
+    })
+
+    test("waitForAvailable resolves on release and times out otherwise", async () => {
+        const semaphore = new Semaphore(1)
+        const release = await semaphore.acquire()
+
+        expect(await semaphore.waitForAvailable(10)).toBe(false)
+
+        const pending = semaphore.waitForAvailable(1000)
+        release()
+        expect(await pending).toBe(true)
+        expect(semaphore.available).toBe(1)
+    })
+})
+
+describe("Semaphore.acquireWithTimeout", () => {
+    test("returns null and leaves the queue when the wait times out", async () => {
+        const semaphore = new Semaphore(1)
+        const release = await semaphore.acquire()
+
+        expect(await semaphore.acquireWithTimeout(10)).toBeNull()
+        expect(semaphore.waiting).toBe(0)
+
+        const pending = semaphore.acquireWithTimeout(1000)
+        release()
+        const granted = await pending
+        expect(granted).not.toBeNull()
+        expect(semaphore.inUse).toBe(1)
+    })
+})
+
+describe("Semaphore queue policy", () => {
+    test("lifo hands the freed permit to the newest waiter", async () => {
+        const semaphore = new Semaphore(1)
+        semaphore.setQueuePolicy("lifo")
+        const release = await semaphore.acquire()
+        const order: string[] = []
+        const older = semaphore.acquire().then(next => { order.push("older"); return next })
+        const newer = semaphore.acquire().then(next => { order.push("newer"); return next })
+
+        release()
+        const releaseNewer = await newer
+        expect(order).toEqual(["newer"])
+        releaseNewer()
+        await older
+        expect(order).toEqual(["newer", "older"])
+    })
+
+    test("lifo waiters still time out", async () => {
+        const semaphore = new Semaphore(1)
+        semaphore.setQueuePolicy("lifo")
+        await semaphore.acquire()
+        expect(await semaphore.acquireWithTimeout(10)).toBeNull()
+        expect(semaphore.waiting).toBe(0)
+    })
+})
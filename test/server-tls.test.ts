@@ -0,0 +1,59 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { mkdtempSync, writeFileSync } from "fs"
+import { tmpdir } from "os"
+import { join } from "path"
+import { constants } from "crypto"
+import { buildSecureOptions, getMinTlsVersion, getServerTlsOptions } from "../src/lib/server-tls"
+
+const KEYS = ["ANTI_API_TLS_CERT", "ANTI_API_TLS_KEY", "ANTI_API_MTLS_CA", "ANTI_API_MIN_TLS_VERSION"]
+
+describe("getServerTlsOptions", () => {
+    afterEach(() => {
+        for (const key of KEYS) delete process.env[key]
+    })
+
+    test("plain HTTP when nothing is configured", () => {
+        expect(getServerTlsOptions()).toBeUndefined()
+    })
+
+    test("requires cert and key together", () => {
+        process.env.ANTI_API_TLS_CERT = "/tmp/cert.pem"
+        expect(() => getServerTlsOptions()).toThrow(/must be set together/)
+    })
+
+    test("mTLS CA without a server cert is rejected", () => {
+        process.env.ANTI_API_MTLS_CA = "/tmp/ca.pem"
+        expect(() => getServerTlsOptions()).toThrow(/requires/)
+    })
+
+    test("enables client certificate verification when a CA is set", () => {
+        const dir = mkdtempSync(join(tmpdir(), "anti-api-tls-"))
+        for (const name of ["cert", "key", "ca"]) writeFileSync(join(dir, `${name}.pem`), `${name}-pem`)
+        process.env.ANTI_API_TLS_CERT = join(dir, "cert.pem")
+        process.env.ANTI_API_TLS_KEY = join(dir, "key.pem")
+        process.env.ANTI_API_MTLS_CA = join(dir, "ca.pem")
+
+        expect(getServerTlsOptions()).toEqual({
+            cert: "cert-pem",
+            key: "key-pem",
+            ca: "ca-pem",
+            requestCert: true,
+            rejectUnauthorized: true,
+            secureOptions: buildSecureOptions("1.2"),
+        })
+    })
+
+    test("minimum TLS version defaults to 1.2 and rejects unknown values", () => {
+        expect(getMinTlsVersion()).toBe("1.2")
+        process.env.ANTI_API_MIN_TLS_VERSION = "TLSv1.3"
+        expect(getMinTlsVersion()).toBe("1.3")
+        process.env.ANTI_API_MIN_TLS_VERSION = "1.0"
+        expect(() => getMinTlsVersion()).toThrow(/ANTI_API_MIN_TLS_VERSION/)
+    })
+
+    test("1.3-only also disables TLS 1.2", () => {
+        expect(buildSecureOptions("1.2") & constants.SSL_OP_NO_TLSv1_2).toBe(0)
+        expect(buildSecureOptions("1.3") & constants.SSL_OP_NO_TLSv1_2).toBe(constants.SSL_OP_NO_TLSv1_2)
+        expect(buildSecureOptions("1.2") & constants.SSL_OP_NO_TLSv1_1).toBe(constants.SSL_OP_NO_TLSv1_1)
+    })
+})
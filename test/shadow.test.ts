@@ -0,0 +1,57 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { buildShadowUrl, getShadowInFlight, getShadowPercent, mirrorToShadow, shouldShadow } from "../src/services/antigravity/shadow"
+
+describe("shadow traffic", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_SHADOW_ENDPOINT
+        delete process.env.ANTI_API_SHADOW_PCT
+        delete process.env.ANTI_API_SHADOW_MAX_CONCURRENCY
+    })
+
+    test("keeps path and query when rewriting to the shadow endpoint", () => {
+        expect(buildShadowUrl("https://cloudcode-pa.googleapis.com/v1internal:streamGenerateContent?alt=sse", "https://canary.example.com"))
+            .toBe("https://canary.example.com/v1internal:streamGenerateContent?alt=sse")
+    })
+
+    test("samples by percentage only when an endpoint is configured", () => {
+        process.env.ANTI_API_SHADOW_PCT = "25"
+        expect(shouldShadow(0.1)).toBe(false)
+
+        process.env.ANTI_API_SHADOW_ENDPOINT = "https://canary.example.com/"
+        expect(shouldShadow(0.1)).toBe(true)
+        expect(shouldShadow(0.5)).toBe(false)
+    })
+
+    test("clamps the percentage", () => {
+        process.env.ANTI_API_SHADOW_PCT = "250"
+        expect(getShadowPercent()).toBe(100)
+        process.env.ANTI_API_SHADOW_PCT = "abc"
+        expect(getShadowPercent()).toBe(0)
+    })
+
+    test("skips shadowing instead of queueing when saturated", async () => {
+        let releaseShadow: () => void = () => { }
+        const held = new Promise<void>(resolve => { releaseShadow = resolve })
+        const server = Bun.serve({
+            port: 0,
+            fetch: async () => {
+                await held
+                return new Response("ok")
+            },
+        })
+        try {
+            process.env.ANTI_API_SHADOW_ENDPOINT = `http://127.0.0.1:${server.port}`
+            process.env.ANTI_API_SHADOW_MAX_CONCURRENCY = "1"
+
+            mirrorToShadow("https://primary.example.com/v1internal:generateContent", { method: "POST", body: "{}" }, 200)
+            mirrorToShadow("https://primary.example.com/v1internal:generateContent", { method: "POST", body: "{}" }, 200)
+            expect(getShadowInFlight()).toBe(1)
+
+            releaseShadow()
+            for (let i = 0; i < 50 && getShadowInFlight() > 0; i++) await Bun.sleep(10)
+            expect(getShadowInFlight()).toBe(0)
+        } finally {
+            server.stop(true)
+        }
+    })
+})
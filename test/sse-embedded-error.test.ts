@@ -0,0 +1,25 @@
+import { test, expect, describe } from "bun:test"
+import { findEmbeddedSseError } from "../src/lib/sse-embedded-error"
+
+describe("findEmbeddedSseError", () => {
+    test("returns null for a normal stream", () => {
+        const body = 'data: {"response":{"candidates":[{"content":{"parts":[{"text":"hi"}]}}]}}\n\ndata: [DONE]\n\n'
+        expect(findEmbeddedSseError(body)).toBeNull()
+    })
+
+    test("detects a top-level error frame with a numeric code", () => {
+        const body = 'data: {"response":{"candidates":[]}}\n\ndata: {"error":{"code":503,"message":"backend overloaded","status":"UNAVAILABLE"}}\n\n'
+        const found = findEmbeddedSseError(body)
+        expect(found?.status).toBe(503)
+        expect(found?.message).toBe("backend overloaded")
+    })
+
+    test("maps rpc status names when no HTTP code is given", () => {
+        const body = 'data: {"response":{"error":{"status":"RESOURCE_EXHAUSTED","message":"quota"}}}\n\n'
+        expect(findEmbeddedSseError(body)?.status).toBe(429)
+    })
+
+    test("falls back to 502 for unknown errors", () => {
+        expect(findEmbeddedSseError('data: {"error":{"message":"boom"}}\n\n')?.status).toBe(502)
+    })
+})
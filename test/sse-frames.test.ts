@@ -0,0 +1,98 @@
+import { test, expect, describe } from "bun:test"
+import { bufferedBodyToSse, isEventStreamContentType, readTextWithIdleTimeout, splitSseFrames, SseFrameSplitter } from "../src/lib/sse-frames"
+
+describe("SseFrameSplitter", () => {
+    test("splits frames across chunk boundaries", () => {
+        const splitter = new SseFrameSplitter(1024)
+        expect(splitter.push("data: {\"a\":1}\n\ndata: {\"b\"")).toEqual(["data: {\"a\":1}"])
+        expect(splitter.push(":2}\r\n\r\n")).toEqual(["data: {\"b\":2}"])
+        expect(splitter.flush()).toBe("")
+    })
+
+    test("skips an oversized frame without buffering it and resumes at the next boundary", () => {
+        const oversized: number[] = []
+        const splitter = new SseFrameSplitter(64, bytes => oversized.push(bytes))
+        const big = "data: " + "x".repeat(100)
+
+        expect(splitter.push("data: {\"ok\":1}\n\n" + big.slice(0, 80))).toEqual(["data: {\"ok\":1}"])
+        expect(oversized.length).toBe(1)
+        expect(splitter.push(big.slice(80))).toEqual([])
+        expect(splitter.push("\n\ndata: {\"ok\":2}\n\n")).toEqual(["data: {\"ok\":2}"])
+        expect(oversized.length).toBe(1)
+    })
+
+    test("drops complete oversized frames", () => {
+        const oversized: number[] = []
+        const splitter = new SseFrameSplitter(16, bytes => oversized.push(bytes))
+        expect(splitter.push("data: " + "y".repeat(40) + "\n\ndata: 1\n\n")).toEqual(["data: 1"])
+        expect(oversized).toEqual([46])
+    })
+
+    test("error mode surfaces as a thrown error from the callback", () => {
+        const splitter = new SseFrameSplitter(8, () => {
+            throw new Error("sse_frame_too_large")
+        })
+        expect(() => splitter.push("data: 0123456789")).toThrow("sse_frame_too_large")
+    })
+})
+
+describe("stream downgrade helpers", () => {
+    test("detects non-SSE content types", () => {
+        expect(isEventStreamContentType("text/event-stream; charset=utf-8")).toBe(true)
+        expect(isEventStreamContentType(null)).toBe(true)
+        expect(isEventStreamContentType("application/json")).toBe(false)
+    })
+
+    test("converts buffered JSON bodies into SSE frames", () => {
+        expect(bufferedBodyToSse('{"response":{"a":1}}')).toBe('data: {"response":{"a":1}}\n\n')
+        expect(bufferedBodyToSse('[{"a":1},{"b":2}]')).toBe('data: {"a":1}\n\ndata: {"b":2}\n\n')
+        expect(bufferedBodyToSse("data: x\n\n")).toBe("data: x\n\n")
+    })
+
+    test("buffers a complete body across chunks", async () => {
+        const body = new ReadableStream<Uint8Array>({
+            start(controller) {
+                controller.enqueue(new TextEncoder().encode('{"response":'))
+                controller.enqueue(new TextEncoder().encode('{"a":1}}'))
+                controller.close()
+            },
+        })
+        let idled = false
+        expect(await readTextWithIdleTimeout(new Response(body), 1000, () => { idled = true })).toBe('{"response":{"a":1}}')
+        expect(idled).toBe(false)
+    })
+
+    test("gives up on a stalled body after the idle timeout", async () => {
+        let cancelled = false
+        const body = new ReadableStream<Uint8Array>({
+            start(controller) {
+                controller.enqueue(new TextEncoder().encode('{"partial":'))
+            },
+            cancel() { cancelled = true },
+        })
+        let idled = false
+        const startedAt = Date.now()
+        await expect(readTextWithIdleTimeout(new Response(body), 50, () => { idled = true })).rejects.toThrow("Stream idle timeout")
+        expect(Date.now() - startedAt).toBeLessThan(1000)
+        expect(idled).toBe(true)
+        expect(cancelled).toBe(true)
+    })
+})
+
+describe("splitSseFrames", () => {
+    test("stops at the frame limit and flags truncation", () => {
+        const raw = Array.from({ length: 50000 }, (_, i) => `data: {"i":${i}}`).join("\n\n") + "\n\n"
+        const capped = splitSseFrames(raw, 1000)
+        expect(capped.truncated).toBe(true)
+        expect(capped.frames).toHaveLength(1000)
+        expect(capped.frames[999]).toBe("data: {\"i\":999}")
+
+        const unlimited = splitSseFrames(raw, 0)
+        expect(unlimited.truncated).toBe(false)
+        expect(unlimited.frames).toHaveLength(50000)
+    })
+
+    test("exactly the limit is not truncated and blank frames do not count", () => {
+        expect(splitSseFrames("data: 1\r\n\r\n\n\ndata: 2\n\n", 2)).toEqual({ frames: ["data: 1", "data: 2"], truncated: false })
+    })
+})
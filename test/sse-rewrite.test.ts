@@ -0,0 +1,23 @@
+import { test, expect } from "bun:test"
+import { rewriteSseModel } from "../src/lib/sse-rewrite"
+
+test("rewriteSseModel rewrites message_start model", () => {
+    const event = "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4-5-thinking\"}}\n\n"
+    const rewritten = rewriteSseModel(event, "Claude-Sonnet-4-5")
+
+    expect(rewritten.startsWith("event: message_start\n")).toBe(true)
+    const data = JSON.parse(rewritten.split("\n")[1].slice(6))
+    expect(data.message.model).toBe("Claude-Sonnet-4-5")
+    expect(rewritten.endsWith("\n\n")).toBe(true)
+})
+
+test("rewriteSseModel passes through frames without model or non-JSON data", () => {
+    const delta = "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0}\n\n"
+    expect(rewriteSseModel(delta, "x")).toBe(delta)
+
+    const ping = ": ping\n\n"
+    expect(rewriteSseModel(ping, "x")).toBe(ping)
+
+    const broken = "data: {\"model\": not json\n\n"
+    expect(rewriteSseModel(broken, "x")).toBe(broken)
+})
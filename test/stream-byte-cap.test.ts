@@ -0,0 +1,36 @@
+import { test, expect, describe } from "bun:test"
+import { capStreamBytes } from "../src/lib/stream-byte-cap"
+
+async function* source(events: string[], consumed: string[]) {
+    for (const event of events) {
+        consumed.push(event)
+        yield event
+    }
+}
+
+async function collect(events: AsyncIterable<string>) {
+    const out: string[] = []
+    try {
+        for await (const event of events) out.push(event)
+    } catch (error) {
+        return { out, error: error as { code?: string } }
+    }
+    return { out, error: undefined }
+}
+
+describe("capStreamBytes", () => {
+    test("passes everything through when unlimited", async () => {
+        const consumed: string[] = []
+        const { out, error } = await collect(capStreamBytes(source(["aaaa", "bbbb"], consumed), 0))
+        expect(out).toEqual(["aaaa", "bbbb"])
+        expect(error).toBeUndefined()
+    })
+
+    test("stops reading upstream once the cap is exceeded", async () => {
+        const consumed: string[] = []
+        const { out, error } = await collect(capStreamBytes(source(["aaaa", "bbbb", "cccc", "dddd"], consumed), 6))
+        expect(out).toEqual(["aaaa"])
+        expect(error?.code).toBe("stream_too_large")
+        expect(consumed).toEqual(["aaaa", "bbbb"])
+    })
+})
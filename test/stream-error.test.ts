@@ -0,0 +1,26 @@
+import { test, expect, describe } from "bun:test"
+import { formatStreamErrorFrame } from "../src/lib/stream-error"
+import { AntigravityError, UpstreamError } from "../src/lib/error"
+
+describe("stream error frames", () => {
+    test("anthropic frames use the error event", () => {
+        const frame = formatStreamErrorFrame(new AntigravityError("Upstream returned an empty response", "empty_response", 502), "anthropic")
+        expect(frame.startsWith("event: error\ndata: ")).toBe(true)
+        expect(frame.endsWith("\n\n")).toBe(true)
+        const data = JSON.parse(frame.split("data: ")[1])
+        expect(data).toEqual({ type: "error", error: { type: "empty_response", message: "Upstream returned an empty response", status_code: 502 } })
+    })
+
+    test("openai frames carry upstream details", () => {
+        const frame = formatStreamErrorFrame(new UpstreamError("antigravity", 503, "unavailable", undefined, "goog-1"), "openai")
+        const data = JSON.parse(frame.slice("data: ".length))
+        expect(data.error.type).toBe("upstream_error")
+        expect(data.error.status_code).toBe(503)
+        expect(data.error.upstream_request_id).toBe("goog-1")
+    })
+
+    test("unknown errors become stream_error", () => {
+        const frame = formatStreamErrorFrame(new Error("socket hang up"), "openai")
+        expect(JSON.parse(frame.slice(6)).error).toEqual({ type: "stream_error", message: "socket hang up" })
+    })
+})
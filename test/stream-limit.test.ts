@@ -0,0 +1,36 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { acquireStreamSlot } from "../src/lib/concurrency"
+import { AntigravityError } from "../src/lib/error"
+
+describe("acquireStreamSlot", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_MAX_CONCURRENT_STREAMS
+        delete process.env.ANTI_API_STREAM_LIMIT_MODE
+    })
+
+    test("rejects excess streams with too_many_streams", () => {
+        process.env.ANTI_API_MAX_CONCURRENT_STREAMS = "1"
+        const release = acquireStreamSlot()
+        expect(release).not.toBeNull()
+        try {
+            acquireStreamSlot()
+            throw new Error("expected rejection")
+        } catch (error) {
+            expect(error).toBeInstanceOf(AntigravityError)
+            expect((error as AntigravityError).code).toBe("too_many_streams")
+            expect((error as AntigravityError).status).toBe(503)
+        }
+        release!()
+        const next = acquireStreamSlot()
+        expect(next).not.toBeNull()
+        next!()
+    })
+
+    test("buffered mode signals a downgrade instead of rejecting", () => {
+        process.env.ANTI_API_MAX_CONCURRENT_STREAMS = "1"
+        process.env.ANTI_API_STREAM_LIMIT_MODE = "buffered"
+        const release = acquireStreamSlot()
+        expect(acquireStreamSlot()).toBeNull()
+        release!()
+    })
+})
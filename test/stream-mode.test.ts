@@ -0,0 +1,47 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { resolveStreamMode, resolveUpstreamMode } from "../src/lib/stream-mode"
+
+describe("resolveStreamMode", () => {
+    test("explicit stream field wins over Accept", () => {
+        expect(resolveStreamMode(false, "text/event-stream")).toBe(false)
+        expect(resolveStreamMode(true, "application/json")).toBe(true)
+    })
+
+    test("falls back to the Accept header when stream is absent", () => {
+        expect(resolveStreamMode(undefined, "text/event-stream")).toBe(true)
+        expect(resolveStreamMode(undefined, "application/json, text/plain")).toBe(false)
+        expect(resolveStreamMode(undefined, "text/event-stream;q=0.9, application/json")).toBe(true)
+    })
+
+    test("defaults to buffered", () => {
+        expect(resolveStreamMode(undefined, undefined)).toBe(false)
+        expect(resolveStreamMode(undefined, "*/*")).toBe(false)
+    })
+})
+
+describe("resolveUpstreamMode", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_STREAM_UPSTREAM_MODE
+        delete process.env.ANTI_API_STREAM_UPSTREAM_MODE_BY_MODEL
+    })
+
+    test("defaults to streaming upstream", () => {
+        expect(resolveUpstreamMode(undefined)).toBe("stream")
+    })
+
+    test("header overrides the configured default", () => {
+        process.env.ANTI_API_STREAM_UPSTREAM_MODE = "buffered"
+        expect(resolveUpstreamMode(undefined)).toBe("buffered")
+        expect(resolveUpstreamMode("stream")).toBe("stream")
+        expect(resolveUpstreamMode("bogus")).toBe("buffered")
+    })
+
+    test("per-model mode sits between the header and the global default", () => {
+        process.env.ANTI_API_STREAM_UPSTREAM_MODE = "stream"
+        process.env.ANTI_API_STREAM_UPSTREAM_MODE_BY_MODEL = "claude-opus-*=buffered,claude-opus-4-5-fast=stream"
+        expect(resolveUpstreamMode(undefined, "claude-opus-4-5-thinking")).toBe("buffered")
+        expect(resolveUpstreamMode(undefined, "claude-opus-4-5-fast")).toBe("stream")
+        expect(resolveUpstreamMode(undefined, "gemini-3-flash")).toBe("stream")
+        expect(resolveUpstreamMode("stream", "claude-opus-4-5-thinking")).toBe("stream")
+    })
+})
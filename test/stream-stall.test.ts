@@ -0,0 +1,52 @@
+import { test, expect, describe, beforeEach, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { rateLimiter } from "../src/lib/rate-limiter"
+import { globalSemaphore, streamSemaphore } from "../src/lib/concurrency"
+import { handleCompletion } from "../src/routes/messages/handler"
+import { dataFrame, installFakeUpstream, sseResponse, type FakeUpstream } from "./fake-upstream"
+import { counterValue } from "./helpers"
+
+describe("upstream stalls mid-stream", () => {
+    const originalWait = rateLimiter.wait
+    let upstream: FakeUpstream | undefined
+
+    beforeEach(() => {
+        rateLimiter.wait = async () => { }
+    })
+
+    afterEach(() => {
+        rateLimiter.wait = originalWait
+        upstream?.restore()
+        upstream = undefined
+        delete process.env.ANTI_API_STREAM_IDLE_TIMEOUT_SECS
+    })
+
+    test("ends with a stream_idle_timeout frame and releases the stream and global permits", async () => {
+        process.env.ANTI_API_STREAM_IDLE_TIMEOUT_SECS = "1"
+        upstream = installFakeUpstream((_, init) => sseResponse([dataFrame("partial")], { signal: init.signal, stall: true }))
+        const app = new Hono()
+        app.post("/v1/messages", handleCompletion)
+        const globalBefore = globalSemaphore.inUse
+        const streamsBefore = streamSemaphore.inUse
+        const timeoutsBefore = counterValue("stream_idle_timeouts", { started: "true" })
+
+        const res = await app.request("/v1/messages", {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({ model: "claude-sonnet-4-6", max_tokens: 16, stream: true, messages: [{ role: "user", content: "hi" }] }),
+        })
+        expect(res.status).toBe(200)
+        const body = await res.text()
+
+        expect(body).toContain("partial")
+        const frames = body.trim().split("\n\n")
+        const last = frames[frames.length - 1]
+        expect(last.startsWith("event: error\n")).toBe(true)
+        expect(JSON.parse(last.split("data: ")[1]).error.type).toBe("stream_idle_timeout")
+        // 已输出内容后不再切换端点
+        expect(upstream.calls).toEqual(["a.test"])
+        expect(globalSemaphore.inUse).toBe(globalBefore)
+        expect(streamSemaphore.inUse).toBe(streamsBefore)
+        expect(counterValue("stream_idle_timeouts", { started: "true" })).toBe(timeoutsBefore + 1)
+    }, 10000)
+})
@@ -0,0 +1,41 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { Semaphore } from "../src/lib/semaphore"
+import { registerSessionRequest, resetSessionTracking, withSupersede } from "../src/lib/supersede"
+
+describe("session supersede", () => {
+    afterEach(() => resetSessionTracking())
+
+    test("a newer request cancels the queued one and a late permit is released", async () => {
+        const semaphore = new Semaphore(1)
+        const holder = await semaphore.acquire()
+
+        const older = registerSessionRequest("client:s1")
+        const olderWait = withSupersede(older, semaphore.acquire(), release => release())
+        const newer = registerSessionRequest("client:s1")
+
+        await expect(olderWait).rejects.toMatchObject({ code: "superseded", status: 409 })
+        holder()
+        const releaseNewer = await withSupersede(newer, semaphore.acquire(), release => release())
+        expect(semaphore.inUse).toBe(1)
+        releaseNewer()
+        await new Promise(resolve => setTimeout(resolve, 0))
+        expect(semaphore.inUse).toBe(0)
+    })
+
+    test("dispatched requests and other sessions are not superseded", async () => {
+        const dispatched = registerSessionRequest("client:s1")
+        dispatched.dispatch()
+        const other = registerSessionRequest("client:s2")
+        registerSessionRequest("client:s1")
+
+        let settled = false
+        dispatched.superseded.catch(() => { settled = true })
+        other.superseded.catch(() => { settled = true })
+        await new Promise(resolve => setTimeout(resolve, 0))
+        expect(settled).toBe(false)
+    })
+
+    test("no ticket means a plain wait", async () => {
+        expect(await withSupersede(null, Promise.resolve(7))).toBe(7)
+    })
+})
@@ -0,0 +1,26 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { assertTokenNotExpired, decodeJwtExp, isTokenExpired } from "../src/lib/token-exp"
+import { jwt } from "./helpers"
+
+describe("local token expiry check", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_CHECK_TOKEN_EXP
+    })
+
+    test("decodes exp from JWTs and skips opaque tokens", () => {
+        expect(decodeJwtExp(jwt({ exp: 1700000000 }))).toBe(1700000000)
+        expect(decodeJwtExp(jwt({ sub: "x" }))).toBeNull()
+        expect(decodeJwtExp("ya29.opaque-access-token")).toBeNull()
+        expect(isTokenExpired("ya29.opaque-access-token")).toBe(false)
+        expect(isTokenExpired(jwt({ exp: 1000 }), 2000 * 1000)).toBe(true)
+    })
+
+    test("fails fast with token_expired only when enabled", () => {
+        const headers = { Authorization: `Bearer ${jwt({ exp: Math.floor(Date.now() / 1000) - 60 })}` }
+        expect(() => assertTokenNotExpired(headers)).not.toThrow()
+
+        process.env.ANTI_API_CHECK_TOKEN_EXP = "1"
+        expect(() => assertTokenNotExpired(headers)).toThrow(/expired/)
+        expect(() => assertTokenNotExpired({ Authorization: `Bearer ${jwt({ exp: Math.floor(Date.now() / 1000) + 600 })}` })).not.toThrow()
+    })
+})
@@ -0,0 +1,33 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { assertTokenMatchesProject, extractTokenProjects } from "../src/lib/token-project"
+import { jwt } from "./helpers"
+
+describe("token project pre-check", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_CHECK_TOKEN_PROJECT
+    })
+
+    test("reads project claims and project audiences, skipping opaque tokens", () => {
+        expect(extractTokenProjects(jwt({ project_id: "alpha" }))).toEqual(["alpha"])
+        expect(extractTokenProjects(jwt({ aud: ["https://x.googleapis.com/projects/beta/locations/global"] }))).toEqual(["beta"])
+        expect(extractTokenProjects(jwt({ aud: "https://cloudcode-pa.googleapis.com/" }))).toBeNull()
+        expect(extractTokenProjects("ya29.opaque-access-token")).toBeNull()
+    })
+
+    test("rejects a mismatched project only when enabled", () => {
+        const token = jwt({ project_id: "alpha" })
+        expect(() => assertTokenMatchesProject(token, "beta")).not.toThrow()
+
+        process.env.ANTI_API_CHECK_TOKEN_PROJECT = "1"
+        let error: any
+        try {
+            assertTokenMatchesProject(token, "beta")
+        } catch (e) {
+            error = e
+        }
+        expect(error).toMatchObject({ code: "project_token_mismatch", status: 403 })
+        expect(() => assertTokenMatchesProject(token, "alpha")).not.toThrow()
+        expect(() => assertTokenMatchesProject("ya29.opaque", "beta")).not.toThrow()
+        expect(() => assertTokenMatchesProject(token, undefined)).not.toThrow()
+    })
+})
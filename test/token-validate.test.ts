@@ -0,0 +1,30 @@
+import { test, expect, describe, beforeEach, afterEach } from "bun:test"
+import { classifyTokenCheck, resetValidationSlots, tryConsumeValidationSlot } from "../src/services/antigravity/token-validate"
+
+describe("classifyTokenCheck", () => {
+    test("distinguishes expired, invalid and rate-limited tokens", () => {
+        expect(classifyTokenCheck(200, "{}")).toBe("valid")
+        expect(classifyTokenCheck(401, '{"error":{"message":"Request had invalid authentication credentials. ACCESS_TOKEN_EXPIRED"}}')).toBe("expired")
+        expect(classifyTokenCheck(401, '{"error":{"status":"UNAUTHENTICATED"}}')).toBe("invalid")
+        expect(classifyTokenCheck(403, "")).toBe("forbidden")
+        expect(classifyTokenCheck(429, "")).toBe("rate_limited")
+        expect(classifyTokenCheck(503, "")).toBe("error")
+    })
+})
+
+describe("tryConsumeValidationSlot", () => {
+    beforeEach(() => {
+        resetValidationSlots()
+        process.env.ANTI_API_VALIDATE_TOKEN_PER_MIN = "2"
+    })
+    afterEach(() => {
+        delete process.env.ANTI_API_VALIDATE_TOKEN_PER_MIN
+    })
+
+    test("limits checks per sliding minute", () => {
+        expect(tryConsumeValidationSlot(1_000)).toBe(true)
+        expect(tryConsumeValidationSlot(2_000)).toBe(true)
+        expect(tryConsumeValidationSlot(3_000)).toBe(false)
+        expect(tryConsumeValidationSlot(61_500)).toBe(true)
+    })
+})
@@ -0,0 +1,38 @@
+import { test, expect, describe } from "bun:test"
+import { buildOtlpPayload, formatTraceparent, parseTraceparent, startTrace } from "../src/lib/tracing"
+
+describe("tracing", () => {
+    test("continues an incoming traceparent", () => {
+        const incoming = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        const trace = startTrace(incoming)
+        expect(trace.traceId).toBe("4bf92f3577b34da6a3ce929d0e0e4736")
+        expect(trace.parentSpanId).toBe("00f067aa0ba902b7")
+        expect(trace.spanId).toMatch(/^[0-9a-f]{16}$/)
+        expect(formatTraceparent(trace)).toBe(`00-${trace.traceId}-${trace.spanId}-01`)
+    })
+
+    test("rejects malformed or all-zero traceparent values", () => {
+        expect(parseTraceparent("garbage")).toBeNull()
+        expect(parseTraceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01")).toBeNull()
+        expect(startTrace(undefined).parentSpanId).toBeUndefined()
+    })
+
+    test("builds OTLP JSON spans", () => {
+        const trace = startTrace(undefined)
+        const payload = buildOtlpPayload([{
+            trace,
+            name: "POST /v1/messages",
+            startMs: 1000,
+            endMs: 1500,
+            attributes: { "anti_api.model": "gemini-3-flash", "http.response.status_code": 200, skipped: undefined },
+            error: false,
+        }])
+        const span = payload.resourceSpans[0].scopeSpans[0].spans[0]
+        expect(span.traceId).toBe(trace.traceId)
+        expect(span.startTimeUnixNano).toBe("1000000000")
+        expect(span.attributes).toEqual([
+            { key: "anti_api.model", value: { stringValue: "gemini-3-flash" } },
+            { key: "http.response.status_code", value: { intValue: 200 } },
+        ])
+    })
+})
@@ -0,0 +1,14 @@
+import { test, expect, beforeEach } from "bun:test"
+import { getTrafficStats, recordTraffic, resetTrafficStats } from "../src/lib/traffic-stats"
+
+beforeEach(() => resetTrafficStats())
+
+test("accumulates request and response bytes per model", () => {
+    recordTraffic("claude-sonnet-4-5", 1200, 5000)
+    recordTraffic("claude-sonnet-4-5", 800, 3000)
+    recordTraffic("gemini-3-pro-high", 100, 50)
+
+    const stats = getTrafficStats()
+    expect(stats.models["claude-sonnet-4-5"]).toEqual({ requests: 2, request_bytes: 2000, response_bytes: 8000 })
+    expect(stats.total).toEqual({ requests: 3, request_bytes: 2100, response_bytes: 8050 })
+})
@@ -1,5 +1,6 @@
 import { test, expect } from "bun:test"
 import { mapModel, translateMessages, translateTools, mapStopReason } from "../src/routes/openai/translator"
+import { buildSseFromResponse } from "../src/lib/translator"
 
 test("mapModel returns normalized (lowercase) model name", () => {
     expect(mapModel("gpt-4")).toBe("gpt-4")
@@ -86,3 +87,20 @@ test("mapStopReason converts Anthropic reasons to OpenAI format", () => {
     expect(mapStopReason("max_tokens")).toBe("length")
     expect(mapStopReason("unknown")).toBe("stop")
 })
+
+test("buildSseFromResponse re-emits a buffered response as SSE events", () => {
+    const events = buildSseFromResponse("claude-sonnet-4-5", [
+        { type: "text", text: "Hello" },
+        { type: "tool_use", id: "toolu_1", name: "lookup", input: { q: "x" } },
+    ], "tool_use", { inputTokens: 12, outputTokens: 3 })
+
+    const types = events.map(event => JSON.parse(event.split("data: ")[1]).type)
+    expect(types).toEqual([
+        "message_start",
+        "content_block_start", "content_block_delta", "content_block_stop",
+        "content_block_start", "content_block_delta", "content_block_stop",
+        "message_delta", "message_stop",
+    ])
+    expect(JSON.parse(events[0].split("data: ")[1]).message.usage.input_tokens).toBe(12)
+    expect(JSON.parse(events[5].split("data: ")[1]).delta.partial_json).toBe('{"q":"x"}')
+})
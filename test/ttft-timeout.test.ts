@@ -0,0 +1,59 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { AntigravityError } from "../src/lib/error"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { createChatCompletionStreamWithOptions } from "../src/services/antigravity/chat"
+import { dataFrame, installFakeUpstream, sseResponse, type FakeUpstream } from "./fake-upstream"
+import { makeRequestContext } from "./helpers"
+
+const request = { model: "gemini-3-flash", messages: [{ role: "user" as const, content: "hi" }] }
+
+async function collect(stream: AsyncGenerator<string>): Promise<string[]> {
+    const events: string[] = []
+    for await (const event of stream) events.push(event)
+    return events
+}
+
+describe("ANTI_API_TTFT_TIMEOUT_MS", () => {
+    let upstream: FakeUpstream | undefined
+
+    afterEach(() => {
+        upstream?.restore()
+        upstream = undefined
+        delete process.env.ANTI_API_TTFT_TIMEOUT_MS
+    })
+
+    test("aborts a stream that sends only heartbeats within the window", async () => {
+        process.env.ANTI_API_TTFT_TIMEOUT_MS = "100"
+        upstream = installFakeUpstream((_, init) => sseResponse([": ping\n\n"], { signal: init.signal, stall: true }))
+
+        const startedAt = Date.now()
+        const error = await runWithRequestContext(makeRequestContext(), () => collect(createChatCompletionStreamWithOptions(request))).catch(e => e)
+        expect(error).toBeInstanceOf(AntigravityError)
+        expect(error.code).toBe("ttft_timeout")
+        expect(error.status).toBe(504)
+        expect(Date.now() - startedAt).toBeLessThan(2000)
+        expect(upstream.calls).toEqual(["a.test"])
+    })
+
+    test("leaves a stream alone once its first data frame arrives in time", async () => {
+        process.env.ANTI_API_TTFT_TIMEOUT_MS = "100"
+        const encoder = new TextEncoder()
+        upstream = installFakeUpstream(() => new Response(new ReadableStream<Uint8Array>({
+            start(controller) {
+                controller.enqueue(encoder.encode(dataFrame("first")))
+                // 第二帧晚于首帧超时到达，不应触发中止
+                setTimeout(() => {
+                    controller.enqueue(encoder.encode(dataFrame("second")))
+                    controller.close()
+                }, 300)
+            },
+        }), { status: 200, headers: { "Content-Type": "text/event-stream" } }))
+
+        const events = await runWithRequestContext(makeRequestContext(), () => collect(createChatCompletionStreamWithOptions(request)))
+        const text = events.join("")
+        expect(text).toContain("first")
+        expect(text).toContain("second")
+        expect(text).not.toContain("ttft_timeout")
+        expect(upstream.calls).toEqual(["a.test"])
+    })
+})
@@ -0,0 +1,55 @@
+import { test, expect, describe, afterEach, beforeEach } from "bun:test"
+import { AntigravityError } from "../src/lib/error"
+import { retryBudget } from "../src/lib/retry"
+import { runWithRequestContext } from "../src/lib/request-context"
+import { createChatCompletionStreamWithOptions } from "../src/services/antigravity/chat"
+import { createRoutedCompletion } from "../src/services/routing/router"
+import { installFakeUpstream, statusResponse, type FakeUpstream } from "./fake-upstream"
+import { makeRequestContext } from "./helpers"
+
+const messages = [{ role: "user" as const, content: "hi" }]
+
+async function drain(stream: AsyncGenerator<string>): Promise<void> {
+    for await (const _ of stream) { }
+}
+
+describe("ANTI_API_MAX_UPSTREAM_CALLS", () => {
+    let upstream: FakeUpstream | undefined
+
+    beforeEach(() => retryBudget.reset())
+
+    afterEach(() => {
+        upstream?.restore()
+        upstream = undefined
+        delete process.env.ANTI_API_MAX_UPSTREAM_CALLS
+        delete process.env.ANTI_API_MODEL_FALLBACKS
+    })
+
+    test("failover and retry passes share one per-request total", async () => {
+        process.env.ANTI_API_MAX_UPSTREAM_CALLS = "4"
+        upstream = installFakeUpstream(() => statusResponse(404, "not here"))
+
+        const error = await runWithRequestContext(makeRequestContext(), () => drain(createChatCompletionStreamWithOptions({ model: "gemini-3-flash", messages }))).catch(e => e)
+        expect(error).toBeInstanceOf(AntigravityError)
+        expect(error.code).toBe("upstream_call_budget_exceeded")
+        expect(error.status).toBe(503)
+        // 第一轮 a、b 切换，第二轮重试 a、b，第三轮在发出前被拒绝
+        expect(upstream.calls).toEqual(["a.test", "b.test", "a.test", "b.test"])
+    })
+
+    test("model fallback calls count toward the same total", async () => {
+        process.env.ANTI_API_MAX_UPSTREAM_CALLS = "2"
+        process.env.ANTI_API_MODEL_FALLBACKS = "claude-sonnet-4-6=gemini-3-flash"
+        let call = 0
+        upstream = installFakeUpstream(() => ++call === 1
+            ? statusResponse(400, JSON.stringify({ error: { code: 400, message: "Requested model not found", status: "NOT_FOUND" } }))
+            : statusResponse(404, "not here"))
+
+        const error = await runWithRequestContext(makeRequestContext(), () => createRoutedCompletion({ model: "claude-sonnet-4-6", messages })).catch(e => e)
+        expect(error).toBeInstanceOf(AntigravityError)
+        expect(error.code).toBe("upstream_call_budget_exceeded")
+        expect(error.status).toBe(503)
+        // 原模型 1 次 + 回退模型 1 次后达到上限，回退模型的第二个端点不再调用
+        expect(upstream.calls).toEqual(["a.test", "a.test"])
+    })
+})
@@ -0,0 +1,22 @@
+import { test, expect, afterEach } from "bun:test"
+import { resetPooledConnectionTracking, shouldReusePooledConnection } from "../src/lib/upstream-pool"
+
+afterEach(() => {
+    resetPooledConnectionTracking()
+    delete process.env.ANTI_API_POOL_IDLE_REUSE_SECS
+})
+
+test("opts out of the pool after the endpoint has been idle too long", () => {
+    process.env.ANTI_API_POOL_IDLE_REUSE_SECS = "10"
+    expect(shouldReusePooledConnection("https://a", 0)).toBe(true)
+    expect(shouldReusePooledConnection("https://a", 5000)).toBe(true)
+    expect(shouldReusePooledConnection("https://a", 20000)).toBe(false)
+    expect(shouldReusePooledConnection("https://a", 21000)).toBe(true)
+    expect(shouldReusePooledConnection("https://b", 21000)).toBe(true)
+})
+
+test("0 always reuses pooled connections", () => {
+    process.env.ANTI_API_POOL_IDLE_REUSE_SECS = "0"
+    shouldReusePooledConnection("https://a", 0)
+    expect(shouldReusePooledConnection("https://a", 3600_000)).toBe(true)
+})
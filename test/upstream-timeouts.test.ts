@@ -0,0 +1,55 @@
+import { test, expect, describe, afterEach } from "bun:test"
+import { getEndpointTimeoutMs, getModelTimeoutMs, parseEndpointTimeouts, parseModelTimeouts } from "../src/lib/upstream-timeouts"
+
+describe("per-endpoint upstream timeouts", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_UPSTREAM_TIMEOUT_MS
+        delete process.env.ANTI_API_ENDPOINT_TIMEOUTS_MS
+    })
+
+    test("unlisted endpoints use the global timeout", () => {
+        expect(getEndpointTimeoutMs("https://a.example.com", 0)).toBe(30000)
+        process.env.ANTI_API_UPSTREAM_TIMEOUT_MS = "45000"
+        expect(getEndpointTimeoutMs("https://a.example.com", 0)).toBe(45000)
+    })
+
+    test("URL overrides win over index overrides", () => {
+        process.env.ANTI_API_ENDPOINT_TIMEOUTS_MS = "0=8000,https://B.example.com/=60000"
+        expect(getEndpointTimeoutMs("https://a.example.com", 0)).toBe(8000)
+        expect(getEndpointTimeoutMs("https://b.example.com", 0)).toBe(60000)
+        expect(getEndpointTimeoutMs("https://c.example.com", 2)).toBe(30000)
+    })
+
+    test("malformed entries are rejected", () => {
+        expect(() => parseEndpointTimeouts("0=fast")).toThrow(/ANTI_API_ENDPOINT_TIMEOUTS_MS/)
+        expect(() => parseEndpointTimeouts("=5000")).toThrow()
+        expect(parseEndpointTimeouts("").size).toBe(0)
+    })
+})
+
+describe("per-model upstream timeouts", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_MODEL_TIMEOUTS_MS
+        delete process.env.ANTI_API_ENDPOINT_TIMEOUTS_MS
+    })
+
+    test("exact match beats the longest wildcard prefix", () => {
+        process.env.ANTI_API_MODEL_TIMEOUTS_MS = "gemini-*=20000,gemini-3-*=40000,gemini-3-flash=10000"
+        expect(getModelTimeoutMs("gemini-3-flash")).toBe(10000)
+        expect(getModelTimeoutMs("Gemini-3-pro-high")).toBe(40000)
+        expect(getModelTimeoutMs("gemini-2.5-flash")).toBe(20000)
+        expect(getModelTimeoutMs("claude-sonnet-4-5")).toBeUndefined()
+    })
+
+    test("endpoint overrides win, then model, then the global default", () => {
+        process.env.ANTI_API_MODEL_TIMEOUTS_MS = "claude-*=90000"
+        expect(getEndpointTimeoutMs("https://a.example.com", 0, "claude-opus-4-5")).toBe(90000)
+        expect(getEndpointTimeoutMs("https://a.example.com", 0, "gemini-3-flash")).toBe(30000)
+        process.env.ANTI_API_ENDPOINT_TIMEOUTS_MS = "0=5000"
+        expect(getEndpointTimeoutMs("https://a.example.com", 0, "claude-opus-4-5")).toBe(5000)
+    })
+
+    test("malformed model entries are rejected", () => {
+        expect(() => parseModelTimeouts("claude-*=slow")).toThrow(/ANTI_API_MODEL_TIMEOUTS_MS/)
+    })
+})
@@ -0,0 +1,45 @@
+import { test, expect, describe, beforeEach, afterEach } from "bun:test"
+import { Hono } from "hono"
+import { rateLimiter } from "../src/lib/rate-limiter"
+import { globalSemaphore } from "../src/lib/concurrency"
+import { handleCompletion } from "../src/routes/messages/handler"
+import { handleChatCompletion } from "../src/routes/openai/handler"
+
+describe("validation runs before rate limiting", () => {
+    const originalWait = rateLimiter.wait
+    let waits = 0
+
+    beforeEach(() => {
+        waits = 0
+        rateLimiter.wait = async () => {
+            waits++
+        }
+    })
+
+    afterEach(() => {
+        rateLimiter.wait = originalWait
+    })
+
+    function createApp() {
+        const app = new Hono()
+        app.post("/v1/messages", handleCompletion)
+        app.post("/v1/chat/completions", handleChatCompletion)
+        return app
+    }
+
+    function post(app: Hono, path: string, body: unknown) {
+        return app.request(path, { method: "POST", headers: { "Content-Type": "application/json" }, body: JSON.stringify(body) })
+    }
+
+    test("malformed and unroutable requests reject without touching the limiter or permits", async () => {
+        const app = createApp()
+        const responses = await Promise.all([
+            post(app, "/v1/messages", { model: "claude-sonnet-4-5", max_tokens: 10 }),
+            post(app, "/v1/messages", { model: "not-a-real-model", max_tokens: 10, messages: [{ role: "user", content: "hi" }] }),
+            post(app, "/v1/chat/completions", { model: "not-a-real-model", messages: [{ role: "user", content: "hi" }] }),
+        ])
+        expect(responses.map(res => res.status)).toEqual([400, 400, 400])
+        expect(waits).toBe(0)
+        expect(globalSemaphore.inUse).toBe(0)
+    })
+})
@@ -1,5 +1,5 @@
-import { test, expect } from "bun:test"
-import { validateChatRequest, validateAnthropicRequest, validateAccountId, sanitizeString } from "../src/lib/validation"
+import { test, expect, describe, afterEach } from "bun:test"
+import { validateChatRequest, validateAnthropicRequest, validateAccountId, sanitizeString, validateAccessToken } from "../src/lib/validation"
 
 test("validateChatRequest accepts valid request", () => {
     const result = validateChatRequest({
@@ -94,3 +94,101 @@ test("sanitizeString handles non-strings", () => {
     expect(sanitizeString(123 as any)).toBe("")
     expect(sanitizeString(null as any)).toBe("")
 })
+
+test("validateChatRequest tolerates surrounding whitespace in model", () => {
+    const result = validateChatRequest({
+        model: "  Claude-Sonnet-4-5 ",
+        messages: [{ role: "user", content: "Hello" }]
+    })
+    expect(result.valid).toBe(true)
+})
+
+test("validateChatRequest rejects model with invalid characters", () => {
+    const result = validateChatRequest({
+        model: "claude sonnet\n4",
+        messages: [{ role: "user", content: "Hello" }]
+    })
+    expect(result.valid).toBe(false)
+    expect(result.error).toContain("invalid characters")
+})
+
+test("validateAnthropicRequest rejects model with invalid characters", () => {
+    const result = validateAnthropicRequest({
+        model: "claude<script>",
+        messages: [{ role: "user", content: "Hello" }]
+    })
+    expect(result.valid).toBe(false)
+    expect(result.error).toContain("invalid characters")
+})
+
+test("validateAccessToken accepts a normal token", () => {
+    expect(validateAccessToken("ya29.a0AfH6SMBx-abc_123").valid).toBe(true)
+})
+
+test("validateAccessToken rejects oversized token", () => {
+    const result = validateAccessToken("a".repeat(10000))
+    expect(result.valid).toBe(false)
+    expect(result.error).toContain("too long")
+})
+
+test("validateAccessToken respects ANTI_API_MAX_TOKEN_LEN", () => {
+    process.env.ANTI_API_MAX_TOKEN_LEN = "16"
+    try {
+        expect(validateAccessToken("a".repeat(17)).valid).toBe(false)
+        expect(validateAccessToken("a".repeat(16)).valid).toBe(true)
+    } finally {
+        delete process.env.ANTI_API_MAX_TOKEN_LEN
+    }
+})
+
+test("validateAccessToken rejects newlines and control characters", () => {
+    expect(validateAccessToken("abc\r\nX-Injected: 1").valid).toBe(false)
+    expect(validateAccessToken("abc\u0000def").valid).toBe(false)
+    expect(validateAccessToken("abc def").valid).toBe(false)
+})
+
+test("validateAccessToken rejects empty and non-string tokens", () => {
+    expect(validateAccessToken("").valid).toBe(false)
+    expect(validateAccessToken(undefined).valid).toBe(false)
+})
+
+test("extra_query is rejected unless enabled, then validated", () => {
+    const payload = { model: "gemini-3-flash", messages: [{ role: "user", content: "hi" }], extra_query: { feature: "on" } }
+    expect(validateAnthropicRequest(payload).valid).toBe(false)
+
+    process.env.ANTI_API_ALLOW_REQUEST_EXTRA_QUERY = "1"
+    try {
+        expect(validateAnthropicRequest(payload).valid).toBe(true)
+        expect(validateChatRequest({ ...payload, extra_query: { alt: "json" } }).valid).toBe(false)
+        expect(validateChatRequest({ ...payload, extra_query: { "bad name": "x" } }).valid).toBe(false)
+        expect(validateChatRequest({ ...payload, extra_query: { n: 1 } }).valid).toBe(false)
+    } finally {
+        delete process.env.ANTI_API_ALLOW_REQUEST_EXTRA_QUERY
+    }
+})
+
+describe("ANTI_API_DEFAULT_MODEL", () => {
+    afterEach(() => {
+        delete process.env.ANTI_API_DEFAULT_MODEL
+    })
+
+    test("keeps a supplied model", () => {
+        process.env.ANTI_API_DEFAULT_MODEL = "gemini-3-pro-high"
+        const payload: any = { model: "claude-sonnet-4-5", max_tokens: 10, messages: [{ role: "user", content: "Hi" }] }
+        expect(validateAnthropicRequest(payload).valid).toBe(true)
+        expect(payload.model).toBe("claude-sonnet-4-5")
+    })
+
+    test("fills in the default when model is omitted", () => {
+        process.env.ANTI_API_DEFAULT_MODEL = "gemini-3-pro-high"
+        const payload: any = { messages: [{ role: "user", content: "Hi" }] }
+        expect(validateChatRequest(payload).valid).toBe(true)
+        expect(payload.model).toBe("gemini-3-pro-high")
+    })
+
+    test("rejects when neither model nor default is set", () => {
+        const result = validateChatRequest({ messages: [{ role: "user", content: "Hi" }] })
+        expect(result.valid).toBe(false)
+        expect(result.error).toContain("Model")
+    })
+})